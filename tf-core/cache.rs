@@ -0,0 +1,458 @@
+use crate::{
+    input::with_path,
+    store::{Encoding, Table},
+    FrequencyTable, Input, Mapping, Ngrams, Result, Stemming, StopWords, Tokenizer,
+};
+use std::{
+    fmt, fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, SystemTime},
+};
+
+/// Directory to use when `--cache` is not given.
+pub const ENV_VAR: &str = "TF_CACHE";
+
+/// Part of every key: bumped when what is stored changes, so that older
+/// entries are never read.
+const VERSION: &str = "tf-cache 1";
+/// Entries are binary [`Table`]s.
+const EXTENSION: &str = "tftb";
+/// Hits and misses so far, one `name count` line each.
+const STATS_FILE: &str = "stats";
+
+/// FNV-1a on 128 bits: no dependency, and no collision in practice.
+struct Fnv(u128);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Fnv(0x6c62_272e_07bb_0142_62b8_2175_6295_c58d)
+    }
+}
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= u128::from(b);
+            self.0 = self
+                .0
+                .wrapping_mul(0x0000_0000_0100_0000_0000_0000_0000_013b);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0 as u64
+    }
+}
+
+/// What the counts of some inputs depend on: the text read and every option
+/// that changes which words are counted. A change to either is a new key, so
+/// stale entries are never read, only left for [`Cache::prune`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key(u128);
+
+impl Key {
+    /// The key of counting `sources` one after the other. Reads them.
+    pub fn new(
+        sources: &[Input],
+        stop_words: &StopWords,
+        tokenizer: &Tokenizer,
+        mapping: &Mapping,
+        ngrams: Ngrams,
+        stemming: Stemming,
+    ) -> Result<Self> {
+        let mut hasher = Fnv::default();
+        VERSION.hash(&mut hasher);
+        stop_words.hash(&mut hasher);
+        tokenizer.hash(&mut hasher);
+        mapping.hash(&mut hasher);
+        ngrams.hash(&mut hasher);
+        stemming.hash(&mut hasher);
+        sources.len().hash(&mut hasher);
+        for source in sources {
+            // as counted, i.e. without the Project Gutenberg boilerplate
+            source.read_to_string()?.hash(&mut hasher);
+        }
+        Ok(Key(hasher.0))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+/// A directory of counts from earlier runs, a file per [`Key`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// What is in a cache, and how useful it was.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub entries: usize,
+    /// Size of the entries on disk.
+    pub bytes: u64,
+    /// Runs that found their counts in the cache, since it was created or
+    /// cleared.
+    pub hits: usize,
+    /// Runs that counted, then stored their counts.
+    pub misses: usize,
+    /// When the least recently used entry was last used.
+    pub oldest: Option<SystemTime>,
+    pub newest: Option<SystemTime>,
+}
+
+impl Stats {
+    /// Hits out of all lookups, 0 before the first one.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            n => self.hits as f64 / n as f64,
+        }
+    }
+}
+
+/// What [`Cache::prune`] removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pruned {
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+/// An entry on disk.
+#[derive(Debug)]
+struct Entry {
+    path: PathBuf,
+    bytes: u64,
+    used: SystemTime,
+}
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: Key) -> PathBuf {
+        self.dir.join(format!("{}.{}", key, EXTENSION))
+    }
+
+    /// The counts stored under `key`, if any. An entry that can't be read is
+    /// a miss, and is written again. Using an entry touches it, so that
+    /// [`Cache::prune`] removes the least recently used first.
+    pub fn get(&self, key: Key) -> Option<FrequencyTable> {
+        let path = self.path(key);
+        let table = Table::decode(&fs::read(&path).ok()?).ok()?;
+        if let Ok(file) = fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(table.counts())
+    }
+
+    /// Stores `counts` under `key`, creating the directory if needed.
+    pub fn put(&self, key: Key, counts: &FrequencyTable) -> Result<()> {
+        fs::create_dir_all(&self.dir).map_err(|e| with_path(&self.dir, e))?;
+        let path = self.path(key);
+        // written aside, then renamed, so that a concurrent run never reads
+        // half an entry
+        let partial = self
+            .dir
+            .join(format!(".{}.{}.{}", key, process::id(), EXTENSION));
+        fs::write(
+            &partial,
+            Table::from_counts(counts).encode(Encoding::Binary),
+        )
+        .and_then(|()| fs::rename(&partial, &path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&partial);
+            with_path(&path, e)
+        })
+    }
+
+    /// The counts of `key` from the cache, or from `count`, then stored.
+    pub fn get_or_count(
+        &self,
+        key: Key,
+        count: impl FnOnce() -> Result<FrequencyTable>,
+    ) -> Result<FrequencyTable> {
+        if let Some(counts) = self.get(key) {
+            self.record(true);
+            return Ok(counts);
+        }
+        let counts = count()?;
+        self.put(key, &counts)?;
+        self.record(false);
+        Ok(counts)
+    }
+
+    /// Adds a hit or a miss to the stats. Best effort: the stats of runs at
+    /// the same time may be lost, never the counts.
+    fn record(&self, hit: bool) {
+        let (mut hits, mut misses) = self.read_stats();
+        if hit {
+            hits += 1;
+        } else {
+            misses += 1;
+        }
+        let _ = fs::write(
+            self.dir.join(STATS_FILE),
+            format!("hits {}\nmisses {}\n", hits, misses),
+        );
+    }
+
+    fn read_stats(&self) -> (usize, usize) {
+        let text = fs::read_to_string(self.dir.join(STATS_FILE)).unwrap_or_default();
+        let mut ret = (0, 0);
+        for line in text.lines() {
+            match line.split_once(' ') {
+                Some(("hits", n)) => ret.0 = n.parse().unwrap_or(0),
+                Some(("misses", n)) => ret.1 = n.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+        ret
+    }
+
+    /// The entries, least recently used first. A cache that was never
+    /// written to is empty.
+    fn entries(&self) -> Result<Vec<Entry>> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(with_path(&self.dir, e)),
+        };
+        let mut ret = Vec::new();
+        for entry in dir {
+            let path = entry.map_err(|e| with_path(&self.dir, e))?.path();
+            let is_entry = path.extension().is_some_and(|e| e == EXTENSION)
+                && !path
+                    .file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+            if !is_entry {
+                continue;
+            }
+            let metadata = fs::metadata(&path).map_err(|e| with_path(&path, e))?;
+            ret.push(Entry {
+                bytes: metadata.len(),
+                used: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                path,
+            });
+        }
+        ret.sort_by_key(|e| e.used);
+        Ok(ret)
+    }
+
+    pub fn stats(&self) -> Result<Stats> {
+        let entries = self.entries()?;
+        let (hits, misses) = self.read_stats();
+        Ok(Stats {
+            entries: entries.len(),
+            bytes: entries.iter().map(|e| e.bytes).sum(),
+            hits,
+            misses,
+            oldest: entries.first().map(|e| e.used),
+            newest: entries.last().map(|e| e.used),
+        })
+    }
+
+    /// Removes the entries not used for longer than `max_age`, then the
+    /// least recently used ones until the rest fit in `max_bytes`.
+    pub fn prune(&self, max_age: Option<Duration>, max_bytes: Option<u64>) -> Result<Pruned> {
+        let entries = self.entries()?;
+        let now = SystemTime::now();
+        let mut kept: u64 = entries.iter().map(|e| e.bytes).sum();
+        let mut ret = Pruned::default();
+        for entry in entries {
+            let age = now.duration_since(entry.used).unwrap_or_default();
+            let too_old = max_age.is_some_and(|max| age > max);
+            let too_big = max_bytes.is_some_and(|max| kept > max);
+            if !too_old && !too_big {
+                continue;
+            }
+            fs::remove_file(&entry.path).map_err(|e| with_path(&entry.path, e))?;
+            kept -= entry.bytes;
+            ret.entries += 1;
+            ret.bytes += entry.bytes;
+        }
+        Ok(ret)
+    }
+
+    /// Removes every entry and the stats.
+    pub fn clear(&self) -> Result<Pruned> {
+        let ret = self.prune(None, Some(0))?;
+        match fs::remove_file(self.dir.join(STATS_FILE)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                Err(with_path(self.dir.join(STATS_FILE), e))
+            }
+            _ => Ok(ret),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mapping::CharMap, tokenizer::Segmentation};
+
+    /// A directory of its own for each test, emptied first.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tf-cache-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn input(dir: &Path, text: &str) -> Input {
+        let path = dir.join("input.txt");
+        fs::write(&path, text).unwrap();
+        Input::File(path)
+    }
+
+    fn key(
+        sources: &[Input],
+        stop_words: &StopWords,
+        tokenizer: &Tokenizer,
+        stemming: Stemming,
+    ) -> Key {
+        Key::new(
+            sources,
+            stop_words,
+            tokenizer,
+            &Mapping::default(),
+            Ngrams::default(),
+            stemming,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn key_changes_with_the_stop_words() {
+        let dir = temp_dir("stop-words");
+        let sources = [input(&dir, "it is a truth universally acknowledged")];
+        let tokenizer = Tokenizer::default();
+        let base = key(
+            &sources,
+            &StopWords::parse("a,is"),
+            &tokenizer,
+            Stemming::Off,
+        );
+        // the same words in another order are the same list
+        assert_eq!(
+            key(
+                &sources,
+                &StopWords::parse("is,a"),
+                &tokenizer,
+                Stemming::Off
+            ),
+            base
+        );
+        assert_ne!(
+            key(
+                &sources,
+                &StopWords::parse("a,is,it"),
+                &tokenizer,
+                Stemming::Off
+            ),
+            base
+        );
+        assert_ne!(
+            key(&sources, &StopWords::default(), &tokenizer, Stemming::Off),
+            base
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn key_changes_with_the_tokenizer_and_stemming() {
+        let dir = temp_dir("tokenizer");
+        let sources = [input(&dir, "it is a truth universally acknowledged")];
+        let stop_words = StopWords::builtin();
+        let base = key(&sources, &stop_words, &Tokenizer::default(), Stemming::Off);
+        assert_eq!(
+            key(&sources, &stop_words, &Tokenizer::default(), Stemming::Off),
+            base
+        );
+        for tokenizer in [
+            Tokenizer::default().with_min_len(3),
+            Tokenizer::default().with_segmentation(Segmentation::Unicode),
+        ] {
+            assert_ne!(
+                key(&sources, &stop_words, &tokenizer, Stemming::Off),
+                base,
+                "{:?}",
+                tokenizer
+            );
+        }
+        assert_ne!(
+            key(
+                &sources,
+                &stop_words,
+                &Tokenizer::default(),
+                Stemming::Stems
+            ),
+            base
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn key_changes_with_the_text_mapping_and_ngrams() {
+        let dir = temp_dir("text");
+        let stop_words = StopWords::builtin();
+        let tokenizer = Tokenizer::default();
+        let with = |text: &str, mapping: &Mapping, ngrams: Ngrams| {
+            let sources = [input(&dir, text)];
+            Key::new(
+                &sources,
+                &stop_words,
+                &tokenizer,
+                mapping,
+                ngrams,
+                Stemming::Off,
+            )
+            .unwrap()
+        };
+        let (mapping, ngrams) = (Mapping::default(), Ngrams::default());
+        let base = with("it is a truth", &mapping, ngrams);
+        assert_eq!(with("it is a truth", &mapping, ngrams), base);
+        assert_ne!(with("it is a truth!", &mapping, ngrams), base);
+        let leet = Mapping::new(vec![CharMap::leet()], Default::default());
+        assert_ne!(with("it is a truth", &leet, ngrams), base);
+        let bigrams = Ngrams { n: 2, ..ngrams };
+        assert_ne!(with("it is a truth", &mapping, bigrams), base);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn counts_once_then_reads_back() {
+        let dir = temp_dir("get-or-count");
+        let cache = Cache::new(dir.join("cache"));
+        let sources = [input(&dir, "pride and prejudice and pride")];
+        let key = key(
+            &sources,
+            &StopWords::default(),
+            &Tokenizer::default(),
+            Stemming::Off,
+        );
+        let counts: FrequencyTable = ["pride", "and", "prejudice", "and", "pride"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        let first = cache.get_or_count(key, || Ok(counts.clone())).unwrap();
+        let second = cache.get_or_count(key, || panic!("counted twice")).unwrap();
+        assert_eq!(Table::from_counts(&first), Table::from_counts(&second));
+        let stats = cache.stats().unwrap();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (1, 1, 1));
+        assert_eq!(cache.clear().unwrap().entries, 1);
+        assert_eq!(cache.stats().unwrap().entries, 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,210 @@
+use std::{env, fmt::Write};
+
+/// How bar lengths follow the counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scale {
+    #[default]
+    Linear,
+    /// Proportional to the logarithm of the count, so that the bars of rare
+    /// words are still visible next to the most frequent ones.
+    Log,
+}
+
+/// The options of [`crate::Format::Chart`]: a horizontal bar per word,
+/// filling the width of the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Chart {
+    pub scale: Scale,
+    /// Draw with `#` rather than Unicode blocks. Also the case when the
+    /// locale is not UTF-8.
+    pub ascii: bool,
+    /// Columns to fill, `None` for the width of the terminal.
+    pub width: Option<usize>,
+    /// Add a histogram of word lengths under the bars.
+    pub length_histogram: bool,
+}
+
+/// When neither the terminal nor `COLUMNS` tell.
+const DEFAULT_WIDTH: usize = 80;
+
+/// Bars stay this long however long the labels, unless the width is
+/// narrower still.
+const MIN_BAR_WIDTH: usize = 10;
+
+/// A full block and its eighths, from the left.
+const FULL_BLOCK: char = '█';
+const PARTIAL_BLOCKS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// One row of a chart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Bar {
+    pub label: String,
+    pub value: usize,
+    /// Printed after the bar, usually the value.
+    pub note: String,
+}
+
+impl Chart {
+    /// The width to fill, in columns.
+    pub fn columns(&self) -> usize {
+        self.width
+            .or_else(|| terminal_size::terminal_size().map(|(w, _h)| w.0 as usize))
+            .or_else(|| env::var("COLUMNS").ok()?.parse().ok())
+            .unwrap_or(DEFAULT_WIDTH)
+    }
+
+    /// Whether bars are drawn with Unicode blocks.
+    pub fn unicode(&self) -> bool {
+        !self.ascii && locale_is_utf8()
+    }
+
+    /// Labels on the left, padded to the longest one, then the bars, scaled
+    /// so that the longest one fills the rest of the line.
+    pub(crate) fn write_bars(&self, out: &mut String, bars: &[Bar]) -> std::fmt::Result {
+        let label_width = bars
+            .iter()
+            .map(|b| b.label.chars().count())
+            .max()
+            .unwrap_or(0);
+        let note_width = bars
+            .iter()
+            .map(|b| b.note.chars().count())
+            .max()
+            .unwrap_or(0);
+        let columns = self.columns();
+        let bar_width = columns
+            .saturating_sub(label_width + note_width + 2)
+            .max(MIN_BAR_WIDTH)
+            .min(columns);
+        let max = bars.iter().map(|b| b.value).max().unwrap_or(0);
+        let unicode = self.unicode();
+        for bar in bars {
+            let eighths = self.eighths(bar.value, max, bar_width);
+            writeln!(
+                out,
+                "{:<width$} {} {}",
+                bar.label,
+                draw(eighths, unicode),
+                bar.note,
+                width = label_width
+            )?;
+        }
+        Ok(())
+    }
+
+    /// How long the bar of `value` is, in eighths of a column, when the one
+    /// of `max` is `width` columns long.
+    fn eighths(&self, value: usize, max: usize, width: usize) -> usize {
+        if max == 0 {
+            return 0;
+        }
+        let share = match self.scale {
+            Scale::Linear => value as f64 / max as f64,
+            Scale::Log => (value as f64).ln_1p() / (max as f64).ln_1p(),
+        };
+        (share * (width * 8) as f64).round() as usize
+    }
+}
+
+/// Unicode bars are precise to an eighth of a column, ASCII ones to a
+/// column.
+fn draw(eighths: usize, unicode: bool) -> String {
+    if !unicode {
+        return "#".repeat((eighths + 4) / 8);
+    }
+    let mut ret: String = std::iter::repeat_n(FULL_BLOCK, eighths / 8).collect();
+    match eighths % 8 {
+        0 => {}
+        rest => ret.push(PARTIAL_BLOCKS[rest - 1]),
+    }
+    ret
+}
+
+/// As the C library decides it: the first of `LC_ALL`, `LC_CTYPE` and `LANG`
+/// that is set.
+fn locale_is_utf8() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .map(|value| {
+            let value = value.to_ascii_lowercase();
+            value.contains("utf-8") || value.contains("utf8")
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bars(values: &[usize]) -> Vec<Bar> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| Bar {
+                label: format!("w{}", i),
+                value,
+                note: value.to_string(),
+            })
+            .collect()
+    }
+
+    fn chart(width: usize, scale: Scale) -> Chart {
+        Chart {
+            scale,
+            ascii: true,
+            width: Some(width),
+            length_histogram: false,
+        }
+    }
+
+    fn lines(chart: Chart, values: &[usize]) -> Vec<String> {
+        let mut out = String::new();
+        chart.write_bars(&mut out, &bars(values)).unwrap();
+        out.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn the_longest_bar_fills_the_width() {
+        // "w0 " + bar + " 10"
+        assert_eq!(
+            lines(chart(30, Scale::Linear), &[10, 5, 0]),
+            [
+                format!("w0 {} 10", "#".repeat(24)),
+                format!("w1 {} 5", "#".repeat(12)),
+                "w2  0".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn bars_never_go_past_the_width() {
+        for line in lines(chart(5, Scale::Linear), &[10, 5]) {
+            assert!(line.matches('#').count() <= 5, "{}", line);
+        }
+        // labels alone leave no room: the bars keep a minimum length
+        assert_eq!(
+            lines(chart(12, Scale::Linear), &[10])[0]
+                .matches('#')
+                .count(),
+            10
+        );
+    }
+
+    #[test]
+    fn log_scale_keeps_small_counts_visible() {
+        let linear = lines(chart(40, Scale::Linear), &[1000, 1]);
+        let log = lines(chart(40, Scale::Log), &[1000, 1]);
+        assert_eq!(linear[1].matches('#').count(), 0);
+        assert!(log[1].matches('#').count() > 0);
+    }
+
+    #[test]
+    fn unicode_bars_are_precise_to_an_eighth() {
+        assert_eq!(draw(8 * 2 + 3, true), "██▍");
+        assert_eq!(draw(8 * 2 + 3, false), "##");
+        assert_eq!(draw(8 * 2 + 4, false), "###");
+        assert_eq!(draw(0, true), "");
+    }
+}
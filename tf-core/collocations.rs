@@ -0,0 +1,259 @@
+use crate::{keyness::x_ln_x_over, Error, Result};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, VecDeque},
+    str::FromStr,
+};
+
+/// How collocations are ranked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Measure {
+    /// Pointwise mutual information: how much more often the words appear
+    /// together than by chance. Favours rare pairs.
+    Pmi,
+    /// How confident it is that the pair is no chance. Favours frequent
+    /// pairs.
+    TScore,
+    /// The harmonic mean of how often each word comes with the other, on a
+    /// scale up to 14. Does not depend on the size of the corpus.
+    LogDice,
+    /// Dunning's log-likelihood, G².
+    #[default]
+    LogLikelihood,
+}
+
+impl FromStr for Measure {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pmi" => Ok(Measure::Pmi),
+            "t-score" => Ok(Measure::TScore),
+            "log-dice" => Ok(Measure::LogDice),
+            "log-likelihood" | "g2" => Ok(Measure::LogLikelihood),
+            _ => Err(Error::Invalid(format!(
+                "unknown measure `{}`, expected pmi, t-score, log-dice or log-likelihood",
+                s
+            ))),
+        }
+    }
+}
+
+/// A pair of words and how strongly they go together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collocation {
+    pub first: String,
+    pub second: String,
+    /// How often `second` came within the window after `first`.
+    pub count: usize,
+    /// How many pairs start with `first`.
+    pub first_count: usize,
+    /// How many pairs end with `second`.
+    pub second_count: usize,
+    pub pmi: f64,
+    pub t_score: f64,
+    pub log_dice: f64,
+    pub log_likelihood: f64,
+}
+
+impl Collocation {
+    /// The value of `measure`, larger is stronger.
+    pub fn score(&self, measure: Measure) -> f64 {
+        match measure {
+            Measure::Pmi => self.pmi,
+            Measure::TScore => self.t_score,
+            Measure::LogDice => self.log_dice,
+            Measure::LogLikelihood => self.log_likelihood,
+        }
+    }
+}
+
+/// Counts the pairs of words that come within `window` words of each other,
+/// in order: with a window of 1 only neighbours, with 2 also the words one
+/// word apart...
+#[derive(Debug, Clone)]
+pub struct Cooccurrences {
+    window: usize,
+    /// The last `window` words.
+    recent: VecDeque<String>,
+    pairs: HashMap<(String, String), usize>,
+    firsts: HashMap<String, usize>,
+    seconds: HashMap<String, usize>,
+    total: usize,
+}
+
+impl Cooccurrences {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            recent: VecDeque::with_capacity(window),
+            pairs: HashMap::new(),
+            firsts: HashMap::new(),
+            seconds: HashMap::new(),
+            total: 0,
+        }
+    }
+
+    /// Takes the next word, pairing it with the ones before.
+    pub fn push(&mut self, word: String) {
+        for first in &self.recent {
+            *self.pairs.entry((first.clone(), word.clone())).or_insert(0) += 1;
+            *self.firsts.entry(first.clone()).or_insert(0) += 1;
+            *self.seconds.entry(word.clone()).or_insert(0) += 1;
+            self.total += 1;
+        }
+        if self.recent.len() == self.window {
+            self.recent.pop_front();
+        }
+        if self.window > 0 {
+            self.recent.push_back(word);
+        }
+    }
+
+    /// No pair spans what comes next, e.g. a stop word or the end of an input.
+    pub fn stop(&mut self) {
+        self.recent.clear();
+    }
+
+    /// Number of pairs counted.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Every pair counted, unordered.
+    pub fn collocations(&self) -> Vec<Collocation> {
+        let total = self.total as f64;
+        self.pairs
+            .iter()
+            .map(|((first, second), &count)| {
+                let first_count = self.firsts[first];
+                let second_count = self.seconds[second];
+                // the 2x2 table of pairs: starting with `first` or not, by
+                // ending with `second` or not
+                let (o11, r1, c1) = (count as f64, first_count as f64, second_count as f64);
+                let observed = [o11, r1 - o11, c1 - o11, total - r1 - c1 + o11];
+                let expected = [
+                    r1 * c1 / total,
+                    r1 * (total - c1) / total,
+                    (total - r1) * c1 / total,
+                    (total - r1) * (total - c1) / total,
+                ];
+                let log_likelihood = 2.0
+                    * observed
+                        .iter()
+                        .zip(&expected)
+                        .map(|(&o, &e)| x_ln_x_over(o, e))
+                        .sum::<f64>();
+                Collocation {
+                    first: first.clone(),
+                    second: second.clone(),
+                    count,
+                    first_count,
+                    second_count,
+                    pmi: (o11 / expected[0]).log2(),
+                    t_score: (o11 - expected[0]) / o11.sqrt(),
+                    log_dice: 14.0 + (2.0 * o11 / (r1 + c1)).log2(),
+                    log_likelihood,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Strongest first by `measure`, ties by count then alphabetically.
+pub fn rank(mut collocations: Vec<Collocation>, measure: Measure) -> Vec<Collocation> {
+    collocations.sort_by(|c1, c2| {
+        c2.score(measure)
+            .partial_cmp(&c1.score(measure))
+            .unwrap_or(Ordering::Equal)
+            .then(c2.count.cmp(&c1.count))
+            .then_with(|| (&c1.first, &c1.second).cmp(&(&c2.first, &c2.second)))
+    });
+    collocations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counted(words: &str, window: usize) -> Cooccurrences {
+        let mut cooccurrences = Cooccurrences::new(window);
+        for word in words.split(' ') {
+            match word {
+                "|" => cooccurrences.stop(),
+                _ => cooccurrences.push(word.to_string()),
+            }
+        }
+        cooccurrences
+    }
+
+    fn pairs(cooccurrences: &Cooccurrences) -> Vec<(String, String, usize)> {
+        let mut pairs: Vec<_> = cooccurrences
+            .collocations()
+            .into_iter()
+            .map(|c| (c.first, c.second, c.count))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    fn pair(first: &str, second: &str, count: usize) -> (String, String, usize) {
+        (first.to_string(), second.to_string(), count)
+    }
+
+    #[test]
+    fn pairs_within_the_window() {
+        let cooccurrences = counted("a b a b", 1);
+        assert_eq!(cooccurrences.total(), 3);
+        assert_eq!(
+            pairs(&cooccurrences),
+            [pair("a", "b", 2), pair("b", "a", 1)]
+        );
+
+        let cooccurrences = counted("a b c", 2);
+        assert_eq!(
+            pairs(&cooccurrences),
+            [pair("a", "b", 1), pair("a", "c", 1), pair("b", "c", 1)]
+        );
+    }
+
+    #[test]
+    fn no_pair_spans_a_stop() {
+        let cooccurrences = counted("a b | c", 2);
+        assert_eq!(pairs(&cooccurrences), [pair("a", "b", 1)]);
+        assert_eq!(counted("a b", 0).total(), 0);
+    }
+
+    #[test]
+    fn scores() {
+        let collocations = counted("a b a b", 1).collocations();
+        let ab = collocations.iter().find(|c| c.first == "a").unwrap();
+        assert_eq!((ab.first_count, ab.second_count), (2, 2));
+        // 2 pairs out of 3, where chance gives 2 * 2 / 3
+        assert!((ab.pmi - 1.5f64.log2()).abs() < 1e-9);
+        // `a` always comes before `b` and `b` always after `a`
+        assert!((ab.log_dice - 14.0).abs() < 1e-9);
+        assert_eq!(ab.score(Measure::LogDice), ab.log_dice);
+        assert!(ab.log_likelihood > 0.0);
+    }
+
+    #[test]
+    fn ranked_by_measure_then_count_then_alphabetically() {
+        let ranked = rank(
+            counted("x y x y | p q | r s", 1).collocations(),
+            Measure::LogDice,
+        );
+        let ranked: Vec<(&str, &str)> = ranked
+            .iter()
+            .map(|c| (c.first.as_str(), c.second.as_str()))
+            .collect();
+        // every pair is as exclusive: a log-Dice of 14
+        assert_eq!(ranked, [("x", "y"), ("p", "q"), ("r", "s"), ("y", "x")]);
+    }
+
+    #[test]
+    fn measures_by_name() {
+        assert_eq!("g2".parse::<Measure>().unwrap(), Measure::LogLikelihood);
+        assert_eq!("t-score".parse::<Measure>().unwrap(), Measure::TScore);
+        assert!("dice".parse::<Measure>().is_err());
+    }
+}
@@ -0,0 +1,802 @@
+use crate::{
+    cache::{self, Cache, Key},
+    chart::{Chart, Scale},
+    contractions::{Contractions, Rules},
+    gutenberg, language, mapping,
+    output::{Format, Note},
+    stop_words::Source,
+    tokenizer::Segmentation,
+    Error, FrequencyTable, Input, Mapping, Ngrams, Page, Ranking, Result, Stemmer, Stemming,
+    StopWords, Tokenizer,
+};
+use std::{env, path::PathBuf, process};
+
+pub const USAGE: &str = "\
+Usage: <program> [options] <input>...
+
+Inputs are files, `-` for stdin, directories (read recursively) or patterns
+such as `chapters/*.txt`; they are counted together. Of Project Gutenberg
+files only the book is read, without the header and the license.
+
+Options:
+    --stop-words <path|builtin|auto>
+                                  read stop words from a file, use the built-in
+                                  English list, or detect the language of each
+                                  input and use the list of every language
+                                  found, of each input alone with --per-file,
+                                  with elisions such as French `l'homme` split
+                                  at the apostrophe; repeat to merge several
+                                  lists
+    --stop-words-dir <dir>        with auto, where lists that replace the built-in
+                                  ones are, e.g. `fr.txt` (default ../stop_words)
+    --no-stop-words               do not filter stop words at all
+    --tokenizer <ascii|alnum|unicode>
+                                  split words on non-letters, on non-alphanumerics
+                                  (default) or on Unicode word boundaries (the
+                                  default and only choice with auto stop words)
+    --min-length <n>              ignore words shorter than n characters (default 2)
+    --contractions <keep|expand|path>
+                                  count words such as `don't` whole, or as the
+                                  words they stand for (`do not`) by the built-in
+                                  English rules or a file of rules; by default
+                                  the tokenizer splits them at the apostrophe
+    --strip-possessives           count `Darcy's` as `darcy`
+    --stem                        count English words by their Porter2 stem, e.g.
+                                  `connected` and `connection` as `connect`
+    --surface-forms               like `--stem`, but report each stem as the word
+                                  it came from most often
+    --map <path|leet|ascii>       replace characters using a mapping file, the leet
+                                  table or the ASCII transliteration table; repeat
+                                  to apply several tables one after the other
+    --map-stage <before|after>    map words before stop-word filtering, or after
+                                  it (default)
+    --ngram <n>                   count sequences of n consecutive words, e.g. 2
+                                  for word pairs (default 1)
+    --ngram-stop-words <drop|break>
+                                  leave stop words out of the sequences (default),
+                                  or never count a sequence across a stop word
+    --format <text|json|csv|tsv|markdown|chart|table>
+                                  how to print the result (default text); chart
+                                  draws a bar per word across the terminal, table
+                                  writes every word in the format read by the
+                                  `tables` tool
+    --log-scale                   with chart, bars proportional to the logarithm
+                                  of the count
+    --ascii-bars                  with chart, draw bars with `#`; the default
+                                  when the locale is not UTF-8
+    --width <n>                   with chart, the columns to fill (default the
+                                  width of the terminal)
+    --length-histogram            with chart, add a histogram of word lengths
+    --rank <count|alpha|first|length|relative>
+                                  order words by count (default), alphabetically,
+                                  by first occurrence, longest first, or by count
+                                  reported as a share of all words; ties are
+                                  broken by count, then alphabetically
+    --top <n>                     report the first n words (default 25, every
+                                  word with `--format table`)
+    --offset <n>                  skip the first n ranks, e.g. `--offset 25` for
+                                  ranks 26 to 50
+    --min-count <n>               leave out words counted fewer than n times
+    --all                         report every word, same as no `--top`
+    --per-file                    report each input on its own before the
+                                  combined table
+    --keep-boilerplate            read Project Gutenberg files whole, header and
+                                  license included
+    --cache <dir>                 keep the counts in dir, and reuse them while
+                                  the inputs and the counting options stay the
+                                  same; see the `cache` tool. Styles #4, #12,
+                                  #16, #26 and #29 and the tools that need more
+                                  than the counts reject it
+    --no-cache                    count from scratch, even with `TF_CACHE` set
+    -h, --help                    print this message
+
+Environment:
+    TF_STOP_WORDS                 stop word lists to use when `--stop-words` is
+                                  not given, separated by `:`
+    TF_CACHE                      directory to use when `--cache` is not given
+";
+
+/// Command line options shared by every style.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// Positional arguments, i.e. the files to read, see [`Input::expand`].
+    pub inputs: Vec<String>,
+    /// Stop word lists to merge; empty means [`StopWords::default_sources`].
+    pub stop_words: Vec<Source>,
+    pub no_stop_words: bool,
+    /// Where the lists of `--stop-words auto` are, by default
+    /// [`StopWords::LANGUAGES_PATH`].
+    pub stop_words_dir: Option<PathBuf>,
+    pub tokenizer: Tokenizer,
+    pub stemming: Stemming,
+    pub ngrams: Ngrams,
+    /// Character tables to apply, in order.
+    pub maps: Vec<mapping::Source>,
+    pub map_stage: mapping::Stage,
+    pub format: Format,
+    pub ranking: Ranking,
+    /// Which ranks to report.
+    pub page: Page,
+    pub per_file: bool,
+    /// Read Project Gutenberg files whole, see [`Input::Verbatim`].
+    pub keep_boilerplate: bool,
+    /// The input this run reports on alone, see [`Config::runs`].
+    pub file: Option<String>,
+    /// Whether the report of another input came before this run's, see
+    /// [`Config::runs`] and [`crate::Report::after_file`].
+    pub after_file: bool,
+    /// Where to keep counts, see [`Config::cache`].
+    pub cache: Option<PathBuf>,
+    pub no_cache: bool,
+}
+
+impl Config {
+    /// Parses the arguments of this process. Prints the usage and exits on
+    /// `-h` / `--help`.
+    pub fn from_env() -> Result<Self> {
+        let args: Vec<String> = env::args().skip(1).collect();
+        if args.iter().any(|a| a == "-h" || a == "--help") {
+            print!("{}", USAGE);
+            process::exit(0);
+        }
+        Self::parse(args)
+    }
+
+    /// Parses arguments, without the program name.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut config = Config::default();
+        // applies to --format chart, which may come later
+        let mut chart = Chart::default();
+        // --format table saves every word unless told otherwise
+        let mut top_given = false;
+        // --stop-words auto reads words whole unless told otherwise
+        let mut segmentation_given = None;
+        let mut args = Args::new(args);
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                config.inputs.push(arg);
+                continue;
+            }
+            match args.flag() {
+                "--" => config.inputs.extend(args.by_ref()),
+                "--stop-words" => config.stop_words.push(args.value()?.parse()?),
+                "--no-stop-words" => config.no_stop_words = args.switch()?,
+                "--stop-words-dir" => config.stop_words_dir = Some(args.value()?.into()),
+                "--tokenizer" => {
+                    let segmentation = args.value()?.parse()?;
+                    config.tokenizer = config.tokenizer.with_segmentation(segmentation);
+                    segmentation_given = Some(segmentation);
+                }
+                "--contractions" => {
+                    config.tokenizer = match args.value()?.as_str() {
+                        "" => return Err(Error::Invalid("empty contractions".to_string())),
+                        how @ ("keep" | "expand") => {
+                            config.tokenizer.with_contractions(how.parse()?)
+                        }
+                        path => config
+                            .tokenizer
+                            .with_contractions(Contractions::Expand)
+                            .with_rules(Rules::from_file(path)?),
+                    }
+                }
+                "--strip-possessives" => {
+                    config.tokenizer = config.tokenizer.with_strip_possessives(args.switch()?)
+                }
+                "--stem" => {
+                    args.switch()?;
+                    config.stemming = Stemming::Stems
+                }
+                "--surface-forms" => {
+                    args.switch()?;
+                    config.stemming = Stemming::SurfaceForms
+                }
+                "--map" => config.maps.push(args.value()?.parse()?),
+                "--map-stage" => config.map_stage = args.value()?.parse()?,
+                "--ngram" => match args.number()? {
+                    0 => return Err(Error::Invalid("`--ngram` must be at least 1".to_string())),
+                    n => config.ngrams.n = n,
+                },
+                "--ngram-stop-words" => config.ngrams.stop_words = args.value()?.parse()?,
+                "--format" => config.format = args.value()?.parse()?,
+                "--log-scale" => {
+                    args.switch()?;
+                    chart.scale = Scale::Log
+                }
+                "--ascii-bars" => chart.ascii = args.switch()?,
+                "--width" => chart.width = Some(args.positive()?),
+                "--length-histogram" => chart.length_histogram = args.switch()?,
+                "--rank" => config.ranking = args.value()?.parse()?,
+                "--top" => {
+                    config.page.top = Some(args.number()?);
+                    top_given = true;
+                }
+                "--offset" => config.page.offset = args.number()?,
+                "--min-count" => config.page.min_count = args.number()?,
+                "--all" => {
+                    args.switch()?;
+                    config.page.top = None;
+                    top_given = true;
+                }
+                "--per-file" => config.per_file = args.switch()?,
+                "--keep-boilerplate" => config.keep_boilerplate = args.switch()?,
+                "--cache" => config.cache = Some(args.value()?.into()),
+                "--no-cache" => config.no_cache = args.switch()?,
+                "--min-length" => config.tokenizer = config.tokenizer.with_min_len(args.number()?),
+                _ => return Err(Error::Invalid(format!("unknown option `{}`", args.flag()))),
+            }
+        }
+        match &mut config.format {
+            Format::Chart(options) => *options = chart,
+            _ if chart != Chart::default() => {
+                return Err(Error::Invalid(
+                    "`--log-scale`, `--ascii-bars`, `--width` and `--length-histogram` \
+                     need `--format chart`"
+                        .to_string(),
+                ))
+            }
+            _ => {}
+        }
+        if config.format == Format::Table {
+            if config.per_file {
+                return Err(Error::Invalid(
+                    "`--format table` writes a single table, it does not go with `--per-file`"
+                        .to_string(),
+                ));
+            }
+            if !top_given {
+                config.page.top = None;
+            }
+        }
+        if config.stop_words.contains(&Source::Auto) {
+            // the French and German lists are full of accented words, which
+            // the ASCII tokenizers cut into pieces ("étaient" into "taient")
+            match segmentation_given {
+                None => {
+                    config.tokenizer = config.tokenizer.with_segmentation(Segmentation::Unicode)
+                }
+                Some(Segmentation::Unicode) => {}
+                Some(_) => {
+                    return Err(Error::Invalid(
+                        "`--stop-words auto` reads accented words, which only \
+                         `--tokenizer unicode` keeps whole"
+                            .to_string(),
+                    ))
+                }
+            }
+            // Unicode segmentation keeps "l'homme" whole, and the `l` of the
+            // French list would never match
+            let elisions = language::LANGUAGES.iter().flat_map(|l| l.elisions);
+            config.tokenizer = config
+                .tokenizer
+                .with_elisions(elisions.map(|e| e.to_string()).collect());
+        }
+        Ok(config)
+    }
+
+    /// The first positional argument.
+    pub fn input(&self) -> Option<&str> {
+        self.inputs.first().map(|s| s.as_str())
+    }
+
+    /// Every input to read, in order.
+    pub fn sources(&self) -> Result<Vec<Input>> {
+        let sources = self.expand(&self.inputs)?;
+        if sources.is_empty() {
+            return Err(Error::Invalid("no input given".to_string()));
+        }
+        Ok(sources)
+    }
+
+    /// [`Input::expand`], read whole with `--keep-boilerplate`.
+    pub fn expand(&self, args: &[String]) -> Result<Vec<Input>> {
+        Ok(Input::expand(args)?
+            .into_iter()
+            .map(|input| input.keeping_boilerplate(self.keep_boilerplate))
+            .collect())
+    }
+
+    /// What to report on: with `--per-file`, one config per input followed by
+    /// the combined one, every one but the first [`Config::after_file`],
+    /// otherwise just this config.
+    pub fn runs(&self) -> Result<Vec<Config>> {
+        if !self.per_file {
+            return Ok(vec![self.clone()]);
+        }
+        let sources = self.sources()?;
+        let base = Config {
+            per_file: false,
+            ..self.clone()
+        };
+        let mut ret: Vec<Config> = sources
+            .iter()
+            .enumerate()
+            .map(|(i, source)| Config {
+                file: Some(source.to_string()),
+                after_file: i > 0,
+                ..base.alone(source)
+            })
+            .collect();
+        ret.push(Config {
+            inputs: sources.iter().map(|s| s.to_string()).collect(),
+            after_file: true,
+            ..base
+        });
+        Ok(ret)
+    }
+
+    /// This config, reading `input` only; with `--stop-words auto` the stop
+    /// words are then those of its language alone.
+    pub fn alone(&self, input: &Input) -> Config {
+        Config {
+            inputs: vec![input.to_string()],
+            ..self.clone()
+        }
+    }
+
+    /// What the report of this run is about: the title and author of the
+    /// book when the run reads a single Project Gutenberg file, and with
+    /// `--stop-words auto` the languages whose stop words are left out and
+    /// how sure the least sure guess is, `unknown` when no input was long
+    /// enough to tell.
+    pub fn about(&self) -> Result<Vec<Note>> {
+        let mut ret = Vec::new();
+        if let [source] = &self.sources()?[..] {
+            ret.extend(book_notes(source.metadata()?.unwrap_or_default()));
+        }
+        if !self.no_stop_words && self.stop_words.contains(&Source::Auto) {
+            ret.extend(self.language_notes(&self.detections()?)?);
+        }
+        Ok(ret)
+    }
+
+    /// [`Config::about`] a text that is none of the inputs, e.g. the body of
+    /// a request: `raw` as it came, Project Gutenberg header included, and
+    /// `text` as it is counted.
+    pub fn about_text(&self, raw: &[u8], text: &str) -> Result<Vec<Note>> {
+        let mut ret = book_notes(
+            gutenberg::find(raw)
+                .map(|book| book.metadata)
+                .unwrap_or_default(),
+        );
+        if !self.no_stop_words && self.stop_words.contains(&Source::Auto) {
+            ret.extend(self.language_notes(&[language::detect(text)])?);
+        }
+        Ok(ret)
+    }
+
+    /// The languages of `detections`, the least sure guess and the
+    /// languages without a list, see [`Config::about`].
+    fn language_notes(&self, detections: &[Option<language::Detection>]) -> Result<Vec<Note>> {
+        let mut ret = Vec::new();
+        let languages: Vec<String> = languages_found(detections)
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        let confidence = detections
+            .iter()
+            .flatten()
+            .map(|d| d.confidence)
+            .min_by(f64::total_cmp);
+        ret.push(Note {
+            key: "language",
+            label: "Language",
+            value: languages.join(", "),
+        });
+        ret.push(Note {
+            key: "confidence",
+            label: "Confidence",
+            value: confidence.map_or("unknown".to_string(), |c| format!("{:.0}%", c * 100.0)),
+        });
+        let dir = self.stop_words_dir();
+        let mut missing = Vec::new();
+        for language in languages_found(detections) {
+            if StopWords::language_sources(language.code, &dir)?.is_none() {
+                missing.push(format!(
+                    "{}, add them as `{}`",
+                    language,
+                    dir.join(format!("{}.txt", language.code)).display()
+                ));
+            }
+        }
+        if !missing.is_empty() {
+            ret.push(Note {
+                key: "warning",
+                label: "Warning",
+                value: format!(
+                    "every word counted, no stop words for {}",
+                    missing.join("; ")
+                ),
+            });
+        }
+        Ok(ret)
+    }
+
+    /// A new stemmer for one run; with `--surface-forms` it must be the same
+    /// one for stemming and for reporting.
+    pub fn stemmer(&self) -> Stemmer {
+        Stemmer::new(self.stemming)
+    }
+
+    /// The tables of `--map`, read from disk.
+    pub fn mapping(&self) -> Result<Mapping> {
+        Mapping::load(&self.maps, self.map_stage)
+    }
+
+    /// The words of `text` that get counted, in order: tokenized, mapped,
+    /// without stop words, joined into n-grams and stemmed, as in every
+    /// style. For tools that count text without a style of their own.
+    pub fn words<'a>(
+        &'a self,
+        text: &'a str,
+        stop_words: &'a StopWords,
+        mapping: &'a Mapping,
+        stemmer: &'a Stemmer,
+    ) -> impl Iterator<Item = String> + 'a {
+        let words = self.tokenizer.words(text).map(move |w| mapping.before(w));
+        self.ngrams
+            .of(words, move |w| stop_words.contains(w))
+            .map(move |w| stemmer.stem(mapping.after(w)))
+    }
+
+    /// The cache of `--cache`, or else of `TF_CACHE`. `None` without
+    /// either, with `--no-cache`, and with `--surface-forms`, whose labels
+    /// come from the text rather than from the counts.
+    pub fn cache(&self) -> Option<Cache> {
+        if self.no_cache || self.stemming == Stemming::SurfaceForms {
+            return None;
+        }
+        self.cache
+            .clone()
+            .or_else(|| env::var_os(cache::ENV_VAR).map(PathBuf::from))
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(Cache::new)
+    }
+
+    /// For the programs that count from scratch every time, i.e. that never
+    /// call [`Config::counted`]: an error with `--cache`, and no
+    /// [`Config::cache`] even with `TF_CACHE` set.
+    pub fn uncached(self) -> Result<Self> {
+        if self.cache.is_some() {
+            return Err(Error::Invalid(
+                "`--cache` does not apply, this program counts from scratch every time".to_string(),
+            ));
+        }
+        Ok(Self {
+            no_cache: true,
+            ..self
+        })
+    }
+
+    /// The counts of `sources`, from `count` or, when they were counted with
+    /// the same options before, from the [`Config::cache`].
+    pub fn counted(
+        &self,
+        sources: &[Input],
+        stop_words: &StopWords,
+        mapping: &Mapping,
+        count: impl FnOnce() -> Result<FrequencyTable>,
+    ) -> Result<FrequencyTable> {
+        let cache = match self.cache() {
+            Some(cache) => cache,
+            None => return count(),
+        };
+        let key = Key::new(
+            sources,
+            stop_words,
+            &self.tokenizer,
+            mapping,
+            self.ngrams,
+            self.stemming,
+        )?;
+        cache.get_or_count(key, count)
+    }
+
+    pub fn stop_words(&self) -> Result<StopWords> {
+        if self.no_stop_words {
+            Ok(StopWords::default())
+        } else if self.stop_words.is_empty() {
+            StopWords::load_default()
+        } else {
+            StopWords::from_sources(&self.stop_word_sources()?)
+        }
+    }
+
+    /// [`Config::stop_words`] for `text` alone, e.g. a section of an input:
+    /// with `--stop-words auto`, those of the language of `text`.
+    pub fn stop_words_of(&self, text: &str) -> Result<StopWords> {
+        if self.no_stop_words || !self.stop_words.contains(&Source::Auto) {
+            return self.stop_words();
+        }
+        StopWords::from_sources(&self.with_languages(&[language::detect(text)])?)
+    }
+
+    /// `--stop-words`, with `auto` replaced by the lists of the languages
+    /// the inputs are in: the language of each input is detected, see
+    /// [`language::detect`], and the list of every language found is used.
+    /// With several inputs, see [`Config::alone`] for the list of each.
+    pub fn stop_word_sources(&self) -> Result<Vec<Source>> {
+        if !self.stop_words.contains(&Source::Auto) {
+            return Ok(self.stop_words.clone());
+        }
+        self.with_languages(&self.detections()?)
+    }
+
+    /// The language of each input, `None` for those too short to tell.
+    fn detections(&self) -> Result<Vec<Option<language::Detection>>> {
+        self.sources()?
+            .iter()
+            .map(|source| Ok(language::detect(&source.read_to_string()?)))
+            .collect()
+    }
+
+    /// `--stop-words` with `auto` replaced by the lists of the languages
+    /// detected; English when no text was long enough to tell.
+    fn with_languages(&self, detections: &[Option<language::Detection>]) -> Result<Vec<Source>> {
+        let languages = languages_found(detections);
+        let dir = self.stop_words_dir();
+        let mut ret = Vec::new();
+        for source in &self.stop_words {
+            match source {
+                Source::Auto => {
+                    for language in &languages {
+                        // see the warning of `Config::about`
+                        let sources = StopWords::language_sources(language.code, &dir)?;
+                        ret.extend(sources.unwrap_or_default());
+                    }
+                }
+                source => ret.push(source.clone()),
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Where the lists of `--stop-words auto` are.
+    pub fn stop_words_dir(&self) -> PathBuf {
+        self.stop_words_dir
+            .clone()
+            .unwrap_or_else(|| StopWords::LANGUAGES_PATH.into())
+    }
+}
+
+/// The title and author of a book, those it has.
+fn book_notes(metadata: gutenberg::Metadata) -> Vec<Note> {
+    let fields = vec![
+        ("title", "Title", metadata.title),
+        ("author", "Author", metadata.author),
+    ];
+    fields
+        .into_iter()
+        .filter_map(|(key, label, value)| {
+            Some(Note {
+                key,
+                label,
+                value: value?,
+            })
+        })
+        .collect()
+}
+
+/// Every language detected, in order, or English when no text was long
+/// enough to tell.
+fn languages_found(detections: &[Option<language::Detection>]) -> Vec<&'static language::Language> {
+    let mut ret: Vec<&'static language::Language> = Vec::new();
+    for detection in detections.iter().flatten() {
+        if !ret.iter().any(|l| l.code == detection.language.code) {
+            ret.push(detection.language);
+        }
+    }
+    if ret.is_empty() {
+        ret.extend(language::find("en"));
+    }
+    ret
+}
+
+/// Command line arguments, one at a time, and the value of the last flag,
+/// `--top 5` and `--top=5` alike. Every program parses its own options with
+/// it and hands the rest to [`Config::parse`].
+///
+/// ```
+/// use tf_core::config::Args;
+/// let mut args = Args::new(["--top=5", "--min-count", "2", "a.txt"].map(String::from));
+/// let mut inputs = Vec::new();
+/// while let Some(arg) = args.next() {
+///     match args.flag() {
+///         "--top" => assert_eq!(args.number().unwrap(), 5),
+///         "--min-count" => assert_eq!(args.value().unwrap(), "2"),
+///         _ => inputs.push(arg),
+///     }
+/// }
+/// assert_eq!(inputs, ["a.txt"]);
+/// ```
+#[derive(Debug)]
+pub struct Args {
+    args: std::vec::IntoIter<String>,
+    /// The last argument up to its `=`, if it is an option.
+    flag: String,
+    /// What follows the `=`.
+    inline: Option<String>,
+}
+
+impl Args {
+    pub fn new(args: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            args: args.into_iter().collect::<Vec<_>>().into_iter(),
+            flag: String::new(),
+            inline: None,
+        }
+    }
+
+    /// The last argument without its value: `--top` for both `--top 5` and
+    /// `--top=5`. Anything but an option is whole.
+    pub fn flag(&self) -> &str {
+        &self.flag
+    }
+
+    /// The value of the last flag: what follows its `=`, or else the next
+    /// argument.
+    pub fn value(&mut self) -> Result<String> {
+        match self.inline.take() {
+            Some(value) => Ok(value),
+            None => self
+                .args
+                .next()
+                .ok_or_else(|| Error::Invalid(format!("missing value for `{}`", self.flag))),
+        }
+    }
+
+    /// [`Args::value`], as a count.
+    pub fn number(&mut self) -> Result<usize> {
+        let value = self.value()?;
+        value.parse().map_err(|_| {
+            Error::Invalid(format!("`{}` expects a number, got `{}`", self.flag, value))
+        })
+    }
+
+    /// [`Args::number`], and not 0.
+    pub fn positive(&mut self) -> Result<usize> {
+        let value = self.value()?;
+        match value.parse() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(Error::Invalid(format!(
+                "`{}` expects a positive number, got `{}`",
+                self.flag, value
+            ))),
+        }
+    }
+
+    /// `true` for a flag that takes no value, such as `--no-stop-words`; an
+    /// error when one is given anyway, e.g. `--no-stop-words=false`, rather
+    /// than switching it on regardless.
+    pub fn switch(&mut self) -> Result<bool> {
+        match self.inline.take() {
+            Some(value) => Err(Error::Invalid(format!(
+                "`{}` takes no value, got `{}`",
+                self.flag, value
+            ))),
+            None => Ok(true),
+        }
+    }
+}
+
+impl Iterator for Args {
+    type Item = String;
+
+    /// The next argument, whole, e.g. `--top=5`.
+    fn next(&mut self) -> Option<String> {
+        let arg = self.args.next()?;
+        (self.flag, self.inline) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => {
+                (arg[..i].to_string(), Some(arg[i + 1..].to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        Some(arg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Config> {
+        Config::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn flags_are_read_before_and_after_the_inputs() {
+        let config = parse(&["a.txt", "--stop-words=builtin", "--stop-words", "b.txt"]).unwrap();
+        assert_eq!(config.inputs, ["a.txt"]);
+        assert_eq!(
+            config.stop_words,
+            [Source::Builtin, Source::File("b.txt".into())]
+        );
+        let config = parse(&["--no-stop-words", "--", "--a.txt"]).unwrap();
+        assert!(config.no_stop_words);
+        assert_eq!(config.inputs, ["--a.txt"]);
+    }
+
+    #[test]
+    fn a_flag_without_a_value_rejects_one() {
+        assert!(parse(&["--no-stop-words=false"]).is_err());
+        assert!(parse(&["--all=no"]).is_err());
+        assert!(parse(&["--stem=false"]).is_err());
+        assert!(parse(&["--per-file=yes"]).is_err());
+        assert!(parse(&["--format=chart", "--log-scale=no"]).is_err());
+        assert!(parse(&["--format=chart", "--width=0"]).is_err());
+        assert!(parse(&["--stop-words"]).is_err());
+        assert!(parse(&["--stop"]).is_err());
+    }
+
+    #[test]
+    fn runs_after_the_first_go_on_after_a_file() {
+        let dir = env::temp_dir().join(format!("tf-config-{}-runs", process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let files: Vec<String> = ["a.txt", "b.txt"]
+            .iter()
+            .map(|name| {
+                let path = dir.join(name);
+                std::fs::write(&path, "words").unwrap();
+                path.display().to_string()
+            })
+            .collect();
+        let config = Config::parse(files.clone()).unwrap();
+        let after: Vec<bool> = config
+            .runs()
+            .unwrap()
+            .iter()
+            .map(|r| r.after_file)
+            .collect();
+        assert_eq!(after, [false]);
+        let config = Config::parse(files.into_iter().chain(Some("--per-file".into()))).unwrap();
+        let runs = config.runs().unwrap();
+        let after: Vec<bool> = runs.iter().map(|r| r.after_file).collect();
+        assert_eq!(after, [false, true, true]);
+        assert_eq!(runs[2].file, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn auto_stop_words_read_unicode_words() {
+        let config = parse(&["--stop-words", "auto"]).unwrap();
+        assert_eq!(
+            config.tokenizer.words("étaient").collect::<Vec<_>>(),
+            ["étaient"]
+        );
+        assert!(parse(&["--stop-words", "auto", "--tokenizer", "unicode"]).is_ok());
+        assert!(parse(&["--tokenizer", "ascii", "--stop-words", "auto"]).is_err());
+        let config = parse(&["--stop-words", "builtin"]).unwrap();
+        assert_eq!(
+            config.tokenizer.words("étaient").collect::<Vec<_>>(),
+            ["taient"]
+        );
+    }
+
+    #[test]
+    fn auto_stop_words_leave_no_french_elision() {
+        let text = include_str!("language_fr.txt");
+        let path = env::temp_dir().join(format!("tf-config-{}-fr.txt", process::id()));
+        std::fs::write(&path, text).unwrap();
+        let config = parse(&["--stop-words", "auto", &path.display().to_string()]).unwrap();
+        let stop_words = config.stop_words().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        for contractions in ["keep", "expand"] {
+            let args = ["--stop-words", "auto", "--contractions", contractions];
+            let tokenizer = parse(&args).unwrap().tokenizer;
+            let words: Vec<_> = tokenizer.words("Qu'il l'était").collect();
+            assert_eq!(words, ["qu", "il", "était"]);
+        }
+        let counted: Vec<String> = config
+            .tokenizer
+            .words(text)
+            .filter(|w| !stop_words.contains(w))
+            .collect();
+        assert!(counted.contains(&"église".to_string()));
+        let elided = [
+            "c", "d", "j", "l", "m", "n", "s", "t", "qu", "jusqu", "lorsqu",
+        ];
+        for word in &counted {
+            assert!(!word.contains('\''), "{}", word);
+            assert!(!elided.contains(&word.as_str()), "{}", word);
+        }
+    }
+}
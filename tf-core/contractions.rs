@@ -0,0 +1,224 @@
+use crate::{input::with_path, Error, Result};
+use std::{collections::BTreeMap, fs, path::Path, str::FromStr};
+
+/// English contractions and what they stand for. A leading `*` stands for
+/// the start of any word, e.g. "couldn't" is `could not`.
+const ENGLISH: &str = "\
+ain't is not
+can't can not
+shan't shall not
+won't will not
+let's let us
+it's it is
+he's he is
+she's she is
+that's that is
+there's there is
+here's here is
+what's what is
+where's where is
+who's who is
+how's how is
+y'all you all
+*n't not
+*'ll will
+*'re are
+*'ve have
+*'m am
+*'d would
+";
+
+/// Whether `ch` is an apostrophe: ASCII, typographic (U+2019) or the
+/// modifier letter (U+02BC).
+pub fn is_apostrophe(ch: char) -> bool {
+    matches!(ch, '\'' | '\u{2019}' | '\u{2bc}')
+}
+
+/// `word` with every apostrophe written `'`, so that "don’t" and "don't" are
+/// the same word.
+pub fn normalize_apostrophes(word: &str) -> String {
+    word.replace(is_apostrophe, "'")
+}
+
+/// What to do with words with an apostrophe in them, e.g. "don't".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Contractions {
+    /// Split at the apostrophe, "don't" into `don` and `t`, whatever the
+    /// segmentation.
+    #[default]
+    Segmented,
+    /// One word, whatever its apostrophe, "don’t" as `don't`.
+    Keep,
+    /// The words they stand for, by the [`Rules`], "don't" as `do` and
+    /// `not`. A word no rule matches is kept.
+    Expand,
+}
+
+impl FromStr for Contractions {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "keep" => Ok(Contractions::Keep),
+            "expand" => Ok(Contractions::Expand),
+            _ => Err(Error::Invalid(format!(
+                "unknown contractions `{}`, expected keep, expand or a path",
+                s
+            ))),
+        }
+    }
+}
+
+/// Contractions and the words they stand for, lowercase, with `'` for
+/// every apostrophe.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rules {
+    words: BTreeMap<String, Vec<String>>,
+    /// Longest first, so that `*n't` is tried before `*'t`.
+    suffixes: Vec<(String, Vec<String>)>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+impl Rules {
+    /// One rule per line: a contraction, whitespace and the words it stands
+    /// for. A contraction starting with `*` matches the end of any word, which
+    /// is kept before the words, e.g. `*'ll will`. Only words with an
+    /// apostrophe are contractions. Empty lines and lines starting with `#`
+    /// are skipped.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut words = BTreeMap::new();
+        let mut suffixes = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let from = normalize_apostrophes(&fields.next().unwrap_or_default().to_lowercase());
+            let to: Vec<String> = fields.map(str::to_lowercase).collect();
+            if to.is_empty() || from == "*" {
+                return Err(Error::Invalid(format!(
+                    "line {}: expected a contraction, then the words it stands for",
+                    i + 1
+                )));
+            }
+            if !from.contains('\'') {
+                return Err(Error::Invalid(format!(
+                    "line {}: `{}` has no apostrophe",
+                    i + 1,
+                    from
+                )));
+            }
+            match from.strip_prefix('*') {
+                Some(suffix) => suffixes.push((suffix.to_string(), to)),
+                None => {
+                    words.insert(from, to);
+                }
+            }
+        }
+        suffixes.sort_by_key(|(suffix, _words)| std::cmp::Reverse(suffix.len()));
+        Ok(Self { words, suffixes })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        Self::parse(&fs::read_to_string(path).map_err(|e| with_path(path, e))?)
+            .map_err(|e| Error::Invalid(format!("{}: {}", path.display(), e)))
+    }
+
+    /// `don't` as `do not`, `they'll` as `they will`...
+    pub fn english() -> Self {
+        Self::parse(ENGLISH).expect("built-in contractions are valid")
+    }
+
+    /// The rule for `word`, lowercase with `'` apostrophes: the start of the
+    /// word a suffix rule keeps, empty for a whole word, and the words that
+    /// follow it.
+    pub fn find<'a>(&'a self, word: &'a str) -> Option<(&'a str, &'a [String])> {
+        if let Some(to) = self.words.get(word) {
+            return Some(("", to));
+        }
+        self.suffixes.iter().find_map(|(suffix, to)| {
+            let stem = word.strip_suffix(suffix.as_str())?;
+            (!stem.is_empty()).then_some((stem, &to[..]))
+        })
+    }
+
+    /// Number of rules.
+    pub fn len(&self) -> usize {
+        self.words.len() + self.suffixes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty() && self.suffixes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    fn words(to: &[String]) -> Vec<&str> {
+        to.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn whole_words_then_suffixes() {
+        let rules = Rules::english();
+        let (stem, to) = rules.find("won't").unwrap();
+        assert_eq!((stem, words(to)), ("", vec!["will", "not"]));
+        let (stem, to) = rules.find("couldn't").unwrap();
+        assert_eq!((stem, words(to)), ("could", vec!["not"]));
+        let (stem, to) = rules.find("they'll").unwrap();
+        assert_eq!((stem, words(to)), ("they", vec!["will"]));
+        assert_eq!(rules.find("n't"), None);
+        assert_eq!(rules.find("darcy's"), None);
+    }
+
+    #[test]
+    fn rules_are_parsed_line_by_line() {
+        let rules = Rules::parse("# mine\n\nY’know you know\n*'t it\n*n't not\n").unwrap();
+        assert_eq!(rules.len(), 3);
+        let (stem, to) = rules.find("y'know").unwrap();
+        assert_eq!((stem, words(to)), ("", vec!["you", "know"]));
+        // the longest suffix first
+        let (stem, to) = rules.find("don't").unwrap();
+        assert_eq!((stem, words(to)), ("do", vec!["not"]));
+        assert!(Rules::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn rules_without_apostrophe_or_words_are_errors() {
+        for text in ["dont do not", "can't", "* will", "ok\nfine fine"] {
+            assert!(Rules::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn rules_from_a_file() {
+        let path = std::env::temp_dir().join(format!("tf-contractions-{}", process::id()));
+        fs::write(&path, "c'mon come on\n").unwrap();
+        assert_eq!(Rules::from_file(&path).unwrap().len(), 1);
+        fs::write(&path, "cmon come on\n").unwrap();
+        let err = Rules::from_file(&path).unwrap_err().to_string();
+        assert!(err.contains("line 1"), "{}", err);
+        fs::remove_file(&path).unwrap();
+        assert!(Rules::from_file(&path).is_err());
+    }
+
+    #[test]
+    fn every_apostrophe_is_written_ascii() {
+        assert!(is_apostrophe('\u{2019}'));
+        assert!(!is_apostrophe('`'));
+        assert_eq!(
+            normalize_apostrophes("don\u{2019}t we\u{2bc}ll"),
+            "don't we'll"
+        );
+        assert_eq!("keep".parse::<Contractions>().unwrap(), Contractions::Keep);
+        assert!("split".parse::<Contractions>().is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use crate::FrequencyTable;
+use std::collections::{HashMap, HashSet};
+
+/// The type-token ratio below which MTLD closes a segment.
+pub const MTLD_THRESHOLD: f64 = 0.72;
+
+/// How rich the vocabulary of a text is, from its frequency table alone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Richness {
+    /// Words counted, N.
+    pub tokens: usize,
+    /// Distinct words, V.
+    pub types: usize,
+    /// V / N. Falls as the text grows, so only comparable between texts of
+    /// the same length.
+    pub type_token_ratio: f64,
+    /// Words seen once.
+    pub hapax_legomena: usize,
+    /// Words seen twice.
+    pub dis_legomena: usize,
+    /// Yule's characteristic K: the lower, the richer. Does not depend on
+    /// the length of the text.
+    pub yules_k: f64,
+    /// Simpson's D: the chance that two words drawn at random are the same.
+    pub simpsons_d: f64,
+    /// Honoré's R, from the share of hapax legomena: the higher, the richer.
+    /// `None` when every word is a hapax.
+    pub honores_r: Option<f64>,
+}
+
+impl Richness {
+    pub fn from_table(table: &FrequencyTable) -> Self {
+        let (n, v) = (table.total() as f64, table.len() as f64);
+        // how many words are seen m times, for each m
+        let mut spectrum: HashMap<usize, usize> = HashMap::new();
+        for (_w, c) in table.iter() {
+            *spectrum.entry(c).or_insert(0) += 1;
+        }
+        let sum = |f: &dyn Fn(f64) -> f64| -> f64 {
+            spectrum
+                .iter()
+                .map(|(&m, &vm)| vm as f64 * f(m as f64))
+                .sum()
+        };
+        let hapax_legomena = spectrum.get(&1).copied().unwrap_or(0);
+        let ratio = |a: f64, b: f64| if b == 0.0 { 0.0 } else { a / b };
+        Richness {
+            tokens: table.total(),
+            types: table.len(),
+            type_token_ratio: ratio(v, n),
+            hapax_legomena,
+            dis_legomena: spectrum.get(&2).copied().unwrap_or(0),
+            yules_k: 10_000.0 * ratio(sum(&|m| m * m) - n, n * n),
+            simpsons_d: ratio(sum(&|m| m * (m - 1.0)), n * (n - 1.0)),
+            honores_r: if hapax_legomena == table.len() {
+                None
+            } else {
+                Some(100.0 * n.ln() / (1.0 - hapax_legomena as f64 / v))
+            },
+        }
+    }
+}
+
+/// Moving-average type-token ratio: the mean ratio of every `window`
+/// consecutive words, which unlike the plain ratio does not fall with the
+/// length of the text. The plain ratio for texts shorter than the window.
+pub fn moving_average_ttr(words: &[String], window: usize) -> f64 {
+    if words.is_empty() || window == 0 {
+        return 0.0;
+    }
+    if words.len() <= window {
+        let distinct: HashSet<&String> = words.iter().collect();
+        return distinct.len() as f64 / words.len() as f64;
+    }
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for w in &words[..window] {
+        *counts.entry(w).or_insert(0) += 1;
+    }
+    let mut sum = counts.len() as f64;
+    for i in window..words.len() {
+        let out = &words[i - window];
+        let left = counts.get_mut(out.as_str()).map(|c| {
+            *c -= 1;
+            *c
+        });
+        if left == Some(0) {
+            counts.remove(out.as_str());
+        }
+        *counts.entry(&words[i]).or_insert(0) += 1;
+        sum += counts.len() as f64;
+    }
+    sum / (words.len() - window + 1) as f64 / window as f64
+}
+
+/// Measure of textual lexical diversity: the mean length of the segments
+/// over which the type-token ratio stays above [`MTLD_THRESHOLD`], read
+/// forwards and backwards. `None` when the text never gets there, e.g. when
+/// every word is different.
+pub fn mtld(words: &[String]) -> Option<f64> {
+    let forwards = mtld_pass(words.iter())?;
+    let backwards = mtld_pass(words.iter().rev())?;
+    Some((forwards + backwards) / 2.0)
+}
+
+fn mtld_pass<'a>(words: impl Iterator<Item = &'a String>) -> Option<f64> {
+    let mut factors = 0.0;
+    let mut seen = HashSet::new();
+    let (mut tokens, mut segment) = (0, 0);
+    for w in words {
+        tokens += 1;
+        segment += 1;
+        seen.insert(w);
+        if seen.len() as f64 / segment as f64 <= MTLD_THRESHOLD {
+            factors += 1.0;
+            seen.clear();
+            segment = 0;
+        }
+    }
+    // what is left counts as the part of a segment it went through
+    if segment > 0 {
+        let ttr = seen.len() as f64 / segment as f64;
+        factors += (1.0 - ttr) / (1.0 - MTLD_THRESHOLD);
+    }
+    if factors == 0.0 {
+        None
+    } else {
+        Some(tokens as f64 / factors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<String> {
+        text.split(' ').map(str::to_string).collect()
+    }
+
+    fn table(text: &str) -> FrequencyTable {
+        let mut table = FrequencyTable::new();
+        for word in words(text) {
+            table.add(word);
+        }
+        table
+    }
+
+    #[test]
+    fn richness_from_the_spectrum() {
+        let richness = Richness::from_table(&table("a a b c"));
+        assert_eq!((richness.tokens, richness.types), (4, 3));
+        assert_eq!(richness.type_token_ratio, 0.75);
+        assert_eq!((richness.hapax_legomena, richness.dis_legomena), (2, 1));
+        // (1 * 2² + 2 * 1² - 4) / 4²
+        assert!((richness.yules_k - 1250.0).abs() < 1e-9);
+        // 2 * 1 / (4 * 3)
+        assert!((richness.simpsons_d - 1.0 / 6.0).abs() < 1e-9);
+        assert!((richness.honores_r.unwrap() - 300.0 * 4f64.ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn richness_of_hapaxes_and_of_nothing() {
+        assert_eq!(Richness::from_table(&table("a b c")).honores_r, None);
+        let empty = Richness::from_table(&FrequencyTable::new());
+        assert_eq!(empty.type_token_ratio, 0.0);
+        assert_eq!(empty.yules_k, 0.0);
+    }
+
+    #[test]
+    fn moving_average() {
+        assert_eq!(moving_average_ttr(&words("a b a b"), 2), 1.0);
+        // "a a" then "a b"
+        assert_eq!(moving_average_ttr(&words("a a b"), 2), 0.75);
+        // shorter than the window
+        assert_eq!(moving_average_ttr(&words("a a"), 5), 0.5);
+        assert_eq!(moving_average_ttr(&[], 5), 0.0);
+    }
+
+    #[test]
+    fn mtld_segments() {
+        // a segment closes every two words, both ways
+        assert_eq!(mtld(&words("a a a a")), Some(2.0));
+        assert_eq!(mtld(&words("a b c")), None);
+    }
+}
@@ -0,0 +1,139 @@
+use std::{collections::HashMap, iter::FromIterator};
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    count: usize,
+    /// how many distinct words were seen before this one, or the position
+    /// given to [`FrequencyTable::add_at`]
+    first_seen: usize,
+}
+
+/// Word counts, plus the order in which words first appeared.
+#[derive(Debug, Clone, Default)]
+pub struct FrequencyTable {
+    entries: HashMap<String, Entry>,
+    total: usize,
+}
+
+impl FrequencyTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, word: String) {
+        self.add_count(word, 1)
+    }
+
+    pub fn add_count(&mut self, word: String, count: usize) {
+        let first_seen = self.entries.len();
+        self.entries
+            .entry(word)
+            .or_insert(Entry {
+                count: 0,
+                first_seen,
+            })
+            .count += count;
+        self.total += count;
+    }
+
+    /// Counts `word` found at `position` in the text, for tables that are
+    /// filled out of order, e.g. by several threads. Don't mix with
+    /// [`Self::add`].
+    pub fn add_at(&mut self, word: String, position: usize) {
+        let entry = self.entries.entry(word).or_insert(Entry {
+            count: 0,
+            first_seen: position,
+        });
+        entry.count += 1;
+        entry.first_seen = entry.first_seen.min(position);
+        self.total += 1;
+    }
+
+    /// Adds every count of `other` to this table. Words new to this table
+    /// are considered seen after all existing ones.
+    pub fn merge(&mut self, other: FrequencyTable) {
+        for (word, count) in other.into_counts() {
+            self.add_count(word, count);
+        }
+    }
+
+    /// Like [`Self::merge`] for tables filled with [`Self::add_at`]: the
+    /// earliest position of each word is kept.
+    pub fn merge_positioned(&mut self, other: FrequencyTable) {
+        for (word, e) in other.entries {
+            let entry = self.entries.entry(word).or_insert(Entry {
+                count: 0,
+                first_seen: e.first_seen,
+            });
+            entry.count += e.count;
+            entry.first_seen = entry.first_seen.min(e.first_seen);
+        }
+        self.total += other.total;
+    }
+
+    pub fn count(&self, word: &str) -> usize {
+        self.entries.get(word).map_or(0, |e| e.count)
+    }
+
+    pub fn first_seen(&self, word: &str) -> Option<usize> {
+        self.entries.get(word).map(|e| e.first_seen)
+    }
+
+    /// Number of distinct words.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of counted words, i.e. the sum of all counts.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// `(word, count)` pairs in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.entries.iter().map(|(w, e)| (w.as_str(), e.count))
+    }
+
+    /// `(word, count)` pairs in first-seen order.
+    pub fn into_counts(self) -> Vec<(String, usize)> {
+        let mut entries: Vec<_> = self.entries.into_iter().collect();
+        entries.sort_unstable_by_key(|(_w, e)| e.first_seen);
+        entries.into_iter().map(|(w, e)| (w, e.count)).collect()
+    }
+}
+
+impl FromIterator<String> for FrequencyTable {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        let mut table = Self::new();
+        table.extend(iter);
+        table
+    }
+}
+
+impl Extend<String> for FrequencyTable {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        for word in iter {
+            self.add(word);
+        }
+    }
+}
+
+impl FromIterator<(String, usize)> for FrequencyTable {
+    fn from_iter<I: IntoIterator<Item = (String, usize)>>(iter: I) -> Self {
+        let mut table = Self::new();
+        table.extend(iter);
+        table
+    }
+}
+
+impl Extend<(String, usize)> for FrequencyTable {
+    fn extend<I: IntoIterator<Item = (String, usize)>>(&mut self, iter: I) {
+        for (word, count) in iter {
+            self.add_count(word, count);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+/// What the header tells about the book.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub language: Option<String>,
+}
+
+/// Where the book is in a Project Gutenberg file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Book {
+    /// Byte offset of the first line after the start marker.
+    pub start: usize,
+    /// Byte offset of the end marker, or the end of the file.
+    pub end: usize,
+    pub metadata: Metadata,
+}
+
+/// The book in `text`, without the header and the license around it, whose
+/// words ("project", "gutenberg", "electronic"...) would otherwise be
+/// counted with the book's. `None` when `text` has no start marker, i.e. is
+/// not a Project Gutenberg file. Knows the `*** START OF THE PROJECT GUTENBERG
+/// EBOOK ... ***` markers and their older variants.
+///
+/// ```
+/// use tf_core::gutenberg;
+/// let text = b"Title: Emma\nAuthor: Jane Austen\n\n\
+///     *** START OF THE PROJECT GUTENBERG EBOOK EMMA ***\nEmma Woodhouse\n\
+///     *** END OF THE PROJECT GUTENBERG EBOOK EMMA ***\nlicense\n";
+/// let book = gutenberg::find(text).unwrap();
+/// assert_eq!(&text[book.start..book.end], b"Emma Woodhouse\n");
+/// assert_eq!(book.metadata.author.as_deref(), Some("Jane Austen"));
+/// ```
+pub fn find(text: &[u8]) -> Option<Book> {
+    let mut metadata = Metadata::default();
+    let mut start = None;
+    let mut offset = 0;
+    for line in text.split_inclusive(|&b| b == b'\n') {
+        let next = offset + line.len();
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim();
+        match start {
+            None if is_start(line) => start = Some(next),
+            None => {
+                if let Some((key, value)) = line.split_once(':') {
+                    let value = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+                    match key.trim().to_ascii_lowercase().as_str() {
+                        "title" => metadata.title = metadata.title.or(value),
+                        "author" => metadata.author = metadata.author.or(value),
+                        "language" => metadata.language = metadata.language.or(value),
+                        _ => {}
+                    }
+                }
+            }
+            Some(start) if is_end(line) => {
+                return Some(Book {
+                    start,
+                    end: offset,
+                    metadata,
+                })
+            }
+            Some(_) => {}
+        }
+        offset = next;
+    }
+    start.map(|start| Book {
+        start,
+        end: text.len(),
+        metadata,
+    })
+}
+
+/// The book in `text`, or all of `text` when it is not a Project Gutenberg
+/// file.
+pub fn strip(text: &[u8]) -> &[u8] {
+    match find(text) {
+        Some(book) => &text[book.start..book.end],
+        None => text,
+    }
+}
+
+fn is_start(line: &str) -> bool {
+    let line = line.to_ascii_uppercase();
+    // `*** START OF THE PROJECT GUTENBERG EBOOK` or `OF THIS PROJECT...`
+    (line.starts_with("***") && line.contains("START OF") && line.contains("PROJECT GUTENBERG"))
+        // the end of the small print of files from the nineties
+        || (line.starts_with("*END*") && line.contains("SMALL PRINT"))
+}
+
+fn is_end(line: &str) -> bool {
+    let line = line.to_ascii_uppercase();
+    (line.starts_with("***") && line.contains("END OF") && line.contains("PROJECT GUTENBERG"))
+        || line.starts_with("END OF THE PROJECT GUTENBERG")
+        || line.starts_with("END OF PROJECT GUTENBERG")
+}
@@ -0,0 +1,300 @@
+use crate::{gutenberg, Error, Result};
+use std::{
+    fmt, fs,
+    io::{self, BufRead, BufReader, Cursor, Read},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::OnceLock,
+};
+
+/// Where text is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    /// `-` on the command line.
+    Stdin,
+    File(PathBuf),
+    /// Read as is, Project Gutenberg boilerplate included, see
+    /// [`Input::open`].
+    Verbatim(Box<Input>),
+}
+
+impl FromStr for Input {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "" => Err(Error::Invalid("empty input path".to_string())),
+            "-" => Ok(Input::Stdin),
+            path => Ok(Input::File(path.into())),
+        }
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Stdin => write!(f, "-"),
+            Input::File(path) => write!(f, "{}", path.display()),
+            Input::Verbatim(input) => input.fmt(f),
+        }
+    }
+}
+
+impl Input {
+    /// Turns command line arguments into inputs: `-` is stdin, directories
+    /// are walked recursively and shell-style patterns such as
+    /// `chapters/*.txt` or `books/**/*.txt` are matched against the file
+    /// system. Arguments keep their order; the files found in a directory or
+    /// by a pattern are sorted by path.
+    pub fn expand(args: &[String]) -> Result<Vec<Input>> {
+        let mut ret = vec![];
+        for arg in args {
+            let path = Path::new(arg);
+            if arg == "-" {
+                ret.push(Input::Stdin);
+            } else if path.is_dir() {
+                walk(path, &mut ret)?;
+            } else if !path.exists() && arg.contains(&['*', '?', '['][..]) {
+                let matched = ret.len();
+                let paths = glob::glob(arg)
+                    .map_err(|e| Error::Invalid(format!("bad pattern `{}`: {}", arg, e)))?;
+                for path in paths {
+                    let path = path.map_err(|e| {
+                        let path = e.path().to_path_buf();
+                        with_path(path, e.into())
+                    })?;
+                    if path.is_dir() {
+                        walk(&path, &mut ret)?;
+                    } else {
+                        ret.push(Input::File(path));
+                    }
+                }
+                if ret.len() == matched {
+                    return Err(Error::Invalid(format!("no files match `{}`", arg)));
+                }
+            } else {
+                ret.push(arg.parse()?);
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Opens the input for reading. Stdin is read completely the first time
+    /// and kept, so that it can be read more than once.
+    ///
+    /// Of a Project Gutenberg file only the book is read, without the header
+    /// and the license, see [`gutenberg::find`]; unless the input is
+    /// [`Input::Verbatim`].
+    pub fn open(&self) -> Result<Box<dyn BufRead>> {
+        if let Input::Verbatim(input) = self {
+            return input.open_verbatim();
+        }
+        let text = self.read_bytes()?;
+        match gutenberg::find(&text) {
+            Some(book) => Ok(Box::new(Cursor::new(text[book.start..book.end].to_vec()))),
+            None => Ok(Box::new(Cursor::new(text))),
+        }
+    }
+
+    fn open_verbatim(&self) -> Result<Box<dyn BufRead>> {
+        match self {
+            Input::Stdin => Ok(Box::new(Cursor::new(stdin()?))),
+            Input::File(path) => fs::File::open(path)
+                .map(|f| Box::new(BufReader::new(f)) as Box<dyn BufRead>)
+                .map_err(|e| with_path(path, e)),
+            Input::Verbatim(input) => input.open_verbatim(),
+        }
+    }
+
+    /// The input as is, e.g. a binary [`crate::store::Table`].
+    pub fn read_bytes(&self) -> Result<Vec<u8>> {
+        let mut ret = Vec::new();
+        self.open_verbatim()?
+            .read_to_end(&mut ret)
+            .map_err(|e| with_path(self.to_string(), e))?;
+        Ok(ret)
+    }
+
+    /// This input, or with `keep` the same read as is, see
+    /// [`Input::Verbatim`].
+    pub fn keeping_boilerplate(self, keep: bool) -> Input {
+        match self {
+            Input::Verbatim(_) => self,
+            _ if keep => Input::Verbatim(Box::new(self)),
+            _ => self,
+        }
+    }
+
+    /// The title, author and language of a Project Gutenberg file, `None`
+    /// for other inputs.
+    pub fn metadata(&self) -> Result<Option<gutenberg::Metadata>> {
+        Ok(gutenberg::find(&self.read_bytes()?).map(|book| book.metadata))
+    }
+
+    /// Number of lines [`Input::open`] leaves out before the book, i.e. the
+    /// Project Gutenberg header; add it to a line number of the text read to
+    /// get the line number in the file.
+    pub fn skipped_lines(&self) -> Result<usize> {
+        if let Input::Verbatim(_) = self {
+            return Ok(0);
+        }
+        let text = self.read_bytes()?;
+        Ok(gutenberg::find(&text).map_or(0, |book| {
+            text[..book.start].iter().filter(|&&b| b == b'\n').count()
+        }))
+    }
+
+    pub fn read_to_string(&self) -> Result<String> {
+        let mut ret = String::new();
+        self.open()?
+            .read_to_string(&mut ret)
+            .map_err(|e| with_path(self.to_string(), e))?;
+        Ok(ret)
+    }
+
+    /// All the inputs one after the other, as if they were a single text.
+    /// A line break is inserted after each one so that the last word of an
+    /// input never runs into the first word of the next.
+    pub fn open_all(inputs: &[Input]) -> Result<Box<dyn BufRead>> {
+        let mut ret: Box<dyn BufRead> = Box::new(io::empty());
+        for input in inputs {
+            ret = Box::new(ret.chain(input.open()?).chain(&b"\n"[..]));
+        }
+        Ok(ret)
+    }
+
+    /// See [`Input::open_all`].
+    pub fn read_all(inputs: &[Input]) -> Result<String> {
+        let mut ret = String::new();
+        for input in inputs {
+            ret.push_str(&input.read_to_string()?);
+            ret.push('\n');
+        }
+        Ok(ret)
+    }
+}
+
+fn stdin() -> Result<&'static [u8]> {
+    static STDIN: OnceLock<Vec<u8>> = OnceLock::new();
+    if let Some(s) = STDIN.get() {
+        return Ok(s);
+    }
+    let mut s = Vec::new();
+    io::stdin().read_to_end(&mut s)?;
+    Ok(STDIN.get_or_init(|| s))
+}
+
+/// Adds every file under `dir` to `inputs`, skipping hidden ones.
+fn walk(dir: &Path, inputs: &mut Vec<Input>) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .and_then(|d| d.collect::<io::Result<Vec<_>>>())
+        .map_err(|e| with_path(dir, e))?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            walk(&path, inputs)?;
+        } else {
+            inputs.push(Input::File(path));
+        }
+    }
+    Ok(())
+}
+
+/// io errors don't tell which file they are about.
+pub fn with_path(path: impl AsRef<Path>, e: io::Error) -> Error {
+    Error::Io(io::Error::new(
+        e.kind(),
+        format!("{}: {}", path.as_ref().display(), e),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of its own for each test, emptied first.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tf-input-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn files(inputs: &[Input], dir: &Path) -> Vec<String> {
+        inputs
+            .iter()
+            .map(|input| match input {
+                Input::File(path) => path.strip_prefix(dir).unwrap().display().to_string(),
+                input => input.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn directories_and_patterns_are_expanded_in_order() {
+        let dir = temp_dir("expand");
+        fs::create_dir_all(dir.join("b/c")).unwrap();
+        for file in ["b/z.txt", "b/c/a.txt", "b/.hidden", "a.md", "b.txt"] {
+            fs::write(dir.join(file), "text").unwrap();
+        }
+        let arg = |s: &str| dir.join(s).display().to_string();
+        let inputs = Input::expand(&[arg("b"), "-".to_string(), arg("*.md")]).unwrap();
+        assert_eq!(
+            files(&inputs[..2], &dir),
+            [
+                Path::new("b/c/a.txt").display().to_string(),
+                Path::new("b/z.txt").display().to_string()
+            ]
+        );
+        assert_eq!(inputs[2], Input::Stdin);
+        assert_eq!(files(&inputs[3..], &dir), ["a.md"]);
+        assert!(Input::expand(&[arg("*.csv")]).is_err());
+        // a name that exists is never a pattern
+        let missing = Input::expand(&[arg("b.txt")]).unwrap();
+        assert_eq!(files(&missing, &dir), ["b.txt"]);
+    }
+
+    #[test]
+    fn inputs_read_together_are_separated_by_a_line_break() {
+        let dir = temp_dir("open-all");
+        fs::write(dir.join("a.txt"), "first").unwrap();
+        fs::write(dir.join("b.txt"), "second").unwrap();
+        let inputs = Input::expand(&[dir.display().to_string()]).unwrap();
+        assert_eq!(Input::read_all(&inputs).unwrap(), "first\nsecond\n");
+        let mut text = String::new();
+        Input::open_all(&inputs)
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "first\nsecond\n");
+        assert!(Input::File(dir.join("none")).open().is_err());
+    }
+
+    #[test]
+    fn gutenberg_boilerplate_is_left_out_unless_verbatim() {
+        let dir = temp_dir("gutenberg");
+        let path = dir.join("emma.txt");
+        fs::write(
+            &path,
+            "Title: Emma\nAuthor: Jane Austen\n\n\
+             *** START OF THE PROJECT GUTENBERG EBOOK EMMA ***\nEmma Woodhouse\n\
+             *** END OF THE PROJECT GUTENBERG EBOOK EMMA ***\nlicense\n",
+        )
+        .unwrap();
+        let input = Input::File(path);
+        assert_eq!(input.read_to_string().unwrap(), "Emma Woodhouse\n");
+        assert_eq!(input.skipped_lines().unwrap(), 4);
+        let title = input.metadata().unwrap().and_then(|m| m.title);
+        assert_eq!(title.as_deref(), Some("Emma"));
+
+        let verbatim = input.keeping_boilerplate(true);
+        assert!(verbatim
+            .read_to_string()
+            .unwrap()
+            .starts_with("Title: Emma"));
+        assert_eq!(verbatim.skipped_lines().unwrap(), 0);
+    }
+}
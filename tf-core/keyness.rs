@@ -0,0 +1,239 @@
+use crate::{Error, FrequencyTable, Result};
+use std::{cmp::Ordering, str::FromStr};
+
+/// How keywords are ranked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Measure {
+    /// Dunning's log-likelihood, G²: how sure it is that the word is used
+    /// more in one corpus than in the other.
+    #[default]
+    LogLikelihood,
+    /// Pearson's chi-squared, also a significance test.
+    ChiSquared,
+    /// The effect size: how much more the word is used, however rare.
+    LogRatio,
+}
+
+impl FromStr for Measure {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "log-likelihood" | "g2" => Ok(Measure::LogLikelihood),
+            "chi-squared" | "chi2" => Ok(Measure::ChiSquared),
+            "log-ratio" => Ok(Measure::LogRatio),
+            _ => Err(Error::Invalid(format!(
+                "unknown measure `{}`, expected log-likelihood, chi-squared or log-ratio",
+                s
+            ))),
+        }
+    }
+}
+
+/// How differently a word is used in a target corpus and in a reference
+/// corpus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyword {
+    pub word: String,
+    /// Count in the target corpus.
+    pub target: usize,
+    /// Count in the reference corpus.
+    pub reference: usize,
+    /// Occurrences per million words of the target corpus.
+    pub target_per_million: f64,
+    pub reference_per_million: f64,
+    pub log_likelihood: f64,
+    pub chi_squared: f64,
+    /// Binary log of the ratio of the relative frequencies: 1 is twice as
+    /// frequent in the target, -1 twice as frequent in the reference. A
+    /// count of 0 is taken as 0.5, so that the ratio stays finite.
+    pub log_ratio: f64,
+}
+
+impl Keyword {
+    /// Relatively more frequent in the target than in the reference.
+    pub fn is_overused(&self) -> bool {
+        self.log_ratio > 0.0
+    }
+
+    /// The value of `measure`, larger is more distinctive. The log ratio is
+    /// taken as is, so the most overused words come first.
+    pub fn score(&self, measure: Measure) -> f64 {
+        match measure {
+            Measure::LogLikelihood => self.log_likelihood,
+            Measure::ChiSquared => self.chi_squared,
+            Measure::LogRatio => self.log_ratio,
+        }
+    }
+}
+
+/// Every word of either table, first-seen order of the target then of the
+/// reference.
+pub fn compare(target: &FrequencyTable, reference: &FrequencyTable) -> Vec<Keyword> {
+    let (c, d) = (target.total() as f64, reference.total() as f64);
+    let words = target.iter().map(|(w, _c)| w).chain(
+        reference
+            .iter()
+            .map(|(w, _c)| w)
+            .filter(|w| target.count(w) == 0),
+    );
+    words
+        .map(|word| {
+            let (a, b) = (target.count(word), reference.count(word));
+            let (af, bf) = (a as f64, b as f64);
+            // expected counts if the word were as frequent in both
+            let e1 = c * (af + bf) / (c + d);
+            let e2 = d * (af + bf) / (c + d);
+            let log_likelihood = 2.0 * (x_ln_x_over(af, e1) + x_ln_x_over(bf, e2));
+            // the 2x2 table: the word and every other word, in each corpus
+            let chi_squared = {
+                let (other_a, other_b) = (c - af, d - bf);
+                let denominator = (af + bf) * (other_a + other_b) * c * d;
+                if denominator == 0.0 {
+                    0.0
+                } else {
+                    let diff = af * other_b - bf * other_a;
+                    (c + d) * diff * diff / denominator
+                }
+            };
+            let smooth = |n: usize| if n == 0 { 0.5 } else { n as f64 };
+            Keyword {
+                word: word.to_string(),
+                target: a,
+                reference: b,
+                target_per_million: per_million(a, c),
+                reference_per_million: per_million(b, d),
+                log_likelihood,
+                chi_squared,
+                log_ratio: ((smooth(a) / c.max(1.0)) / (smooth(b) / d.max(1.0))).log2(),
+            }
+        })
+        .collect()
+}
+
+/// The keywords overused in the target, and those overused in the reference,
+/// most distinctive first by `measure`, ties by overall count then
+/// alphabetically.
+pub fn rank(keywords: Vec<Keyword>, measure: Measure) -> (Vec<Keyword>, Vec<Keyword>) {
+    let (mut over, mut under): (Vec<Keyword>, Vec<Keyword>) = keywords
+        .into_iter()
+        .filter(|k| k.log_ratio != 0.0)
+        .partition(Keyword::is_overused);
+    let order = |sign: f64| {
+        move |k1: &Keyword, k2: &Keyword| {
+            let (s1, s2) = (sign * k1.score(measure), sign * k2.score(measure));
+            s2.partial_cmp(&s1)
+                .unwrap_or(Ordering::Equal)
+                .then((k2.target + k2.reference).cmp(&(k1.target + k1.reference)))
+                .then(k1.word.cmp(&k2.word))
+        }
+    };
+    over.sort_by(order(1.0));
+    // the log ratio of words overused in the reference is negative
+    let sign = if measure == Measure::LogRatio {
+        -1.0
+    } else {
+        1.0
+    };
+    under.sort_by(order(sign));
+    (over, under)
+}
+
+/// `x ln(x / e)`, 0 when `x` is.
+pub(crate) fn x_ln_x_over(x: f64, e: f64) -> f64 {
+    if x == 0.0 {
+        0.0
+    } else {
+        x * (x / e).ln()
+    }
+}
+
+fn per_million(count: usize, total: f64) -> f64 {
+    if total == 0.0 {
+        0.0
+    } else {
+        count as f64 * 1_000_000.0 / total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `n` times each word, then `filler` other words up to `total`.
+    fn table(words: &[(&str, usize)], total: usize) -> FrequencyTable {
+        let mut ret = FrequencyTable::new();
+        for &(word, n) in words {
+            for _ in 0..n {
+                ret.add(word.to_string());
+            }
+        }
+        while ret.total() < total {
+            ret.add("filler".to_string());
+        }
+        ret
+    }
+
+    fn keyword<'a>(keywords: &'a [Keyword], word: &str) -> &'a Keyword {
+        keywords.iter().find(|k| k.word == word).unwrap()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "{} instead of {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn known_values() {
+        // 10 in 1000 words against 5 in 1000
+        let keywords = compare(
+            &table(&[("pride", 10)], 1000),
+            &table(&[("pride", 5)], 1000),
+        );
+        let pride = keyword(&keywords, "pride");
+        assert_close(pride.log_likelihood, 1.698990);
+        assert_close(pride.chi_squared, 1.679261);
+        assert_close(pride.log_ratio, 1.0);
+        assert_close(pride.target_per_million, 10_000.0);
+        assert!(pride.is_overused());
+    }
+
+    #[test]
+    fn word_missing_from_the_reference() {
+        let keywords = compare(&table(&[("darcy", 4)], 100), &table(&[], 100));
+        let darcy = keyword(&keywords, "darcy");
+        assert_close(darcy.log_likelihood, 8.0 * 2f64.ln());
+        // counted as half an occurrence
+        assert_close(darcy.log_ratio, 3.0);
+        assert_eq!(darcy.reference, 0);
+    }
+
+    #[test]
+    fn same_frequency_is_not_key() {
+        let keywords = compare(&table(&[("the", 50)], 1000), &table(&[("the", 100)], 2000));
+        let the = keyword(&keywords, "the");
+        assert_close(the.log_likelihood, 0.0);
+        assert_close(the.chi_squared, 0.0);
+        let (over, under) = rank(keywords, Measure::LogLikelihood);
+        assert!(!over.iter().chain(&under).any(|k| k.word == "the"));
+    }
+
+    #[test]
+    fn ranks_each_side_most_distinctive_first() {
+        let target = table(&[("darcy", 20), ("pride", 8), ("war", 1)], 1000);
+        let reference = table(&[("darcy", 1), ("pride", 4), ("war", 30)], 1000);
+        for measure in [
+            Measure::LogLikelihood,
+            Measure::ChiSquared,
+            Measure::LogRatio,
+        ] {
+            let (over, under) = rank(compare(&target, &reference), measure);
+            let words = |k: &[Keyword]| k.iter().map(|k| k.word.clone()).collect::<Vec<_>>();
+            assert_eq!(words(&over)[..2], ["darcy", "pride"], "{:?}", measure);
+            assert_eq!(words(&under)[0], "war", "{:?}", measure);
+        }
+    }
+}
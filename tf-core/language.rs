@@ -0,0 +1,229 @@
+use std::{cmp::Reverse, collections::HashMap, fmt, sync::OnceLock};
+
+/// A language the identifier knows, with a sample of ordinary prose it
+/// learns the language from.
+#[derive(Debug)]
+pub struct Language {
+    /// ISO 639-1, e.g. `fr`; also the name of its stop word list, see
+    /// [`crate::StopWords::language_sources`].
+    pub code: &'static str,
+    pub name: &'static str,
+    /// Words written short before an apostrophe and the next word, e.g. `l`
+    /// in "l'homme", see [`crate::Tokenizer::with_elisions`].
+    pub elisions: &'static [&'static str],
+    sample: &'static str,
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.code)
+    }
+}
+
+/// Every language [`detect`] tells apart.
+pub const LANGUAGES: &[Language] = &[
+    Language {
+        code: "en",
+        name: "English",
+        elisions: &[],
+        sample: include_str!("language_en.txt"),
+    },
+    Language {
+        code: "fr",
+        name: "French",
+        elisions: &[
+            "c", "d", "j", "l", "m", "n", "s", "t", "qu", "jusqu", "lorsqu", "puisqu", "quoiqu",
+        ],
+        sample: include_str!("language_fr.txt"),
+    },
+    Language {
+        code: "de",
+        name: "German",
+        elisions: &[],
+        sample: include_str!("language_de.txt"),
+    },
+];
+
+/// The most frequent trigrams of a text kept to compare it.
+const PROFILE_SIZE: usize = 300;
+/// Fewer distinct trigrams than this say too little about a text.
+const MIN_TRIGRAMS: usize = 30;
+
+/// Which language a text is in, and how sure that is.
+#[derive(Debug, Clone)]
+pub struct Detection {
+    pub language: &'static Language,
+    /// From 0, when the runner-up fits as well, to 1, when no other language
+    /// fits at all; 0.5 when the language fits twice as well as the
+    /// runner-up.
+    pub confidence: f64,
+    /// The distance of the text to each language, from 0 (same trigrams in
+    /// the same order) to 1 (no trigram in common), closest first.
+    pub distances: Vec<(&'static Language, f64)>,
+}
+
+/// Trigrams by rank, most frequent first.
+type Profile = HashMap<String, usize>;
+
+/// The language of `text`, by its character trigrams: the most frequent
+/// ones, in order, are compared to those of each language's sample with the
+/// out-of-place measure of Cavnar and Trenkle. `None` when `text` is too
+/// short to tell.
+///
+/// ```
+/// use tf_core::language;
+/// let text = "Il y avait une fois un roi et une reine qui étaient bien fâchés \
+///     de n'avoir point d'enfants, si fâchés qu'on ne saurait dire.";
+/// assert_eq!(language::detect(text).unwrap().language.code, "fr");
+/// ```
+pub fn detect(text: &str) -> Option<Detection> {
+    let ranked = ranked_trigrams(text);
+    if ranked.len() < MIN_TRIGRAMS {
+        return None;
+    }
+    let mut distances: Vec<(&'static Language, f64)> = LANGUAGES
+        .iter()
+        .zip(profiles())
+        .map(|(language, profile)| (language, distance(&ranked, profile)))
+        .collect();
+    distances.sort_by(|(_l1, d1), (_l2, d2)| d1.total_cmp(d2));
+    // how well each fits, from 0 to 1
+    let best = 1.0 - distances[0].1;
+    let confidence = match distances.get(1) {
+        Some(&(_language, second)) if best > 0.0 => 1.0 - (1.0 - second) / best,
+        _ => 1.0,
+    };
+    Some(Detection {
+        language: distances[0].0,
+        confidence,
+        distances,
+    })
+}
+
+/// The language with this code, see [`Language::code`].
+pub fn find(code: &str) -> Option<&'static Language> {
+    LANGUAGES.iter().find(|l| l.code == code)
+}
+
+fn profiles() -> &'static [Profile] {
+    static PROFILES: OnceLock<Vec<Profile>> = OnceLock::new();
+    PROFILES.get_or_init(|| {
+        LANGUAGES
+            .iter()
+            .map(|language| {
+                ranked_trigrams(language.sample)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(rank, trigram)| (trigram, rank))
+                    .collect()
+            })
+            .collect()
+    })
+}
+
+/// The [`PROFILE_SIZE`] most frequent trigrams of the lowercase words of
+/// `text`, each word padded with a space on both sides so that beginnings
+/// and ends of words count, ties alphabetically.
+fn ranked_trigrams(text: &str) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for word in text.split(|c: char| !c.is_alphabetic()) {
+        if word.is_empty() {
+            continue;
+        }
+        let padded: Vec<char> = std::iter::once(' ')
+            .chain(word.chars().flat_map(char::to_lowercase))
+            .chain(std::iter::once(' '))
+            .collect();
+        for trigram in padded.windows(3) {
+            *counts.entry(trigram.iter().collect()).or_insert(0) += 1;
+        }
+    }
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|(t1, c1), (t2, c2)| (Reverse(c1), t1).cmp(&(Reverse(c2), t2)));
+    ranked
+        .into_iter()
+        .take(PROFILE_SIZE)
+        .map(|(trigram, _count)| trigram)
+        .collect()
+}
+
+/// How far the ranks of `ranked` are from those of `profile`, a trigram
+/// missing from the profile counting as the farthest, scaled to 0..=1.
+fn distance(ranked: &[String], profile: &Profile) -> f64 {
+    let total: usize = ranked
+        .iter()
+        .enumerate()
+        .map(|(rank, trigram)| match profile.get(trigram) {
+            Some(&other) => rank.abs_diff(other),
+            None => PROFILE_SIZE,
+        })
+        .sum();
+    total as f64 / (ranked.len() * PROFILE_SIZE) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENGLISH: &str = "It is a truth universally acknowledged, that a single man in \
+        possession of a good fortune, must be in want of a wife.";
+    const FRENCH: &str = "Il y avait une fois un roi et une reine qui étaient bien fâchés \
+        de n'avoir point d'enfants, si fâchés qu'on ne saurait dire.";
+    const GERMAN: &str = "Als Gregor Samsa eines Morgens aus unruhigen Träumen erwachte, \
+        fand er sich in seinem Bett zu einem ungeheueren Ungeziefer verwandelt.";
+
+    #[test]
+    fn sentences() {
+        for (text, code) in [(ENGLISH, "en"), (FRENCH, "fr"), (GERMAN, "de")] {
+            let detection = detect(text).unwrap();
+            assert_eq!(detection.language.code, code);
+            assert!(detection.confidence > 0.0 && detection.confidence <= 1.0);
+            assert_eq!(detection.distances.len(), LANGUAGES.len());
+            assert_eq!(detection.distances[0].0.code, code);
+        }
+    }
+
+    #[test]
+    fn too_short_to_tell() {
+        for text in ["", "the", "Bonjour, madame.", "Guten Tag", "1984 -- 2024!"] {
+            assert!(detect(text).is_none(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn samples_are_their_own_language() {
+        for (language, profile) in LANGUAGES.iter().zip(profiles()) {
+            assert_eq!(distance(&ranked_trigrams(language.sample), profile), 0.0);
+            assert_eq!(
+                detect(language.sample).unwrap().language.code,
+                language.code
+            );
+        }
+    }
+
+    #[test]
+    fn mixed_text_is_less_sure() {
+        let mixed = format!("{} {}", ENGLISH, FRENCH);
+        let detection = detect(&mixed).unwrap();
+        assert!(["en", "fr"].contains(&detection.language.code));
+        assert!(detection.confidence < detect(ENGLISH).unwrap().confidence);
+        assert!(detection.confidence < detect(FRENCH).unwrap().confidence);
+        // German fits worst
+        assert_eq!(detection.distances[2].0.code, "de");
+    }
+
+    #[test]
+    fn trigrams_are_padded_lowercase_words() {
+        assert_eq!(ranked_trigrams("Ab, ab!"), [" ab", "ab "]);
+        // ties by their bytes
+        assert_eq!(ranked_trigrams("Été"), [" ét", "té ", "été"]);
+        assert!(ranked_trigrams("42 ... --").is_empty());
+    }
+
+    #[test]
+    fn find_by_code() {
+        assert_eq!(find("de").unwrap().name, "German");
+        assert_eq!(find("de").unwrap().to_string(), "German (de)");
+        assert!(find("xx").is_none());
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashSet;
+
+/// A straight line fitted by least squares.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fit {
+    pub slope: f64,
+    pub intercept: f64,
+    /// Share of the variance of `y` the line explains, 1 for a perfect fit.
+    pub r_squared: f64,
+    /// Root mean square of the residuals.
+    pub rms_error: f64,
+}
+
+impl Fit {
+    /// `None` with fewer than two distinct `x`.
+    pub fn least_squares(points: &[(f64, f64)]) -> Option<Fit> {
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+        let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+        let syy: f64 = points.iter().map(|p| (p.1 - mean_y).powi(2)).sum();
+        if points.len() < 2 || sxx == 0.0 {
+            return None;
+        }
+        let slope = sxy / sxx;
+        let intercept = mean_y - slope * mean_x;
+        let sse: f64 = points
+            .iter()
+            .map(|p| (p.1 - intercept - slope * p.0).powi(2))
+            .sum();
+        Some(Fit {
+            slope,
+            intercept,
+            r_squared: if syy == 0.0 { 1.0 } else { 1.0 - sse / syy },
+            rms_error: (sse / n).sqrt(),
+        })
+    }
+
+    pub fn at(&self, x: f64) -> f64 {
+        self.intercept + self.slope * x
+    }
+}
+
+/// Zipf's law: the word of rank `r` is counted about `constant / r^exponent`
+/// times, with an exponent close to 1 in most texts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zipf {
+    pub exponent: f64,
+    /// The count expected of the most frequent word.
+    pub constant: f64,
+    /// The line through `(ln rank, ln count)`.
+    pub fit: Fit,
+}
+
+impl Zipf {
+    /// Fits the counts of a whole ranking, most frequent first.
+    ///
+    /// ```
+    /// use tf_core::laws::Zipf;
+    /// let zipf = Zipf::fit(&[60, 30, 20, 15, 12, 10]).unwrap();
+    /// assert!((zipf.exponent - 1.0).abs() < 1e-9);
+    /// assert!((zipf.expected(4) - 15.0).abs() < 1e-9);
+    /// ```
+    pub fn fit(counts: &[usize]) -> Option<Zipf> {
+        let points: Vec<(f64, f64)> = counts
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| (((i + 1) as f64).ln(), (c as f64).ln()))
+            .collect();
+        let fit = Fit::least_squares(&points)?;
+        Some(Zipf {
+            exponent: -fit.slope,
+            constant: fit.intercept.exp(),
+            fit,
+        })
+    }
+
+    /// The count the law expects at `rank`, counting from 1.
+    pub fn expected(&self, rank: usize) -> f64 {
+        self.fit.at((rank as f64).ln()).exp()
+    }
+}
+
+/// Heaps' law: after `n` words, about `k n^beta` of them are distinct, with
+/// `beta` usually between 0.4 and 0.6 in English.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Heaps {
+    pub k: f64,
+    pub beta: f64,
+    /// The line through `(ln words, ln distinct words)`.
+    pub fit: Fit,
+}
+
+impl Heaps {
+    /// Fits `(words read, distinct words)` points, see [`Growth`].
+    pub fn fit(points: &[(usize, usize)]) -> Option<Heaps> {
+        let points: Vec<(f64, f64)> = points
+            .iter()
+            .map(|&(n, v)| ((n as f64).ln(), (v as f64).ln()))
+            .collect();
+        let fit = Fit::least_squares(&points)?;
+        Some(Heaps {
+            k: fit.intercept.exp(),
+            beta: fit.slope,
+            fit,
+        })
+    }
+
+    /// The vocabulary the law expects after `words` words.
+    pub fn expected(&self, words: usize) -> f64 {
+        self.fit.at((words as f64).ln()).exp()
+    }
+}
+
+/// How many distinct words have been seen as words stream in, sampled at
+/// points evenly spaced on a log scale, so that the start of the text
+/// weighs as much as the rest in a fit.
+#[derive(Debug, Clone, Default)]
+pub struct Growth {
+    seen: HashSet<String>,
+    words: usize,
+    /// When to take the next point.
+    next: usize,
+    points: Vec<(usize, usize)>,
+}
+
+/// Each point is this much further in the text than the previous one.
+const GROWTH_STEP: f64 = 1.05;
+
+impl Growth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, word: String) {
+        self.words += 1;
+        self.seen.insert(word);
+        if self.words >= self.next {
+            self.points.push((self.words, self.seen.len()));
+            self.next = ((self.words as f64 * GROWTH_STEP).ceil() as usize).max(self.words + 1);
+        }
+    }
+
+    /// Words read so far.
+    pub fn words(&self) -> usize {
+        self.words
+    }
+
+    /// Distinct words read so far.
+    pub fn vocabulary(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// `(words read, distinct words)`, up to the last word read.
+    pub fn points(&self) -> Vec<(usize, usize)> {
+        let mut points = self.points.clone();
+        if points.last().map(|p| p.0) != Some(self.words) && self.words > 0 {
+            points.push((self.words, self.seen.len()));
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_line_needs_two_distinct_x() {
+        assert_eq!(Fit::least_squares(&[(1.0, 2.0)]), None);
+        assert_eq!(Fit::least_squares(&[(1.0, 2.0), (1.0, 3.0)]), None);
+        let fit = Fit::least_squares(&[(0.0, 1.0), (1.0, 3.0), (2.0, 5.0)]).unwrap();
+        assert!((fit.slope - 2.0).abs() < 1e-9);
+        assert!((fit.r_squared - 1.0).abs() < 1e-9);
+        assert!(fit.rms_error < 1e-9);
+        assert!((fit.at(3.0) - 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn heaps_of_a_square_root_vocabulary() {
+        let points: Vec<(usize, usize)> = [1, 4, 9, 16, 25]
+            .iter()
+            .map(|&n| (n, (n as f64).sqrt() as usize))
+            .collect();
+        let heaps = Heaps::fit(&points).unwrap();
+        assert!((heaps.beta - 0.5).abs() < 1e-9);
+        assert!((heaps.k - 1.0).abs() < 1e-9);
+        assert!((heaps.expected(100) - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn growth_samples_up_to_the_last_word() {
+        let mut growth = Growth::new();
+        assert!(growth.points().is_empty());
+        for word in "a b a c a b d a".split(' ') {
+            growth.push(word.to_string());
+        }
+        assert_eq!(growth.words(), 8);
+        assert_eq!(growth.vocabulary(), 4);
+        let points = growth.points();
+        assert_eq!(points.first(), Some(&(1, 1)));
+        assert_eq!(points.last(), Some(&(8, 4)));
+        assert!(points.windows(2).all(|p| p[0].0 < p[1].0));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::FrequencyTable;
+
+/// How often each of the letters `a` to `z`, and each pair of adjacent
+/// letters, appears in a text, regardless of case. Other characters are
+/// skipped and no pair spans them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LetterFrequencies {
+    letters: [usize; 26],
+    /// `26 * first + second`
+    bigrams: Vec<usize>,
+}
+
+impl Default for LetterFrequencies {
+    fn default() -> Self {
+        Self {
+            letters: [0; 26],
+            bigrams: vec![0; 26 * 26],
+        }
+    }
+}
+
+impl LetterFrequencies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_text(text: &str) -> Self {
+        let mut ret = Self::new();
+        ret.add_text(text);
+        ret
+    }
+
+    pub fn add_text(&mut self, text: &str) {
+        let mut prev = None;
+        for ch in text.chars() {
+            let i = letter_index(ch);
+            if let Some(i) = i {
+                self.letters[i] += 1;
+                if let Some(p) = prev {
+                    self.bigrams[26 * p + i] += 1;
+                }
+            }
+            prev = i;
+        }
+    }
+
+    /// Number of letters.
+    pub fn total(&self) -> usize {
+        self.letters.iter().sum()
+    }
+
+    /// Number of letter pairs.
+    pub fn bigram_total(&self) -> usize {
+        self.bigrams.iter().sum()
+    }
+
+    pub fn count(&self, letter: char) -> usize {
+        letter_index(letter).map_or(0, |i| self.letters[i])
+    }
+
+    pub fn bigram_count(&self, first: char, second: char) -> usize {
+        match (letter_index(first), letter_index(second)) {
+            (Some(a), Some(b)) => self.bigrams[26 * a + b],
+            _ => 0,
+        }
+    }
+
+    /// Pearson's chi-squared statistic of the letters of `observed` against
+    /// the letter frequencies of this table: the lower, the more `observed`
+    /// looks like this text.
+    pub fn chi_squared(&self, observed: &LetterFrequencies) -> f64 {
+        let n = observed.total() as f64;
+        (0..26)
+            .map(|i| {
+                let expected = n * self.letter_share(i);
+                let diff = observed.letters[i] as f64 - expected;
+                diff * diff / expected
+            })
+            .sum()
+    }
+
+    /// Mean log-probability of the letter pairs of `observed` under the pair
+    /// frequencies of this table: the higher, the more `observed` looks like
+    /// this text. 0 when `observed` has no pairs.
+    pub fn bigram_fitness(&self, observed: &LetterFrequencies) -> f64 {
+        let n = observed.bigram_total();
+        if n == 0 {
+            return 0.0;
+        }
+        let log_likelihood: f64 = (0..26 * 26)
+            .filter(|&i| observed.bigrams[i] > 0)
+            .map(|i| observed.bigrams[i] as f64 * self.bigram_share(i).ln())
+            .sum();
+        log_likelihood / n as f64
+    }
+
+    /// The letters as words of a table, alphabetically.
+    pub fn letters(&self) -> FrequencyTable {
+        (0..26)
+            .filter(|&i| self.letters[i] > 0)
+            .map(|i| (letter(i).to_string(), self.letters[i]))
+            .collect()
+    }
+
+    /// The letter pairs as words of a table, alphabetically.
+    pub fn bigrams(&self) -> FrequencyTable {
+        (0..26 * 26)
+            .filter(|&i| self.bigrams[i] > 0)
+            .map(|i| {
+                (
+                    format!("{}{}", letter(i / 26), letter(i % 26)),
+                    self.bigrams[i],
+                )
+            })
+            .collect()
+    }
+
+    /// Add-one smoothed, so that letters missing from a small reference
+    /// don't make every text impossible.
+    fn letter_share(&self, i: usize) -> f64 {
+        (self.letters[i] as f64 + 1.0) / (self.total() as f64 + 26.0)
+    }
+
+    fn bigram_share(&self, i: usize) -> f64 {
+        (self.bigrams[i] as f64 + 1.0) / (self.bigram_total() as f64 + 26.0 * 26.0)
+    }
+}
+
+/// 0 for 'a' or 'A' up to 25 for 'z' or 'Z', `None` for anything else.
+pub fn letter_index(ch: char) -> Option<usize> {
+    if ch.is_ascii_alphabetic() {
+        Some((ch.to_ascii_lowercase() as u8 - b'a') as usize)
+    } else {
+        None
+    }
+}
+
+/// The lowercase letter at `index`, see [`letter_index`].
+pub fn letter(index: usize) -> char {
+    (b'a' + index as u8) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_and_pairs_are_counted_without_case() {
+        let frequencies = LetterFrequencies::from_text("Abba, ab!");
+        assert_eq!(frequencies.total(), 6);
+        assert_eq!(frequencies.count('a'), 3);
+        assert_eq!(frequencies.count('B'), 3);
+        // "ab", "bb", "ba" and "ab": none across the comma
+        assert_eq!(frequencies.bigram_total(), 4);
+        assert_eq!(frequencies.bigram_count('a', 'b'), 2);
+        assert_eq!(frequencies.bigram_count('a', 'a'), 0);
+        let letters: Vec<(String, usize)> = frequencies.letters().into_counts();
+        assert_eq!(letters, [("a".to_string(), 3), ("b".to_string(), 3)]);
+        assert_eq!(frequencies.bigrams().len(), 3);
+    }
+
+    #[test]
+    fn text_like_the_reference_scores_better() {
+        let reference = LetterFrequencies::from_text("the quick brown fox jumps over the lazy dog");
+        let english = LetterFrequencies::from_text("the other fox");
+        let noise = LetterFrequencies::from_text("qzx jvq zzk");
+        assert!(reference.chi_squared(&english) < reference.chi_squared(&noise));
+        assert!(reference.bigram_fitness(&english) > reference.bigram_fitness(&noise));
+        assert_eq!(reference.bigram_fitness(&LetterFrequencies::new()), 0.0);
+    }
+
+    #[test]
+    fn letters_are_indexed_from_a() {
+        assert_eq!(letter_index('a'), Some(0));
+        assert_eq!(letter_index('Z'), Some(25));
+        assert_eq!(letter_index('é'), None);
+        assert_eq!(letter(25), 'z');
+    }
+}
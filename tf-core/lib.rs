@@ -0,0 +1,50 @@
+//! tf-core
+//! ==============================
+//! The building blocks shared by every style: a tokenizer, a stop-word set,
+//! an optional stemmer, a frequency table and a top-N ranking. Each style
+//! keeps its own way of wiring the steps together, but they all agree on what
+//! a word is and how the result is ordered.
+
+pub mod cache;
+pub mod chart;
+pub mod collocations;
+pub mod config;
+pub mod contractions;
+pub mod diversity;
+pub mod error;
+pub mod frequency;
+pub mod gutenberg;
+pub mod input;
+pub mod keyness;
+pub mod language;
+pub mod laws;
+pub mod letters;
+pub mod mapping;
+pub mod names;
+pub mod ngrams;
+pub mod output;
+pub mod ranking;
+pub mod segments;
+pub mod stemmer;
+pub mod stop_words;
+pub mod store;
+pub mod tokenizer;
+
+// the examples of the README are tested with the others
+#[doc = include_str!("../README.md")]
+#[cfg(doctest)]
+pub struct ReadmeDoctests;
+
+pub use chart::Chart;
+pub use config::Config;
+pub use error::{Error, Result};
+pub use frequency::FrequencyTable;
+pub use input::Input;
+pub use letters::LetterFrequencies;
+pub use mapping::{CharMap, Mapping};
+pub use ngrams::Ngrams;
+pub use output::{Format, Report};
+pub use ranking::{Page, Ranking};
+pub use stemmer::{Stemmer, Stemming};
+pub use stop_words::StopWords;
+pub use tokenizer::Tokenizer;
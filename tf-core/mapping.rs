@@ -0,0 +1,303 @@
+use crate::{Error, Result};
+use std::{
+    collections::HashMap,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// The leet table of `week9/week9.py`, for lowercase words.
+const LEET: &str = "\
+a 4
+b 8
+c (
+i 1
+n И
+o 0
+r Я
+s 5
+t 7
+z 2
+";
+
+/// Letters outside ASCII, spelled with ASCII letters.
+const ASCII: &str = "\
+à a
+á a
+â a
+ã a
+ä a
+å a
+ā a
+ą a
+æ ae
+ç c
+ć c
+č c
+ď d
+ð d
+è e
+é e
+ê e
+ë e
+ē e
+ę e
+ě e
+ğ g
+ì i
+í i
+î i
+ï i
+ī i
+ı i
+ĳ ij
+ł l
+ñ n
+ń n
+ň n
+ò o
+ó o
+ô o
+õ o
+ö o
+ø o
+ō o
+ő o
+œ oe
+ř r
+ß ss
+ś s
+š s
+ş s
+ș s
+ť t
+ţ t
+ț t
+þ th
+ù u
+ú u
+û u
+ü u
+ū u
+ů u
+ű u
+ý y
+ÿ y
+ź z
+ż z
+ž z
+";
+
+/// Where a character table comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// `leet`, see [`CharMap::leet`].
+    Leet,
+    /// `ascii`, see [`CharMap::ascii`].
+    Ascii,
+    File(PathBuf),
+}
+
+impl FromStr for Source {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "" => Err(Error::Invalid("empty mapping source".to_string())),
+            "leet" => Ok(Source::Leet),
+            "ascii" => Ok(Source::Ascii),
+            path => Ok(Source::File(path.into())),
+        }
+    }
+}
+
+/// When words are mapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Stage {
+    /// Before stop-word filtering, so stop words are matched on mapped words.
+    Before,
+    /// After stop-word filtering, only changing how words are reported.
+    #[default]
+    After,
+}
+
+impl FromStr for Stage {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "before" => Ok(Stage::Before),
+            "after" => Ok(Stage::After),
+            _ => Err(Error::Invalid(format!(
+                "unknown mapping stage `{}`, expected before or after",
+                s
+            ))),
+        }
+    }
+}
+
+/// Replaces single characters with strings, e.g. 'ß' with "ss".
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CharMap {
+    rules: HashMap<char, String>,
+}
+
+impl CharMap {
+    /// One rule per line: a character, whitespace and what it becomes.
+    /// Nothing after the character deletes it. Empty lines and lines
+    /// starting with `#` are skipped.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut rules = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut chars = line.chars();
+            let from = chars.next().unwrap_or_default();
+            let rest = chars.as_str();
+            if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+                return Err(Error::Invalid(format!(
+                    "line {}: expected a single character, then what it becomes",
+                    i + 1
+                )));
+            }
+            rules.insert(from, rest.trim().to_string());
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|e| Error::Invalid(format!("{}: {}", path.display(), e)))
+    }
+
+    /// The leet table of style #9: `a` becomes `4`, `e` stays, `t` becomes `7`...
+    pub fn leet() -> Self {
+        Self::parse(LEET).expect("built-in leet table is valid")
+    }
+
+    /// Accented and other non-ASCII Latin letters as ASCII, e.g. "æ" as "ae".
+    pub fn ascii() -> Self {
+        Self::parse(ASCII).expect("built-in ASCII table is valid")
+    }
+
+    pub fn load(source: &Source) -> Result<Self> {
+        match source {
+            Source::Leet => Ok(Self::leet()),
+            Source::Ascii => Ok(Self::ascii()),
+            Source::File(path) => Self::from_file(path),
+        }
+    }
+
+    pub fn apply(&self, word: &str) -> String {
+        let mut ret = String::with_capacity(word.len());
+        for ch in word.chars() {
+            match self.rules.get(&ch) {
+                Some(to) => ret.push_str(to),
+                None => ret.push(ch),
+            }
+        }
+        ret
+    }
+
+    /// Number of rules.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// The tables given with `--map`, applied one after the other, and the
+/// stage at which they apply. Without tables every word is kept as is.
+#[derive(Debug, Clone, Default)]
+pub struct Mapping {
+    maps: Vec<CharMap>,
+    stage: Stage,
+}
+
+/// The same for the same rules, whatever their order in the `HashMap`.
+impl Hash for Mapping {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for map in &self.maps {
+            let mut rules: Vec<_> = map.rules.iter().collect();
+            rules.sort();
+            rules.hash(state);
+        }
+        self.stage.hash(state);
+    }
+}
+
+impl Mapping {
+    pub fn new(maps: Vec<CharMap>, stage: Stage) -> Self {
+        Self { maps, stage }
+    }
+
+    pub fn load(sources: &[Source], stage: Stage) -> Result<Self> {
+        let maps = sources.iter().map(CharMap::load).collect::<Result<_>>()?;
+        Ok(Self::new(maps, stage))
+    }
+
+    pub fn apply(&self, word: String) -> String {
+        self.maps.iter().fold(word, |word, map| map.apply(&word))
+    }
+
+    /// For the step before stop-word filtering.
+    pub fn before(&self, word: String) -> String {
+        match self.stage {
+            Stage::Before => self.apply(word),
+            Stage::After => word,
+        }
+    }
+
+    /// For the step after stop-word filtering.
+    pub fn after(&self, word: String) -> String {
+        match self.stage {
+            Stage::Before => word,
+            Stage::After => self.apply(word),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_replace_or_delete_characters() {
+        let map = CharMap::parse("# comment\n\nß ss\n' \næ  ae\n").unwrap();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.apply("straße"), "strasse");
+        assert_eq!(map.apply("don't"), "dont");
+        assert_eq!(map.apply("æther"), "aether");
+        assert!(CharMap::parse("ss s").is_err());
+    }
+
+    #[test]
+    fn built_in_tables() {
+        assert_eq!(CharMap::leet().apply("pride"), "pЯ1de");
+        assert_eq!(CharMap::ascii().apply("café"), "cafe");
+        assert_eq!("leet".parse::<Source>().unwrap(), Source::Leet);
+        assert_eq!(
+            "table.txt".parse::<Source>().unwrap(),
+            Source::File("table.txt".into())
+        );
+    }
+
+    #[test]
+    fn tables_apply_in_order_at_their_stage() {
+        let maps = vec![
+            CharMap::parse("a b").unwrap(),
+            CharMap::parse("b c").unwrap(),
+        ];
+        let before = Mapping::new(maps.clone(), Stage::Before);
+        assert_eq!(before.before("ab".to_string()), "cc");
+        assert_eq!(before.after("ab".to_string()), "ab");
+        let after = Mapping::new(maps, Stage::After);
+        assert_eq!(after.before("ab".to_string()), "ab");
+        assert_eq!(after.after("ab".to_string()), "cc");
+        assert_eq!(Mapping::default().apply("ab".to_string()), "ab");
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{FrequencyTable, StopWords, Tokenizer};
+use std::collections::{HashMap, HashSet};
+
+/// Words that come before a name, without their period.
+const TITLES: &[&str] = &[
+    "mr",
+    "mrs",
+    "miss",
+    "ms",
+    "master",
+    "dr",
+    "sir",
+    "dame",
+    "lady",
+    "lord",
+    "colonel",
+    "col",
+    "captain",
+    "capt",
+    "major",
+    "general",
+    "gen",
+    "admiral",
+    "aunt",
+    "uncle",
+    "saint",
+    "st",
+    "rev",
+    "reverend",
+    "prof",
+    "professor",
+    "king",
+    "queen",
+    "prince",
+    "princess",
+    "duke",
+    "duchess",
+    "madame",
+    "mme",
+    "mademoiselle",
+    "mlle",
+    "monsieur",
+];
+
+/// Lowercase words that may come inside a name, e.g. Lady Catherine de
+/// Bourgh.
+const PARTICLES: &[&str] = &["de", "du", "da", "di", "le", "la", "van", "von", "der"];
+
+/// A word as written, and where.
+#[derive(Debug)]
+struct Token<'a> {
+    word: &'a str,
+    /// What separates it from the previous word.
+    gap: &'a str,
+}
+
+impl Token<'_> {
+    /// `Bennet` or `McDonald`, but neither `I` nor `CHAPTER`.
+    fn is_capitalized(&self) -> bool {
+        let mut chars = self.word.chars();
+        chars.next().is_some_and(char::is_uppercase) && chars.any(char::is_lowercase)
+    }
+
+    fn is_title(&self) -> bool {
+        self.is_capitalized() && TITLES.contains(&self.word.to_lowercase().as_str())
+    }
+
+    fn is_particle(&self) -> bool {
+        PARTICLES.contains(&self.word)
+    }
+}
+
+/// Whether a gap ends a sentence, or a paragraph: capitalized words right
+/// after it say little.
+fn ends_sentence(gap: &str) -> bool {
+    gap.contains(['.', '!', '?', '"', '“']) || gap.matches('\n').count() > 1
+}
+
+/// Only spaces, and at most one line break: a name may be wrapped.
+fn is_space(gap: &str) -> bool {
+    gap.chars().all(char::is_whitespace) && gap.matches('\n').count() <= 1
+}
+
+/// The names of people and places in `text`, found by their capital
+/// letters, so before any lowercasing. A name is:
+///
+/// - a run of capitalized words, maybe with a title, e.g. `Mr. Darcy`,
+///   `Lady Catherine de Bourgh` or `Netherfield Park`;
+/// - a single capitalized word in the middle of a sentence, unless the word
+///   is more often written in lowercase;
+/// - a single capitalized word at the start of a sentence, if it is a name
+///   elsewhere in the text.
+///
+/// Names are counted as written, titles without their period, e.g.
+/// `Mr Darcy`. Stop words are never names, so `The Bennets` is `Bennets`.
+///
+/// ```
+/// use tf_core::{names, StopWords, Tokenizer};
+/// let text = "Mr. Darcy danced. Elizabeth said to Mr. Darcy that \
+///     Lady Catherine de Bourgh and Elizabeth were not at home.";
+/// let names = names::find(text, &Tokenizer::default(), &StopWords::builtin());
+/// assert_eq!(names.count("Mr Darcy"), 2);
+/// assert_eq!(names.count("Lady Catherine de Bourgh"), 1);
+/// assert_eq!(names.count("Elizabeth"), 2);
+/// ```
+pub fn find(text: &str, tokenizer: &Tokenizer, stop_words: &StopWords) -> FrequencyTable {
+    let mut tokens = Vec::new();
+    let mut last = 0;
+    for (start, word) in tokenizer.segment_indices(text) {
+        // with --strip-possessives, the `'s` of `Darcy's` is in the next
+        // gap, so that no name runs on past it
+        let word = tokenizer.possessive_stem(word);
+        tokens.push(Token {
+            word,
+            gap: &text[last..start],
+        });
+        last = start + word.len();
+    }
+    let is_stop = |token: &Token| stop_words.contains(&token.word.to_lowercase());
+    let mut lowercase: HashMap<&str, usize> = HashMap::new();
+    for token in &tokens {
+        if token.word.chars().all(|c| !c.is_uppercase()) {
+            *lowercase.entry(token.word).or_insert(0) += 1;
+        }
+    }
+
+    // runs of capitalized words, and whether the first starts a sentence
+    let mut runs: Vec<(Vec<&str>, bool)> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if !tokens[i].is_capitalized() {
+            i += 1;
+            continue;
+        }
+        let mut j = i + 1;
+        while j < tokens.len() {
+            let (previous, token) = (&tokens[j - 1], &tokens[j]);
+            let joined = is_space(token.gap)
+                || (previous.is_title() && is_space(token.gap.trim_start_matches('.')));
+            let next_is_capitalized = tokens
+                .get(j + 1)
+                .is_some_and(|next| next.is_capitalized() && is_space(next.gap));
+            if !joined || !(token.is_capitalized() || token.is_particle() && next_is_capitalized) {
+                break;
+            }
+            j += 1;
+        }
+        let initial = i == 0 || ends_sentence(tokens[i].gap);
+        // `The`, `But`... at the start of a sentence
+        let skip = tokens[i..j].iter().take_while(|t| is_stop(t)).count();
+        let run: Vec<&str> = tokens[i + skip..j].iter().map(|t| t.word).collect();
+        let keep = match run.len() {
+            0 => false,
+            // `Sir` alone
+            1 => !tokens[i + skip].is_title(),
+            _ => true,
+        };
+        if keep {
+            runs.push((run, initial && skip == 0));
+        }
+        i = j;
+    }
+
+    // single words written more often in lowercase are no names
+    let mut capitalized: HashMap<&str, usize> = HashMap::new();
+    for (run, _initial) in &runs {
+        for word in run {
+            *capitalized.entry(word).or_insert(0) += 1;
+        }
+    }
+    let is_common = |word: &str| {
+        lowercase
+            .get(word.to_lowercase().as_str())
+            .copied()
+            .unwrap_or(0)
+            >= capitalized.get(word).copied().unwrap_or(0)
+    };
+    let known: HashSet<&str> = runs
+        .iter()
+        .filter(|(run, initial)| run.len() > 1 || !initial && !is_common(run[0]))
+        .flat_map(|(run, _initial)| run.iter().copied())
+        .collect();
+
+    let mut ret = FrequencyTable::new();
+    for (run, initial) in runs {
+        let is_name = match run.len() {
+            1 if initial => known.contains(run[0]) && !is_common(run[0]),
+            1 => !is_common(run[0]),
+            _ => true,
+        };
+        if is_name {
+            ret.add(run.join(" "));
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(text: &str) -> Vec<(String, usize)> {
+        find(text, &Tokenizer::default(), &StopWords::builtin()).into_counts()
+    }
+
+    fn name(name: &str, count: usize) -> (String, usize) {
+        (name.to_string(), count)
+    }
+
+    #[test]
+    fn runs_of_capitalized_words_and_titles() {
+        let text = "They walked to Netherfield Park with Sir William Lucas.";
+        assert_eq!(
+            names(text),
+            [name("Netherfield Park", 1), name("Sir William Lucas", 1)]
+        );
+        // wrapped over a line, but not over a paragraph
+        assert_eq!(names("they met Mr.\nBingley"), [name("Mr Bingley", 1)]);
+        assert_eq!(names("they met Mr.\n\nBingley"), []);
+        // a title alone is no name
+        assert_eq!(names("yes, said the Colonel to her"), []);
+    }
+
+    #[test]
+    fn stop_words_and_sentence_starts() {
+        // `The` is a stop word, `Bennets` a name on its own
+        assert_eq!(names("and The Bennets came"), [name("Bennets", 1)]);
+        // at the start of a sentence only when a name elsewhere
+        assert_eq!(names("Jane smiled. Then she saw Jane."), [name("Jane", 2)]);
+        assert_eq!(names("Jane smiled."), []);
+    }
+
+    #[test]
+    fn words_more_often_lowercase_are_no_names() {
+        let text = "a long Walk, a walk, and another walk with Jane";
+        assert_eq!(names(text), [name("Jane", 1)]);
+        // `CHAPTER` and `I` are not capitalized words
+        assert_eq!(names("in CHAPTER one I met Jane"), [name("Jane", 1)]);
+    }
+
+    #[test]
+    fn particles_only_inside_a_name() {
+        assert_eq!(
+            names("it was Lady Catherine de Bourgh"),
+            [name("Lady Catherine de Bourgh", 1)]
+        );
+        assert_eq!(
+            names("it was Lady Catherine de la"),
+            [name("Lady Catherine", 1)]
+        );
+    }
+}
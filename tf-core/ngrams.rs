@@ -0,0 +1,140 @@
+use crate::{Error, Result};
+use std::{collections::VecDeque, str::FromStr};
+
+/// What a stop word does to the n-grams around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StopWordPolicy {
+    /// Leave it out, the words on either side are consecutive.
+    #[default]
+    Drop,
+    /// No n-gram spans it.
+    Break,
+}
+
+impl FromStr for StopWordPolicy {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "drop" => Ok(StopWordPolicy::Drop),
+            "break" => Ok(StopWordPolicy::Break),
+            _ => Err(Error::Invalid(format!(
+                "unknown stop word policy `{}`, expected drop or break",
+                s
+            ))),
+        }
+    }
+}
+
+/// Counting sequences of `n` consecutive words, joined by a space, instead
+/// of single words. `n = 1` is plain word counting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ngrams {
+    pub n: usize,
+    pub stop_words: StopWordPolicy,
+}
+
+impl Default for Ngrams {
+    fn default() -> Self {
+        Self {
+            n: 1,
+            stop_words: StopWordPolicy::default(),
+        }
+    }
+}
+
+impl Ngrams {
+    /// For styles that see one word at a time.
+    pub fn window(self) -> Window {
+        Window {
+            ngrams: self,
+            words: VecDeque::with_capacity(self.n),
+        }
+    }
+
+    /// The n-grams of `words`, which still include the stop words.
+    /// With `n = 1` this is `words.filter(|w| !is_stop(w))`.
+    ///
+    /// ```
+    /// use tf_core::ngrams::{Ngrams, StopWordPolicy};
+    /// let words = "said the young elizabeth".split(' ').map(String::from);
+    /// let ngrams = Ngrams { n: 2, stop_words: StopWordPolicy::Break };
+    /// let bigrams: Vec<String> = ngrams.of(words, |w| w == "the").collect();
+    /// assert_eq!(bigrams, ["young elizabeth"]);
+    /// ```
+    pub fn of<I, F>(self, words: I, is_stop: F) -> NgramIter<I::IntoIter, F>
+    where
+        I: IntoIterator<Item = String>,
+        F: FnMut(&str) -> bool,
+    {
+        NgramIter {
+            words: words.into_iter(),
+            is_stop,
+            window: self.window(),
+        }
+    }
+}
+
+/// The last words seen, see [`Ngrams::window`].
+#[derive(Debug, Clone)]
+pub struct Window {
+    ngrams: Ngrams,
+    words: VecDeque<String>,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Ngrams::default().window()
+    }
+}
+
+impl Window {
+    /// Takes the next word that is not a stop word, returns the n-gram it
+    /// completes, if any.
+    pub fn push(&mut self, word: String) -> Option<String> {
+        if self.ngrams.n <= 1 {
+            return Some(word);
+        }
+        if self.words.len() == self.ngrams.n {
+            self.words.pop_front();
+        }
+        self.words.push_back(word);
+        if self.words.len() < self.ngrams.n {
+            return None;
+        }
+        let words: Vec<&str> = self.words.iter().map(|w| w.as_str()).collect();
+        Some(words.join(" "))
+    }
+
+    /// Tells that a stop word came next.
+    pub fn stop(&mut self) {
+        if self.ngrams.stop_words == StopWordPolicy::Break {
+            self.words.clear();
+        }
+    }
+}
+
+/// See [`Ngrams::of`].
+#[derive(Debug, Clone)]
+pub struct NgramIter<I, F> {
+    words: I,
+    is_stop: F,
+    window: Window,
+}
+
+impl<I, F> Iterator for NgramIter<I, F>
+where
+    I: Iterator<Item = String>,
+    F: FnMut(&str) -> bool,
+{
+    type Item = String;
+    fn next(&mut self) -> Option<String> {
+        loop {
+            let word = self.words.next()?;
+            if (self.is_stop)(&word) {
+                self.window.stop();
+            } else if let Some(ngram) = self.window.push(word) {
+                return Some(ngram);
+            }
+        }
+    }
+}
@@ -0,0 +1,582 @@
+use crate::{
+    chart::{Bar, Chart},
+    ranking::Page,
+    ranking::Ranking,
+    store, Error, Result,
+};
+use std::{collections::BTreeMap, fmt::Write, str::FromStr};
+
+/// How the structured formats name the report of every input together,
+/// after those of each one, see [`crate::Config::runs`].
+pub const ALL_FILES: &str = "*";
+
+/// How the final table is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// The human readable layout of each style.
+    #[default]
+    Text,
+    Json,
+    Csv,
+    Tsv,
+    Markdown,
+    /// Horizontal bars, see [`Chart`].
+    Chart(Chart),
+    /// The text encoding of [`store::Table`], to read back with the `tables`
+    /// tool.
+    Table,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "markdown" | "md" => Ok(Format::Markdown),
+            "chart" => Ok(Format::Chart(Chart::default())),
+            "table" => Ok(Format::Table),
+            _ => Err(Error::Invalid(format!(
+                "unknown format `{}`, expected text, json, csv, tsv, markdown, chart or table",
+                s
+            ))),
+        }
+    }
+}
+
+/// What [`Format::Text`] looks like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// `word - count`
+    Plain,
+    /// `[ 1]                 word -    5`
+    Ranked,
+}
+
+/// An extra number reported next to the table, e.g. the count of words with a 'z'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stat {
+    /// Field name in the structured formats.
+    pub key: &'static str,
+    /// Shown in front of the value in the text formats.
+    pub label: &'static str,
+    pub value: usize,
+}
+
+/// What a report is about, beyond the file name, e.g. the title of a book.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    /// Field name in the structured formats.
+    pub key: &'static str,
+    /// Shown in front of the value in the text formats.
+    pub label: &'static str,
+    pub value: String,
+}
+
+/// The final result of a run: the ranked words, the number of words counted
+/// and any extra statistics.
+#[derive(Debug, Clone)]
+pub struct Report {
+    entries: Vec<(String, usize)>,
+    total: usize,
+    stats: Vec<Stat>,
+    layout: Layout,
+    /// Rank of the first entry.
+    first_rank: usize,
+    /// The input the report is about, when there are several.
+    file: Option<String>,
+    /// Whether the report of a file came before this one, see
+    /// [`Report::after_file`].
+    after_file: bool,
+    /// Shown before the entries, see [`crate::Config::about`].
+    notes: Vec<Note>,
+    /// Report each count as a share of `total` as well.
+    relative: bool,
+    /// How often words of each length, in characters, were counted,
+    /// shortest first.
+    lengths: Vec<(usize, usize)>,
+}
+
+impl Report {
+    /// `entries` must already be ranked; `total` is the number of counted words.
+    pub fn new(entries: Vec<(String, usize)>, total: usize) -> Self {
+        Self {
+            entries,
+            total,
+            stats: vec![],
+            layout: Layout::Plain,
+            first_rank: 1,
+            file: None,
+            after_file: false,
+            notes: vec![],
+            relative: false,
+            lengths: vec![],
+        }
+    }
+
+    /// Keeps the entries of `page` only, out of a whole ranking.
+    pub fn paged(self, page: &Page) -> Self {
+        Self {
+            lengths: word_lengths(&self.entries),
+            entries: page.select(self.entries, |(_w, c)| *c),
+            first_rank: page.first_rank(),
+            ..self
+        }
+    }
+
+    /// For entries that are already a page of a longer ranking.
+    pub fn starting_at(self, first_rank: usize) -> Self {
+        Self { first_rank, ..self }
+    }
+
+    /// For entries that are already a page: the word lengths of the whole
+    /// ranking, see [`word_lengths`].
+    pub fn with_lengths(self, lengths: Vec<(usize, usize)>) -> Self {
+        Self { lengths, ..self }
+    }
+
+    /// Names the input this report is about, see [`crate::Config::runs`].
+    pub fn of_file(self, file: Option<String>) -> Self {
+        Self { file, ..self }
+    }
+
+    /// Goes on with the document of the reports of files printed before,
+    /// e.g. after the first of `--per-file`, see
+    /// [`crate::Config::after_file`]: in JSON each report takes a single
+    /// line, and in CSV and TSV they have a `file` column, with the header
+    /// before the first only. Following them, a report of no file in
+    /// particular is that of every input, [`ALL_FILES`].
+    pub fn after_file(self, after_file: bool) -> Self {
+        Self { after_file, ..self }
+    }
+
+    /// Tells what the input is, see [`crate::Config::about`].
+    pub fn with_notes(self, notes: Vec<Note>) -> Self {
+        Self { notes, ..self }
+    }
+
+    /// Tells how the entries were ranked; [`Ranking::Relative`] reports
+    /// relative frequencies next to the counts.
+    pub fn ranked_by(self, ranking: Ranking) -> Self {
+        Self {
+            relative: ranking == Ranking::Relative,
+            ..self
+        }
+    }
+
+    /// Use the `[ 1]  word -    5` text layout.
+    pub fn ranked(self) -> Self {
+        Self {
+            layout: Layout::Ranked,
+            ..self
+        }
+    }
+
+    pub fn with_stat(mut self, key: &'static str, label: &'static str, value: usize) -> Self {
+        self.add_stat(key, label, value);
+        self
+    }
+
+    pub fn add_stat(&mut self, key: &'static str, label: &'static str, value: usize) {
+        self.stats.push(Stat { key, label, value });
+    }
+
+    pub fn entries(&self) -> &[(String, usize)] {
+        &self.entries
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn print(&self, format: Format) {
+        print!("{}", self.render(format));
+    }
+
+    /// The report, on its own or going on with those before it, see
+    /// [`Report::after_file`].
+    pub fn render(&self, format: Format) -> String {
+        let after_file = self.after_file;
+        let part = Report {
+            file: self
+                .file
+                .clone()
+                .or_else(|| after_file.then(|| ALL_FILES.to_string())),
+            ..self.clone()
+        };
+        let mut out = String::new();
+        // writing to a String never fails
+        let _ = match format {
+            Format::Text => self.write_text(&mut out),
+            Format::Json => part.write_json(&mut out),
+            Format::Csv => part.write_separated(&mut out, !after_file, ',', csv_field),
+            Format::Tsv => part.write_separated(&mut out, !after_file, '\t', tsv_field),
+            Format::Markdown => self.write_markdown(&mut out),
+            Format::Chart(chart) => self.write_chart(&mut out, &chart),
+            Format::Table => {
+                store::Table::new(self.entries.clone(), self.total).write_text(&mut out)
+            }
+        };
+        if format == Format::Json && part.file.is_some() {
+            out = one_line(&out);
+        }
+        out
+    }
+
+    fn ranks(&self) -> impl Iterator<Item = (usize, &str, usize)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(move |(i, (w, c))| (self.first_rank + i, w.as_str(), *c))
+    }
+
+    /// `count` relative to the total.
+    fn share(&self, count: usize) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            count as f64 / self.total as f64
+        }
+    }
+
+    fn write_text(&self, out: &mut String) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            writeln!(out, "==> {} <==", file)?;
+        }
+        self.write_notes(out)?;
+        for (rank, word, count) in self.ranks() {
+            match self.layout {
+                Layout::Plain => write!(out, "{} - {}", word, count)?,
+                Layout::Ranked => write!(out, "[{:>2}] {:>20} - {:>4}", rank, word, count)?,
+            }
+            if self.relative {
+                write!(out, " ({:.2}%)", self.share(count) * 100.0)?;
+            }
+            writeln!(out)?;
+        }
+        for stat in &self.stats {
+            writeln!(out, "{}: {}", stat.label, stat.value)?;
+        }
+        if self.file.is_some() {
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    fn write_json(&self, out: &mut String) -> std::fmt::Result {
+        writeln!(out, "{{")?;
+        if let Some(file) = &self.file {
+            writeln!(out, "  \"file\": {},", json_string(file))?;
+        }
+        if !self.notes.is_empty() {
+            let notes: Vec<String> = self
+                .notes
+                .iter()
+                .map(|n| format!("{}: {}", json_string(n.key), json_string(&n.value)))
+                .collect();
+            writeln!(out, "  \"about\": {{{}}},", notes.join(", "))?;
+        }
+        writeln!(out, "  \"total\": {},", self.total)?;
+        writeln!(out, "  \"entries\": [")?;
+        for (rank, word, count) in self.ranks() {
+            let comma = if rank + 1 < self.first_rank + self.entries.len() {
+                ","
+            } else {
+                ""
+            };
+            write!(
+                out,
+                "    {{\"rank\": {}, \"word\": {}, \"count\": {}",
+                rank,
+                json_string(word),
+                count
+            )?;
+            if self.relative {
+                write!(out, ", \"frequency\": {}", self.share(count))?;
+            }
+            writeln!(out, "}}{}", comma)?;
+        }
+        writeln!(out, "  ],")?;
+        let stats: Vec<String> = self
+            .stats
+            .iter()
+            .map(|s| format!("{}: {}", json_string(s.key), s.value))
+            .collect();
+        writeln!(out, "  \"stats\": {{{}}}", stats.join(", "))?;
+        writeln!(out, "}}")
+    }
+
+    /// CSV and TSV: one row per word, the input, the total and the stats
+    /// repeated as extra columns so that every row is self-contained.
+    fn write_separated(
+        &self,
+        out: &mut String,
+        header: bool,
+        sep: char,
+        field: fn(&str) -> String,
+    ) -> std::fmt::Result {
+        let mut columns = vec![];
+        if self.file.is_some() {
+            columns.push("file");
+        }
+        columns.extend(["rank", "word", "count"]);
+        if self.relative {
+            columns.push("frequency");
+        }
+        columns.push("total");
+        columns.extend(self.stats.iter().map(|s| s.key));
+        if header {
+            writeln!(out, "{}", columns.join(&sep.to_string()))?;
+        }
+        for (rank, word, count) in self.ranks() {
+            if let Some(file) = &self.file {
+                write!(out, "{}{}", field(file), sep)?;
+            }
+            write!(out, "{}{}{}{}{}", rank, sep, field(word), sep, count)?;
+            if self.relative {
+                write!(out, "{}{}", sep, self.share(count))?;
+            }
+            write!(out, "{}{}", sep, self.total)?;
+            for stat in &self.stats {
+                write!(out, "{}{}", sep, stat.value)?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// `label: value`, one note per line.
+    fn write_notes(&self, out: &mut String) -> std::fmt::Result {
+        for note in &self.notes {
+            writeln!(out, "{}: {}", note.label, note.value)?;
+        }
+        Ok(())
+    }
+
+    /// The ranks as bars, then the word lengths with
+    /// [`Chart::length_histogram`].
+    fn write_chart(&self, out: &mut String, chart: &Chart) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            writeln!(out, "==> {} <==", file)?;
+        }
+        self.write_notes(out)?;
+        let bars: Vec<Bar> = self
+            .ranks()
+            .map(|(rank, word, count)| Bar {
+                label: format!("[{:>2}] {}", rank, word),
+                value: count,
+                note: self.note(count),
+            })
+            .collect();
+        chart.write_bars(out, &bars)?;
+        if chart.length_histogram {
+            // every length from the shortest word to the longest, so that
+            // gaps show
+            let shortest = self.lengths.first().map_or(1, |(len, _count)| *len);
+            let longest = self.lengths.last().map_or(0, |(len, _count)| *len);
+            let bars: Vec<Bar> = (shortest..=longest)
+                .map(|len| {
+                    let count = self
+                        .lengths
+                        .iter()
+                        .find(|(l, _count)| *l == len)
+                        .map_or(0, |(_len, count)| *count);
+                    Bar {
+                        label: format!("{:>2} chars", len),
+                        value: count,
+                        note: self.note(count),
+                    }
+                })
+                .collect();
+            writeln!(out)?;
+            writeln!(out, "Word lengths:")?;
+            chart.write_bars(out, &bars)?;
+        }
+        for stat in &self.stats {
+            writeln!(out, "{}: {}", stat.label, stat.value)?;
+        }
+        if self.file.is_some() {
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// What follows a bar: the count, and its share with a relative ranking.
+    fn note(&self, count: usize) -> String {
+        if self.relative {
+            format!("{} ({:.2}%)", count, self.share(count) * 100.0)
+        } else {
+            count.to_string()
+        }
+    }
+
+    fn write_markdown(&self, out: &mut String) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            writeln!(out, "## {}", file)?;
+            writeln!(out)?;
+        }
+        for note in &self.notes {
+            writeln!(out, "- **{}**: {}", note.label, note.value)?;
+        }
+        if !self.notes.is_empty() {
+            writeln!(out)?;
+        }
+        if self.relative {
+            writeln!(out, "| Rank | Word | Count | Frequency |")?;
+            writeln!(out, "| ---: | :--- | ----: | --------: |")?;
+        } else {
+            writeln!(out, "| Rank | Word | Count |")?;
+            writeln!(out, "| ---: | :--- | ----: |")?;
+        }
+        for (rank, word, count) in self.ranks() {
+            write!(
+                out,
+                "| {} | {} | {} |",
+                rank,
+                word.replace('|', "\\|"),
+                count
+            )?;
+            if self.relative {
+                write!(out, " {:.2}% |", self.share(count) * 100.0)?;
+            }
+            writeln!(out)?;
+        }
+        writeln!(out)?;
+        writeln!(out, "Total words: {}", self.total)?;
+        for stat in &self.stats {
+            writeln!(out, "{}: {}", stat.label, stat.value)?;
+        }
+        if self.file.is_some() {
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// The pretty JSON of a report on one line: the layout is all at the start
+/// and the end of the lines, strings never span several.
+fn one_line(json: &str) -> String {
+    let mut ret: Vec<&str> = json.lines().map(str::trim).collect();
+    ret.retain(|l| !l.is_empty());
+    ret.join(" ") + "\n"
+}
+
+/// How often words of each length, in characters, appear in `entries`,
+/// shortest first.
+pub fn word_lengths(entries: &[(String, usize)]) -> Vec<(usize, usize)> {
+    let mut lengths = BTreeMap::new();
+    for (word, count) in entries {
+        *lengths.entry(word.chars().count()).or_insert(0) += count;
+    }
+    lengths.into_iter().collect()
+}
+
+/// `s` as a quoted JSON string.
+pub fn json_string(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len() + 2);
+    ret.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => {
+                let _ = write!(ret, "\\u{:04x}", ch as u32);
+            }
+            ch => ret.push(ch),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+/// `s` as a CSV field, quoted when needed.
+pub fn csv_field(s: &str) -> String {
+    if s.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// `s` as a TSV field: tabs and line breaks become spaces.
+pub fn tsv_field(s: &str) -> String {
+    s.replace(&['\t', '\n', '\r'][..], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        Report::new(
+            vec![("a,\"b\"".to_string(), 3), ("c|d\te".to_string(), 1)],
+            10,
+        )
+        .with_stat("z_words", "Words with z", 2)
+    }
+
+    #[test]
+    fn fields_are_escaped() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+        assert_eq!(tsv_field("a\tb\nc"), "a b c");
+    }
+
+    #[test]
+    fn every_format_carries_the_words_the_total_and_the_stats() {
+        let report = report();
+        assert_eq!(
+            report.render(Format::Json),
+            "{\n  \"total\": 10,\n  \"entries\": [\n    \
+             {\"rank\": 1, \"word\": \"a,\\\"b\\\"\", \"count\": 3},\n    \
+             {\"rank\": 2, \"word\": \"c|d\\te\", \"count\": 1}\n  ],\n  \
+             \"stats\": {\"z_words\": 2}\n}\n"
+        );
+        assert_eq!(
+            report.render(Format::Csv),
+            "rank,word,count,total,z_words\n1,\"a,\"\"b\"\"\",3,10,2\n2,c|d\te,1,10,2\n"
+        );
+        assert_eq!(
+            report.render(Format::Tsv),
+            "rank\tword\tcount\ttotal\tz_words\n1\ta,\"b\"\t3\t10\t2\n2\tc|d e\t1\t10\t2\n"
+        );
+        assert!(report
+            .render(Format::Markdown)
+            .contains("| 2 | c\\|d\te | 1 |"));
+        assert_eq!(
+            report.ranked().render(Format::Text),
+            "[ 1]                a,\"b\" -    3\n[ 2]                c|d\te -    1\nWords with z: 2\n"
+        );
+    }
+
+    #[test]
+    fn the_reports_of_each_file_make_a_single_document() {
+        let report = |file: Option<&str>| report().of_file(file.map(String::from));
+        let first = report(Some("a.txt")).render(Format::Json);
+        let combined = report(None).after_file(true).render(Format::Json);
+        assert_eq!(first.lines().count(), 1);
+        assert!(first.starts_with("{ \"file\": \"a.txt\", \"total\": 10, \"entries\": ["));
+        assert!(combined.starts_with("{ \"file\": \"*\", "));
+        assert_eq!(combined.lines().count(), 1);
+        assert_eq!(
+            report(Some("a.txt")).render(Format::Csv)
+                + &report(Some("b.txt")).after_file(true).render(Format::Csv)
+                + &report(None).after_file(true).render(Format::Csv),
+            "file,rank,word,count,total,z_words\n\
+             a.txt,1,\"a,\"\"b\"\"\",3,10,2\na.txt,2,c|d\te,1,10,2\n\
+             b.txt,1,\"a,\"\"b\"\"\",3,10,2\nb.txt,2,c|d\te,1,10,2\n\
+             *,1,\"a,\"\"b\"\"\",3,10,2\n*,2,c|d\te,1,10,2\n"
+        );
+        // text keeps its own layout
+        assert!(!report(None)
+            .after_file(true)
+            .render(Format::Text)
+            .contains('*'));
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{Error, FrequencyTable, Result};
+use std::{cmp::Ordering, cmp::Reverse, str::FromStr};
+
+/// The order in which words are reported. Every policy is total, so the
+/// output is the same on every run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Ranking {
+    /// Most frequent first, ties alphabetically.
+    #[default]
+    Count,
+    /// Alphabetically.
+    Alphabetical,
+    /// In the order the words first appear in the text.
+    FirstOccurrence,
+    /// Longest first (in characters), then like [`Ranking::Count`].
+    Length,
+    /// Like [`Ranking::Count`], but reported as a share of all counted words.
+    Relative,
+}
+
+impl FromStr for Ranking {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "count" => Ok(Ranking::Count),
+            "alpha" | "alphabetical" => Ok(Ranking::Alphabetical),
+            "first" | "first-occurrence" => Ok(Ranking::FirstOccurrence),
+            "length" => Ok(Ranking::Length),
+            "relative" => Ok(Ranking::Relative),
+            _ => Err(Error::Invalid(format!(
+                "unknown ranking `{}`, expected count, alpha, first, length or relative",
+                s
+            ))),
+        }
+    }
+}
+
+impl Ranking {
+    /// Compares two `(word, count)` entries.
+    ///
+    /// [`Ranking::FirstOccurrence`] considers every pair equal: a stable sort
+    /// of entries that are in first-seen order keeps them that way.
+    pub fn compare(self, (w1, c1): (&str, usize), (w2, c2): (&str, usize)) -> Ordering {
+        match self {
+            Ranking::Count | Ranking::Relative => (Reverse(c1), w1).cmp(&(Reverse(c2), w2)),
+            Ranking::Alphabetical => w1.cmp(w2),
+            Ranking::FirstOccurrence => Ordering::Equal,
+            Ranking::Length => (Reverse(w1.chars().count()), Reverse(c1), w1).cmp(&(
+                Reverse(w2.chars().count()),
+                Reverse(c2),
+                w2,
+            )),
+        }
+    }
+
+    /// Every word of the table, in this order.
+    pub fn rank(self, table: &FrequencyTable) -> Vec<(String, usize)> {
+        let mut entries: Vec<(String, usize)> =
+            table.iter().map(|(w, c)| (w.to_string(), c)).collect();
+        entries.sort_unstable_by_key(|(w, _c)| table.first_seen(w));
+        entries.sort_by(|(w1, c1), (w2, c2)| self.compare((w1, *c1), (w2, *c2)));
+        entries
+    }
+}
+
+/// Which part of a ranking is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    /// How many words to report, `None` for all of them.
+    pub top: Option<usize>,
+    /// How many of the first ranks to skip.
+    pub offset: usize,
+    /// Words counted fewer times are left out before paging.
+    pub min_count: usize,
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Self {
+            top: Some(25),
+            offset: 0,
+            min_count: 0,
+        }
+    }
+}
+
+impl Page {
+    /// The entries of this page out of a whole ranking, `count` telling how
+    /// many times an entry was counted, for [`Page::min_count`].
+    pub fn select<T>(&self, ranked: Vec<T>, count: impl Fn(&T) -> usize) -> Vec<T> {
+        let entries = ranked
+            .into_iter()
+            .filter(|entry| count(entry) >= self.min_count)
+            .skip(self.offset);
+        match self.top {
+            Some(n) => entries.take(n).collect(),
+            None => entries.collect(),
+        }
+    }
+
+    /// Rank of the first entry of the page.
+    pub fn first_rank(&self) -> usize {
+        self.offset + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> FrequencyTable {
+        "pear fig apple fig pear banana fig apple"
+            .split(' ')
+            .map(String::from)
+            .collect()
+    }
+
+    fn words(ranking: Ranking) -> Vec<String> {
+        ranking
+            .rank(&table())
+            .into_iter()
+            .map(|(w, _c)| w)
+            .collect()
+    }
+
+    #[test]
+    fn every_policy_is_deterministic() {
+        assert_eq!(words(Ranking::Count), ["fig", "apple", "pear", "banana"]);
+        assert_eq!(words(Ranking::Relative), words(Ranking::Count));
+        assert_eq!(
+            words(Ranking::Alphabetical),
+            ["apple", "banana", "fig", "pear"]
+        );
+        assert_eq!(
+            words(Ranking::FirstOccurrence),
+            ["pear", "fig", "apple", "banana"]
+        );
+        assert_eq!(words(Ranking::Length), ["banana", "apple", "pear", "fig"]);
+    }
+
+    #[test]
+    fn a_page_skips_rare_words_then_the_offset_then_keeps_the_top() {
+        let ranked = Ranking::Count.rank(&table());
+        let page = |top, offset, min_count| {
+            let page = Page {
+                top,
+                offset,
+                min_count,
+            };
+            let words: Vec<String> = page
+                .select(ranked.clone(), |(_w, c)| *c)
+                .into_iter()
+                .map(|e| e.0)
+                .collect();
+            words
+        };
+        assert_eq!(page(Some(25), 0, 0), ["fig", "apple", "pear", "banana"]);
+        assert_eq!(page(Some(2), 0, 0), ["fig", "apple"]);
+        assert_eq!(page(Some(2), 1, 0), ["apple", "pear"]);
+        assert_eq!(page(None, 1, 2), ["apple", "pear"]);
+        assert_eq!(page(None, 5, 0), Vec::<String>::new());
+        assert_eq!(
+            Page {
+                offset: 25,
+                ..Page::default()
+            }
+            .first_rank(),
+            26
+        );
+    }
+
+    #[test]
+    fn rankings_are_parsed_by_name() {
+        assert_eq!(
+            "first".parse::<Ranking>().unwrap(),
+            Ranking::FirstOccurrence
+        );
+        assert!("size".parse::<Ranking>().is_err());
+    }
+}
@@ -0,0 +1,291 @@
+use crate::{Error, Result};
+use glob::Pattern;
+use std::str::FromStr;
+
+/// How a text is cut into sections.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Segmentation {
+    /// At lines like `Chapter 12` or `CHAPTER XII.`.
+    #[default]
+    Chapters,
+    /// At lines holding only a Roman numeral, like `XII.`.
+    Roman,
+    /// At runs of at least this many blank lines.
+    Blank(usize),
+    /// Every this many lines.
+    Lines(usize),
+    /// At lines matching a glob pattern, e.g. `BOOK *`, once trimmed.
+    Heading(Pattern),
+}
+
+impl FromStr for Segmentation {
+    type Err = Error;
+    /// `chapters`, `roman`, `blank`, `blank:<n>`, `lines:<n>` or
+    /// `heading:<pattern>`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, arg) = match s.find(':') {
+            Some(i) => (&s[..i], Some(&s[i + 1..])),
+            None => (s, None),
+        };
+        let number = |arg: Option<&str>| -> Result<usize> {
+            match arg.map(str::parse) {
+                Some(Ok(n)) if n > 0 => Ok(n),
+                _ => Err(Error::Invalid(format!(
+                    "`{}` expects a positive number, e.g. `{}:2`",
+                    kind, kind
+                ))),
+            }
+        };
+        match (kind, arg) {
+            ("chapters", None) => Ok(Segmentation::Chapters),
+            ("roman", None) => Ok(Segmentation::Roman),
+            ("blank", None) => Ok(Segmentation::Blank(1)),
+            ("blank", arg) => Ok(Segmentation::Blank(number(arg)?)),
+            ("lines", arg) => Ok(Segmentation::Lines(number(arg)?)),
+            ("heading", Some(pattern)) => Pattern::new(pattern)
+                .map(Segmentation::Heading)
+                .map_err(|e| Error::Invalid(format!("bad heading pattern `{}`: {}", pattern, e))),
+            _ => Err(Error::Invalid(format!(
+                "unknown segmentation `{}`, expected chapters, roman, blank[:n], lines:n \
+                 or heading:<pattern>",
+                s
+            ))),
+        }
+    }
+}
+
+/// A part of a text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// The heading, or a description such as `lines 1-200`.
+    pub title: String,
+    /// Line the section starts at, counting from 1.
+    pub line: usize,
+    /// The text of the section, without its heading.
+    pub text: String,
+}
+
+impl Segmentation {
+    /// The sections of `text`, in order. Text before the first heading is a
+    /// section of its own, unless blank; sections with no text are kept, so
+    /// that numbering follows the headings.
+    ///
+    /// ```
+    /// use tf_core::segments::Segmentation;
+    /// let text = "Title\n\nChapter 1\n\nIt is a truth.\n\nCHAPTER II.\nMr. Bennet\n";
+    /// let sections = Segmentation::Chapters.split(text);
+    /// let titles: Vec<&str> = sections.iter().map(|s| s.title.as_str()).collect();
+    /// assert_eq!(titles, ["(start)", "Chapter 1", "CHAPTER II."]);
+    /// assert_eq!(sections[2].text, "Mr. Bennet\n");
+    /// ```
+    pub fn split(&self, text: &str) -> Vec<Segment> {
+        self.split_after(text, 0)
+    }
+
+    /// Like [`Segmentation::split`], for a `text` that starts after
+    /// `skipped` lines of its file, e.g. a Project Gutenberg header: line
+    /// numbers count from the top of the file.
+    pub fn split_after(&self, text: &str, skipped: usize) -> Vec<Segment> {
+        match self {
+            Segmentation::Blank(n) => split_blank(text, *n, skipped),
+            Segmentation::Lines(n) => split_lines(text, *n, skipped),
+            _ => self.split_headings(text, skipped),
+        }
+    }
+
+    fn is_heading(&self, line: &str) -> bool {
+        let line = line.trim();
+        match self {
+            Segmentation::Chapters => {
+                let mut words = line.split_whitespace();
+                match (words.next(), words.next()) {
+                    (Some(first), Some(number)) => {
+                        first.eq_ignore_ascii_case("chapter") && is_number(number)
+                    }
+                    _ => false,
+                }
+            }
+            Segmentation::Roman => is_roman(line.trim_end_matches('.')),
+            Segmentation::Heading(pattern) => !line.is_empty() && pattern.matches(line),
+            Segmentation::Blank(_) | Segmentation::Lines(_) => false,
+        }
+    }
+
+    fn split_headings(&self, text: &str, skipped: usize) -> Vec<Segment> {
+        let mut ret = vec![Segment {
+            title: "(start)".to_string(),
+            line: skipped + 1,
+            text: String::new(),
+        }];
+        for (i, line) in text.split_inclusive('\n').enumerate() {
+            if self.is_heading(line) {
+                ret.push(Segment {
+                    title: line.trim().to_string(),
+                    line: skipped + i + 1,
+                    text: String::new(),
+                });
+            } else if let Some(last) = ret.last_mut() {
+                last.text.push_str(line);
+            }
+        }
+        if ret[0].text.trim().is_empty() {
+            ret.remove(0);
+        }
+        ret
+    }
+}
+
+/// Digits, or a Roman numeral, maybe followed by `.` or `:`.
+fn is_number(s: &str) -> bool {
+    let s = s.trim_end_matches(['.', ':']);
+    (!s.is_empty() && s.chars().all(|c| c.is_ascii_digit())) || is_roman(s)
+}
+
+fn is_roman(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| "IVXLCDM".contains(c))
+}
+
+fn split_blank(text: &str, min_blank: usize, skipped: usize) -> Vec<Segment> {
+    let mut ret: Vec<Segment> = Vec::new();
+    let mut blank = 0;
+    // a new section starts at the next line with text
+    let mut boundary = true;
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        if line.trim().is_empty() {
+            blank += 1;
+            if blank >= min_blank {
+                boundary = true;
+            }
+        } else {
+            blank = 0;
+            if boundary {
+                ret.push(Segment {
+                    title: format!("section {}", ret.len() + 1),
+                    line: skipped + i + 1,
+                    text: String::new(),
+                });
+                boundary = false;
+            }
+        }
+        if let Some(last) = ret.last_mut() {
+            last.text.push_str(line);
+        }
+    }
+    ret
+}
+
+fn split_lines(text: &str, n: usize, skipped: usize) -> Vec<Segment> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    lines
+        .chunks(n)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let line = skipped + i * n + 1;
+            Segment {
+                title: format!("lines {}-{}", line, line + chunk.len() - 1),
+                line,
+                text: chunk.concat(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(segmentation: &str, text: &str) -> Vec<(String, usize)> {
+        segmentation
+            .parse::<Segmentation>()
+            .unwrap()
+            .split(text)
+            .into_iter()
+            .map(|s| (s.title, s.line))
+            .collect()
+    }
+
+    fn titled(title: &str, line: usize) -> (String, usize) {
+        (title.to_string(), line)
+    }
+
+    #[test]
+    fn segmentations_by_name() {
+        assert_eq!(
+            "blank".parse::<Segmentation>().unwrap(),
+            Segmentation::Blank(1)
+        );
+        assert_eq!(
+            "blank:3".parse::<Segmentation>().unwrap(),
+            Segmentation::Blank(3)
+        );
+        assert_eq!(
+            "lines:200".parse::<Segmentation>().unwrap(),
+            Segmentation::Lines(200)
+        );
+        assert!("lines".parse::<Segmentation>().is_err());
+        assert!("lines:0".parse::<Segmentation>().is_err());
+        assert!("roman:2".parse::<Segmentation>().is_err());
+        assert!("heading:[".parse::<Segmentation>().is_err());
+        assert!("pages".parse::<Segmentation>().is_err());
+    }
+
+    #[test]
+    fn chapter_headings_need_a_number() {
+        let text = "Chapter one\nChapter 3:\ntext\nchapter IV\n";
+        assert_eq!(
+            split("chapters", text),
+            [
+                titled("(start)", 1),
+                titled("Chapter 3:", 2),
+                titled("chapter IV", 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn roman_and_pattern_headings() {
+        let text = "I.\nIt is a truth.\nII\nMr. Bennet\n";
+        assert_eq!(split("roman", text), [titled("I.", 1), titled("II", 3)]);
+        let text = "  BOOK ONE\ntext\nBOOKS\nBOOK TWO\n";
+        assert_eq!(
+            split("heading:BOOK *", text),
+            [titled("BOOK ONE", 1), titled("BOOK TWO", 4)]
+        );
+    }
+
+    #[test]
+    fn runs_of_blank_lines() {
+        let text = "a\nb\n\nc\n\n\nd\n";
+        assert_eq!(
+            split("blank", text),
+            [
+                titled("section 1", 1),
+                titled("section 2", 4),
+                titled("section 3", 7)
+            ]
+        );
+        let sections = Segmentation::Blank(2).split(text);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].text, "a\nb\n\nc\n\n\n");
+        assert_eq!(sections[1].line, 7);
+    }
+
+    #[test]
+    fn fixed_numbers_of_lines() {
+        let sections = Segmentation::Lines(2).split("a\nb\nc\n");
+        let titles: Vec<&str> = sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["lines 1-2", "lines 3-3"]);
+        assert_eq!(sections[1].text, "c\n");
+    }
+
+    #[test]
+    fn line_numbers_from_the_top_of_the_file() {
+        let text = "Chapter 1\ntext\nChapter 2\n";
+        let sections = Segmentation::Chapters.split_after(text, 7);
+        assert_eq!((sections[0].line, sections[1].line), (8, 10));
+        let sections = Segmentation::Lines(2).split_after(text, 7);
+        let titles: Vec<&str> = sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["lines 8-9", "lines 10-10"]);
+        assert_eq!(Segmentation::Blank(1).split_after(text, 7)[0].line, 8);
+    }
+}
@@ -0,0 +1,456 @@
+use crate::FrequencyTable;
+use std::{collections::HashMap, sync::Mutex};
+
+/// Whether words are reduced to their stem before counting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Stemming {
+    #[default]
+    Off,
+    /// Count and report stems, e.g. `connect` for `connected` and `connection`.
+    Stems,
+    /// Count stems but report each one as its most frequent word in the text.
+    SurfaceForms,
+}
+
+/// A stage that goes after lowercasing and stop-word filtering. With
+/// [`Stemming::SurfaceForms`] it remembers which words each stem came from,
+/// so it is shared (it is `Sync`) rather than copied between the steps that
+/// stem and the step that reports.
+#[derive(Debug, Default)]
+pub struct Stemmer {
+    stemming: Stemming,
+    /// stem -> word -> count
+    forms: Mutex<HashMap<String, HashMap<String, usize>>>,
+}
+
+impl Stemmer {
+    pub fn new(stemming: Stemming) -> Self {
+        Self {
+            stemming,
+            forms: Default::default(),
+        }
+    }
+
+    /// The stem of a lowercase `word`, or `word` itself when stemming is off.
+    /// Each word of an n-gram is stemmed on its own.
+    pub fn stem(&self, word: String) -> String {
+        match self.stemming {
+            Stemming::Off => word,
+            Stemming::Stems => stem_words(&word),
+            Stemming::SurfaceForms => {
+                let stem = stem_words(&word);
+                *self
+                    .forms
+                    .lock()
+                    .unwrap()
+                    .entry(stem.clone())
+                    .or_default()
+                    .entry(word)
+                    .or_insert(0) += 1;
+                stem
+            }
+        }
+    }
+
+    /// What to report `stem` as: the word it was stemmed from most often
+    /// (ties alphabetically) with [`Stemming::SurfaceForms`], else `stem`.
+    pub fn label(&self, stem: &str) -> String {
+        let forms = self.forms.lock().unwrap();
+        forms
+            .get(stem)
+            .and_then(|words| {
+                words
+                    .iter()
+                    .min_by(|(w1, c1), (w2, c2)| c2.cmp(c1).then(w1.cmp(w2)))
+            })
+            .map_or_else(|| stem.to_string(), |(w, _c)| w.clone())
+    }
+
+    /// `table` with every stem replaced by its [`Self::label`], keeping the
+    /// first-seen order. Different stems never share a label, so no counts
+    /// are merged.
+    pub fn relabel(&self, table: &FrequencyTable) -> FrequencyTable {
+        if self.stemming != Stemming::SurfaceForms {
+            return table.clone();
+        }
+        table
+            .clone()
+            .into_counts()
+            .into_iter()
+            .map(|(w, c)| (self.label(&w), c))
+            .collect()
+    }
+}
+
+fn stem_words(ngram: &str) -> String {
+    if !ngram.contains(' ') {
+        return stem(ngram);
+    }
+    let stems: Vec<String> = ngram.split(' ').map(stem).collect();
+    stems.join(" ")
+}
+
+/// The Porter2 ("English" Snowball) stem of a lowercase word. Words of less
+/// than three letters are left alone.
+///
+/// ```
+/// assert_eq!(tf_core::stemmer::stem("connections"), "connect");
+/// assert_eq!(tf_core::stemmer::stem("happily"), "happili");
+/// ```
+pub fn stem(word: &str) -> String {
+    if word.chars().count() < 3 {
+        return word.to_string();
+    }
+    if let Some(stem) = exception1(word) {
+        return stem.to_string();
+    }
+    let mut w: Vec<char> = word.strip_prefix('\'').unwrap_or(word).chars().collect();
+    // a 'y' that works as a consonant
+    for i in 0..w.len() {
+        if w[i] == 'y' && (i == 0 || is_vowel(w[i - 1])) {
+            w[i] = 'Y';
+        }
+    }
+    let p1 = match ["gener", "commun", "arsen"]
+        .iter()
+        .find(|p| starts_with(&w, p))
+    {
+        Some(prefix) => prefix.len(),
+        None => region_after(&w, 0),
+    };
+    let p2 = region_after(&w, p1);
+
+    step_1a(&mut w);
+    let exception2 = [
+        "inning", "outing", "canning", "herring", "earring", "proceed", "exceed", "succeed",
+    ];
+    if !exception2.iter().any(|e| w.iter().copied().eq(e.chars())) {
+        step_1b(&mut w, p1);
+        step_1c(&mut w);
+        step_2(&mut w, p1);
+        step_3(&mut w, p1, p2);
+        step_4(&mut w, p2);
+        step_5(&mut w, p1, p2);
+    }
+    w.into_iter()
+        .map(|c| if c == 'Y' { 'y' } else { c })
+        .collect()
+}
+
+fn exception1(word: &str) -> Option<&'static str> {
+    Some(match word {
+        "skis" => "ski",
+        "skies" => "sky",
+        "dying" => "die",
+        "lying" => "lie",
+        "tying" => "tie",
+        "idly" => "idl",
+        "gently" => "gentl",
+        "ugly" => "ugli",
+        "early" => "earli",
+        "only" => "onli",
+        "singly" => "singl",
+        "sky" => "sky",
+        "news" => "news",
+        "howe" => "howe",
+        "atlas" => "atlas",
+        "cosmos" => "cosmos",
+        "bias" => "bias",
+        "andes" => "andes",
+        _ => return None,
+    })
+}
+
+fn step_1a(w: &mut Vec<char>) {
+    if let Some(suffix) = longest_suffix(w, &["'", "'s", "'s'"]) {
+        w.truncate(w.len() - suffix.len());
+    }
+    match longest_suffix(w, &["sses", "ied", "ies", "s", "us", "ss"]) {
+        Some("sses") => replace_suffix(w, 4, "ss"),
+        Some("ied") | Some("ies") if w.len() > 4 => replace_suffix(w, 3, "i"),
+        Some("ied") | Some("ies") => replace_suffix(w, 3, "ie"),
+        // only if there is a vowel before the letter in front of the 's'
+        Some("s") if w.len() > 2 && w[..w.len() - 2].iter().any(|&c| is_vowel(c)) => {
+            w.pop();
+        }
+        _ => {}
+    }
+}
+
+fn step_1b(w: &mut Vec<char>, p1: usize) {
+    let suffixes = ["eed", "eedly", "ed", "edly", "ing", "ingly"];
+    let suffix = match longest_suffix(w, &suffixes) {
+        Some(suffix) => suffix,
+        None => return,
+    };
+    let start = w.len() - suffix.len();
+    if suffix.starts_with("eed") {
+        if start >= p1 {
+            replace_suffix(w, suffix.len(), "ee");
+        }
+        return;
+    }
+    if !w[..start].iter().any(|&c| is_vowel(c)) {
+        return;
+    }
+    w.truncate(start);
+    if longest_suffix(w, &["at", "bl", "iz"]).is_some() {
+        w.push('e');
+    } else if longest_suffix(w, &["bb", "dd", "ff", "gg", "mm", "nn", "pp", "rr", "tt"]).is_some() {
+        w.pop();
+    } else if w.len() == p1 && ends_in_short_syllable(w, w.len()) {
+        w.push('e');
+    }
+}
+
+fn step_1c(w: &mut [char]) {
+    let n = w.len();
+    if n > 2 && (w[n - 1] == 'y' || w[n - 1] == 'Y') && !is_vowel(w[n - 2]) {
+        w[n - 1] = 'i';
+    }
+}
+
+fn step_2(w: &mut Vec<char>, p1: usize) {
+    const RULES: &[(&str, &str)] = &[
+        ("tional", "tion"),
+        ("enci", "ence"),
+        ("anci", "ance"),
+        ("abli", "able"),
+        ("entli", "ent"),
+        ("izer", "ize"),
+        ("ization", "ize"),
+        ("ational", "ate"),
+        ("ation", "ate"),
+        ("ator", "ate"),
+        ("alism", "al"),
+        ("aliti", "al"),
+        ("alli", "al"),
+        ("fulness", "ful"),
+        ("ousli", "ous"),
+        ("ousness", "ous"),
+        ("iveness", "ive"),
+        ("iviti", "ive"),
+        ("biliti", "ble"),
+        ("bli", "ble"),
+        ("ogi", "og"),
+        ("fulli", "ful"),
+        ("lessli", "less"),
+        ("li", ""),
+    ];
+    let (suffix, replacement) = match longest_rule(w, RULES) {
+        Some(rule) => rule,
+        None => return,
+    };
+    let start = w.len() - suffix.len();
+    if start < p1 {
+        return;
+    }
+    let before = if start > 0 { w[start - 1] } else { ' ' };
+    match suffix {
+        "ogi" if before != 'l' => {}
+        "li" if !"cdeghkmnrt".contains(before) => {}
+        _ => replace_suffix(w, suffix.len(), replacement),
+    }
+}
+
+fn step_3(w: &mut Vec<char>, p1: usize, p2: usize) {
+    const RULES: &[(&str, &str)] = &[
+        ("tional", "tion"),
+        ("ational", "ate"),
+        ("alize", "al"),
+        ("icate", "ic"),
+        ("iciti", "ic"),
+        ("ical", "ic"),
+        ("ful", ""),
+        ("ness", ""),
+        ("ative", ""),
+    ];
+    if let Some((suffix, replacement)) = longest_rule(w, RULES) {
+        let start = w.len() - suffix.len();
+        if start >= p1 && (suffix != "ative" || start >= p2) {
+            replace_suffix(w, suffix.len(), replacement);
+        }
+    }
+}
+
+fn step_4(w: &mut Vec<char>, p2: usize) {
+    let suffixes = [
+        "al", "ance", "ence", "er", "ic", "able", "ible", "ant", "ement", "ment", "ent", "ism",
+        "ate", "iti", "ous", "ive", "ize", "ion",
+    ];
+    if let Some(suffix) = longest_suffix(w, &suffixes) {
+        let start = w.len() - suffix.len();
+        if start >= p2 && (suffix != "ion" || (start > 0 && "st".contains(w[start - 1]))) {
+            w.truncate(start);
+        }
+    }
+}
+
+fn step_5(w: &mut Vec<char>, p1: usize, p2: usize) {
+    let start = match w.len().checked_sub(1) {
+        Some(start) => start,
+        None => return,
+    };
+    match w[start] {
+        'e' if start >= p2 || (start >= p1 && !ends_in_short_syllable(w, start)) => {
+            w.pop();
+        }
+        'l' if start >= p2 && start > 0 && w[start - 1] == 'l' => {
+            w.pop();
+        }
+        _ => {}
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+/// Start of the region after the first non-vowel following a vowel, looking
+/// from `from` on; R1 from 0, R2 from R1.
+fn region_after(w: &[char], from: usize) -> usize {
+    let mut i = from;
+    while i < w.len() && !is_vowel(w[i]) {
+        i += 1;
+    }
+    while i < w.len() && is_vowel(w[i]) {
+        i += 1;
+    }
+    (i + 1).min(w.len())
+}
+
+/// Whether `w[..end]` ends in a short syllable: non-vowel, vowel, non-vowel
+/// other than 'w', 'x' or 'Y', or a vowel and a non-vowel starting the word.
+fn ends_in_short_syllable(w: &[char], end: usize) -> bool {
+    match end {
+        2 => is_vowel(w[0]) && !is_vowel(w[1]),
+        n if n > 2 => {
+            !is_vowel(w[n - 3])
+                && is_vowel(w[n - 2])
+                && !is_vowel(w[n - 1])
+                && !matches!(w[n - 1], 'w' | 'x' | 'Y')
+        }
+        _ => false,
+    }
+}
+
+fn starts_with(w: &[char], prefix: &str) -> bool {
+    w.len() >= prefix.len() && w.iter().copied().zip(prefix.chars()).all(|(a, b)| a == b)
+}
+
+fn ends_with(w: &[char], suffix: &str) -> bool {
+    w.len() >= suffix.len()
+        && w.iter()
+            .rev()
+            .copied()
+            .zip(suffix.chars().rev())
+            .all(|(a, b)| a == b)
+}
+
+fn longest_suffix<'a>(w: &[char], suffixes: &[&'a str]) -> Option<&'a str> {
+    suffixes
+        .iter()
+        .copied()
+        .filter(|s| ends_with(w, s))
+        .max_by_key(|s| s.len())
+}
+
+fn longest_rule(
+    w: &[char],
+    rules: &[(&'static str, &'static str)],
+) -> Option<(&'static str, &'static str)> {
+    rules
+        .iter()
+        .copied()
+        .filter(|(s, _r)| ends_with(w, s))
+        .max_by_key(|(s, _r)| s.len())
+}
+
+fn replace_suffix(w: &mut Vec<char>, len: usize, replacement: &str) {
+    w.truncate(w.len() - len);
+    w.extend(replacement.chars());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pairs from the vocabulary and output of the Snowball English stemmer.
+    const REFERENCE: &[(&str, &str)] = &[
+        ("consign", "consign"),
+        ("consigned", "consign"),
+        ("consignment", "consign"),
+        ("consistency", "consist"),
+        ("consistently", "consist"),
+        ("consolation", "consol"),
+        ("consolatory", "consolatori"),
+        ("consolidating", "consolid"),
+        ("consolingly", "consol"),
+        ("consonant", "conson"),
+        ("conspicuously", "conspicu"),
+        ("conspiracy", "conspiraci"),
+        ("conspirators", "conspir"),
+        ("constables", "constabl"),
+        ("constancy", "constanc"),
+        ("knackeries", "knackeri"),
+        ("knaves", "knave"),
+        ("kneaded", "knead"),
+        ("knees", "knee"),
+        ("knightly", "knight"),
+        ("knitting", "knit"),
+        ("knives", "knive"),
+        ("knockers", "knocker"),
+        ("caresses", "caress"),
+        ("ponies", "poni"),
+        ("ties", "tie"),
+        ("cries", "cri"),
+        ("hopefulness", "hope"),
+        ("luxuriating", "luxuri"),
+        ("relational", "relat"),
+        ("happy", "happi"),
+        ("enjoying", "enjoy"),
+    ];
+
+    #[test]
+    fn reference_pairs() {
+        for (word, expected) in REFERENCE {
+            assert_eq!(stem(word), *expected, "stem of {}", word);
+        }
+    }
+
+    #[test]
+    fn exceptions() {
+        for (word, expected) in [
+            ("skis", "ski"),
+            ("skies", "sky"),
+            ("dying", "die"),
+            ("idly", "idl"),
+            ("gently", "gentl"),
+            ("early", "earli"),
+            ("only", "onli"),
+            ("news", "news"),
+            ("atlas", "atlas"),
+            ("inning", "inning"),
+            ("proceed", "proceed"),
+            ("generously", "generous"),
+        ] {
+            assert_eq!(stem(word), expected, "stem of {}", word);
+        }
+    }
+
+    #[test]
+    fn short_words_are_kept() {
+        assert_eq!(stem("is"), "is");
+        assert_eq!(stem("as"), "as");
+    }
+
+    #[test]
+    fn surface_forms_report_the_most_frequent_word() {
+        let stemmer = Stemmer::new(Stemming::SurfaceForms);
+        for word in ["connected", "connection", "connection"] {
+            stemmer.stem(word.to_string());
+        }
+        assert_eq!(stemmer.label("connect"), "connection");
+        assert_eq!(stemmer.stem("connect ideas".to_string()), "connect idea");
+    }
+}
@@ -0,0 +1,249 @@
+use crate::{Error, Result};
+use std::{
+    collections::HashSet,
+    env,
+    ffi::OsString,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Stop word lists used when none are given on the command line, separated
+/// like `PATH` (`:` on unix).
+pub const ENV_VAR: &str = "TF_STOP_WORDS";
+
+/// Where a stop word list comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The English list compiled into the binary.
+    Builtin,
+    /// The list compiled into the binary for the language with this code,
+    /// see [`StopWords::builtin_language`].
+    Language(&'static str),
+    File(PathBuf),
+    /// The lists of the languages the inputs are in, see
+    /// [`crate::Config::stop_word_sources`].
+    Auto,
+}
+
+impl FromStr for Source {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "" => Err(Error::Invalid("empty stop words source".to_string())),
+            "builtin" => Ok(Source::Builtin),
+            "auto" => Ok(Source::Auto),
+            path => Ok(Source::File(path.into())),
+        }
+    }
+}
+
+/// A set of words that are not worth counting.
+#[derive(Debug, Clone, Default)]
+pub struct StopWords {
+    words: HashSet<String>,
+}
+
+/// The same for the same words, whatever their order in the `HashSet`.
+impl Hash for StopWords {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut words: Vec<&String> = self.words.iter().collect();
+        words.sort();
+        words.hash(state);
+    }
+}
+
+impl StopWords {
+    /// Where the exercises expect the list, relative to a `weekN` directory.
+    pub const DEFAULT_PATH: &'static str = "../stop_words.txt";
+    /// Where the lists of each language are, named after their code, e.g.
+    /// `../stop_words/fr.txt`; see [`StopWords::language_sources`].
+    pub const LANGUAGES_PATH: &'static str = "../stop_words";
+    const BUILTIN: &'static str = include_str!("stop_words_en.txt");
+    /// A list for every language of [`crate::language::LANGUAGES`], by code.
+    const BUILTIN_LANGUAGES: &'static [(&'static str, &'static str)] = &[
+        ("en", Self::BUILTIN),
+        ("fr", include_str!("stop_words_fr.txt")),
+        ("de", include_str!("stop_words_de.txt")),
+    ];
+
+    /// Parses a comma (or newline) separated list of words.
+    pub fn parse(s: &str) -> Self {
+        s.split(&[',', '\n'][..])
+            .map(|w| w.trim())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_lowercase())
+            .collect()
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn builtin() -> Self {
+        Self::parse(Self::BUILTIN)
+    }
+
+    /// The list compiled into the binary for the language with this code,
+    /// e.g. `fr`.
+    pub fn builtin_language(code: &str) -> Option<Self> {
+        Self::BUILTIN_LANGUAGES
+            .iter()
+            .find(|(c, _words)| *c == code)
+            .map(|(_code, words)| Self::parse(words))
+    }
+
+    /// All the given lists merged into one.
+    pub fn from_sources(sources: &[Source]) -> Result<Self> {
+        let mut ret = Self::default();
+        for source in sources {
+            let words = match source {
+                Source::Builtin => Self::builtin(),
+                Source::Language(code) => Self::builtin_language(code).ok_or_else(|| {
+                    Error::Invalid(format!("no built-in stop words for language `{}`", code))
+                })?,
+                Source::File(path) => Self::from_file(path).map_err(|e| {
+                    Error::Invalid(format!(
+                        "failed to read stop words `{}`: {}",
+                        path.display(),
+                        e
+                    ))
+                })?,
+                Source::Auto => {
+                    return Err(Error::Invalid(
+                        "`auto` stop words depend on the inputs".to_string(),
+                    ))
+                }
+            };
+            ret.extend(words.words);
+        }
+        Ok(ret)
+    }
+
+    /// The lists in [`ENV_VAR`] if set, otherwise [`Self::DEFAULT_PATH`] if it
+    /// exists, otherwise the builtin list.
+    pub fn default_sources() -> Result<Vec<Source>> {
+        Self::sources_in(env::var_os(ENV_VAR), Path::new(Self::DEFAULT_PATH))
+    }
+
+    /// [`Self::default_sources`], given the value of [`ENV_VAR`] and the
+    /// default path.
+    fn sources_in(var: Option<OsString>, default_path: &Path) -> Result<Vec<Source>> {
+        if let Some(paths) = var {
+            return env::split_paths(&paths)
+                .map(|p| p.to_string_lossy().parse())
+                .collect();
+        }
+        if default_path.exists() {
+            Ok(vec![Source::File(default_path.into())])
+        } else {
+            Ok(vec![Source::Builtin])
+        }
+    }
+
+    /// The list of the language with this code: `<dir>/<code>.txt`, or
+    /// without such a file, the [`StopWords::default_sources`] for English
+    /// and the [`StopWords::builtin_language`] list for the others. `None`
+    /// for a language without a list, whose words are then all counted;
+    /// tell the user to add them as `<dir>/<code>.txt`.
+    pub fn language_sources(code: &str, dir: &Path) -> Result<Option<Vec<Source>>> {
+        let path = dir.join(format!("{}.txt", code));
+        if path.exists() {
+            return Ok(Some(vec![Source::File(path)]));
+        }
+        if code == "en" {
+            return Self::default_sources().map(Some);
+        }
+        Ok(Self::BUILTIN_LANGUAGES
+            .iter()
+            .find(|(c, _words)| *c == code)
+            .map(|(code, _words)| vec![Source::Language(code)]))
+    }
+
+    pub fn load_default() -> Result<Self> {
+        Self::from_sources(&Self::default_sources()?)
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+
+    /// The words, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.words.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+impl std::iter::FromIterator<String> for StopWords {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        Self {
+            words: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<String> for StopWords {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        self.words.extend(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_come_from_the_variable_then_the_default_path_then_the_builtin_list() {
+        let here = Path::new("Cargo.toml");
+        let missing = Path::new("no/such/stop_words.txt");
+        let var = env::join_paths(["a.txt", "builtin"]).unwrap();
+        assert_eq!(
+            StopWords::sources_in(Some(var), here).unwrap(),
+            [Source::File("a.txt".into()), Source::Builtin]
+        );
+        assert_eq!(
+            StopWords::sources_in(None, here).unwrap(),
+            [Source::File("Cargo.toml".into())]
+        );
+        assert_eq!(
+            StopWords::sources_in(None, missing).unwrap(),
+            [Source::Builtin]
+        );
+    }
+
+    #[test]
+    fn lists_are_merged_and_lowercased() {
+        let words = StopWords::parse("The, a\nAND\n\n");
+        assert!(words.contains("the") && words.contains("a") && words.contains("and"));
+        assert_eq!(words.len(), 3);
+        let merged = StopWords::from_sources(&[Source::Builtin, Source::Builtin]).unwrap();
+        assert_eq!(merged.len(), StopWords::builtin().len());
+        assert!(StopWords::from_sources(&[Source::File("no/such/file".into())]).is_err());
+    }
+
+    #[test]
+    fn language_lists_from_the_directory_then_built_in() {
+        let dir = std::env::temp_dir().join(format!("tf-stop-words-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("de.txt"), "und,der").unwrap();
+        assert_eq!(
+            StopWords::language_sources("de", &dir).unwrap(),
+            Some(vec![Source::File(dir.join("de.txt"))])
+        );
+        assert_eq!(
+            StopWords::language_sources("fr", &dir).unwrap(),
+            Some(vec![Source::Language("fr")])
+        );
+        assert_eq!(StopWords::language_sources("xx", &dir).unwrap(), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
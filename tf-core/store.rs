@@ -0,0 +1,392 @@
+use crate::{Error, FrequencyTable, Ranking, Result};
+use std::{fmt, str::FromStr};
+
+/// First line of the text encoding, and the version it is at.
+const TEXT_HEADER: &str = "# tf-table 1";
+/// First bytes of the binary encoding, the last one being the version.
+const BINARY_MAGIC: &[u8] = b"TFTB\x01";
+
+/// How a [`Table`] is written to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// A header, then a `count<TAB>word` line per word; easy to read, to
+    /// `grep` and to `sort -n`.
+    #[default]
+    Text,
+    /// A magic number, then LEB128 varints and UTF-8 words.
+    Binary,
+}
+
+impl FromStr for Encoding {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Encoding::Text),
+            "binary" => Ok(Encoding::Binary),
+            _ => Err(Error::Invalid(format!(
+                "unknown table encoding `{}`, expected text or binary",
+                s
+            ))),
+        }
+    }
+}
+
+/// A frequency table that outlives the process: the counts in rank order,
+/// and the number of words counted, which is more than the sum of the counts
+/// when only some ranks were kept.
+///
+/// The text encoding reads:
+///
+/// ```text
+/// # tf-table 1
+/// # total 12
+/// 5    pride
+/// 3    prejudice
+/// ```
+///
+/// with a tab between each count and its word. Tabs, line breaks and backslashes in words are escaped as `\t`, `\n`,
+/// `\r` and `\\`. [`Table::decode`] tells both encodings apart, so tools read
+/// either.
+///
+/// ```
+/// use tf_core::store::{Encoding, Table};
+/// let table = Table::new(vec![("pride".to_string(), 5), ("prejudice".to_string(), 3)], 12);
+/// for encoding in [Encoding::Text, Encoding::Binary] {
+///     assert_eq!(Table::decode(&table.encode(encoding)).unwrap(), table);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Table {
+    pub entries: Vec<(String, usize)>,
+    pub total: usize,
+}
+
+impl Table {
+    /// `entries` in the order to keep them; `total` is the number of counted
+    /// words.
+    pub fn new(entries: Vec<(String, usize)>, total: usize) -> Self {
+        Self { entries, total }
+    }
+
+    /// Every word of `table`, in first-seen order.
+    pub fn from_counts(table: &FrequencyTable) -> Self {
+        Self::new(Ranking::FirstOccurrence.rank(table), table.total())
+    }
+
+    /// The counts, first-seen in the stored order.
+    pub fn counts(&self) -> FrequencyTable {
+        self.entries.iter().cloned().collect()
+    }
+
+    /// Adds the counts of `other`, words new to this table coming after its
+    /// own.
+    pub fn merge(&mut self, other: &Table) {
+        let mut counts = self.counts();
+        counts.extend(other.entries.iter().cloned());
+        self.entries = Ranking::FirstOccurrence.rank(&counts);
+        self.total += other.total;
+    }
+
+    /// Takes the counts of `other` away, leaving out the words that are no
+    /// longer counted. A count never goes below 0, and the total only goes
+    /// down by what was taken away.
+    pub fn subtract(&mut self, other: &Table) {
+        let removed = other.counts();
+        let mut taken = 0;
+        for (word, count) in &mut self.entries {
+            let n = removed.count(word).min(*count);
+            *count -= n;
+            taken += n;
+        }
+        self.entries.retain(|(_w, c)| *c > 0);
+        self.total = self.total.saturating_sub(taken);
+    }
+
+    pub fn encode(&self, encoding: Encoding) -> Vec<u8> {
+        match encoding {
+            Encoding::Text => {
+                let mut out = String::new();
+                // writing to a String never fails
+                let _ = self.write_text(&mut out);
+                out.into_bytes()
+            }
+            Encoding::Binary => {
+                let mut out = BINARY_MAGIC.to_vec();
+                write_varint(&mut out, self.total);
+                write_varint(&mut out, self.entries.len());
+                for (word, count) in &self.entries {
+                    write_varint(&mut out, *count);
+                    write_varint(&mut out, word.len());
+                    out.extend(word.as_bytes());
+                }
+                out
+            }
+        }
+    }
+
+    /// The text encoding, see [`Table`].
+    pub fn write_text(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        writeln!(out, "{}", TEXT_HEADER)?;
+        writeln!(out, "# total {}", self.total)?;
+        for (word, count) in &self.entries {
+            writeln!(out, "{}\t{}", count, escape(word))?;
+        }
+        Ok(())
+    }
+
+    /// Reads either encoding.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        if let Some(rest) = bytes.strip_prefix(BINARY_MAGIC) {
+            return decode_binary(rest);
+        }
+        if bytes.starts_with(&BINARY_MAGIC[..4]) {
+            return Err(Error::Invalid(format!(
+                "binary frequency table of unknown version {}",
+                bytes.get(4).copied().unwrap_or(0)
+            )));
+        }
+        let text = std::str::from_utf8(bytes)
+            .map_err(|_| Error::Invalid("not a frequency table".to_string()))?;
+        decode_text(text)
+    }
+}
+
+fn decode_text(text: &str) -> Result<Table> {
+    let mut lines = text.lines().enumerate();
+    match lines.next() {
+        Some((_, TEXT_HEADER)) => {}
+        Some((_, line)) if line.starts_with("# tf-table ") => {
+            return Err(Error::Invalid(format!(
+                "frequency table of unknown version `{}`",
+                &line["# tf-table ".len()..]
+            )))
+        }
+        _ => return Err(Error::Invalid("not a frequency table".to_string())),
+    }
+    let mut ret = Table::default();
+    let mut total = None;
+    for (i, line) in lines {
+        let bad = || Error::Invalid(format!("line {} of the frequency table: `{}`", i + 1, line));
+        if let Some(comment) = line.strip_prefix('#') {
+            if let Some(n) = comment.trim().strip_prefix("total ") {
+                total = Some(n.trim().parse().map_err(|_| bad())?);
+            }
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        let (count, word) = line.split_once('\t').ok_or_else(bad)?;
+        let count = count.parse().map_err(|_| bad())?;
+        ret.entries.push((unescape(word).ok_or_else(bad)?, count));
+    }
+    ret.total = total.unwrap_or_else(|| ret.entries.iter().map(|(_w, c)| c).sum());
+    Ok(ret)
+}
+
+fn decode_binary(mut bytes: &[u8]) -> Result<Table> {
+    let truncated = || Error::Invalid("truncated binary frequency table".to_string());
+    let total = read_varint(&mut bytes).ok_or_else(truncated)?;
+    let len = read_varint(&mut bytes).ok_or_else(truncated)?;
+    let mut entries = Vec::new();
+    for _ in 0..len {
+        let count = read_varint(&mut bytes).ok_or_else(truncated)?;
+        let n = read_varint(&mut bytes).ok_or_else(truncated)?;
+        if n > bytes.len() {
+            return Err(truncated());
+        }
+        let (word, rest) = bytes.split_at(n);
+        let word = String::from_utf8(word.to_vec()).map_err(|_| {
+            Error::Invalid("binary frequency table with a word that is not UTF-8".to_string())
+        })?;
+        entries.push((word, count));
+        bytes = rest;
+    }
+    if !bytes.is_empty() {
+        return Err(Error::Invalid(
+            "trailing bytes after the binary frequency table".to_string(),
+        ));
+    }
+    Ok(Table::new(entries, total))
+}
+
+/// Seven bits at a time, lowest first, the high bit telling that more
+/// follow.
+fn write_varint(out: &mut Vec<u8>, mut n: usize) {
+    while n >= 0x80 {
+        out.push((n & 0x7f) as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+/// See [`write_varint`]; `None` at the end of `bytes`, or on overflow.
+fn read_varint(bytes: &mut &[u8]) -> Option<usize> {
+    let mut ret: usize = 0;
+    let mut shift = 0;
+    loop {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        let bits = ((byte & 0x7f) as usize).checked_shl(shift)?;
+        if bits >> shift != (byte & 0x7f) as usize {
+            return None;
+        }
+        ret |= bits;
+        if byte & 0x80 == 0 {
+            return Some(ret);
+        }
+        shift += 7;
+    }
+}
+
+fn escape(word: &str) -> String {
+    let mut ret = String::with_capacity(word.len());
+    for ch in word.chars() {
+        match ch {
+            '\\' => ret.push_str("\\\\"),
+            '\t' => ret.push_str("\\t"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            ch => ret.push(ch),
+        }
+    }
+    ret
+}
+
+/// See [`escape`]; `None` for an unknown escape.
+fn unescape(word: &str) -> Option<String> {
+    let mut ret = String::with_capacity(word.len());
+    let mut chars = word.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            ret.push(ch);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => ret.push('\\'),
+            't' => ret.push('\t'),
+            'n' => ret.push('\n'),
+            'r' => ret.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Table {
+        Table::new(
+            vec![
+                ("pride".to_string(), 5),
+                ("tab\there".to_string(), 3),
+                ("back\\slash\nnew line".to_string(), 300),
+                ("déjà".to_string(), 1),
+            ],
+            1_000_000,
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        for encoding in [Encoding::Text, Encoding::Binary] {
+            assert_eq!(Table::decode(&table().encode(encoding)).unwrap(), table());
+        }
+        let empty = Table::default();
+        assert_eq!(
+            Table::decode(&empty.encode(Encoding::Binary)).unwrap(),
+            empty
+        );
+    }
+
+    #[test]
+    fn varints() {
+        for n in [0, 1, 0x7f, 0x80, 300, usize::MAX] {
+            let mut out = Vec::new();
+            write_varint(&mut out, n);
+            let mut bytes = &out[..];
+            assert_eq!(read_varint(&mut bytes), Some(n));
+            assert!(bytes.is_empty());
+        }
+        // more bits than a usize holds
+        let mut bytes = &[0xff; 11][..];
+        assert_eq!(read_varint(&mut bytes), None);
+    }
+
+    #[test]
+    fn corrupt_binary() {
+        let bytes = table().encode(Encoding::Binary);
+        for len in BINARY_MAGIC.len()..bytes.len() {
+            assert!(
+                Table::decode(&bytes[..len]).is_err(),
+                "truncated at {}",
+                len
+            );
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Table::decode(&trailing).is_err());
+        let mut version = bytes;
+        version[4] = 9;
+        let err = Table::decode(&version).unwrap_err().to_string();
+        assert!(err.contains("unknown version 9"), "{}", err);
+    }
+
+    #[test]
+    fn corrupt_text() {
+        for text in [
+            "",
+            "pride 5",
+            "# tf-table 2\n5\tpride\n",
+            "# tf-table 1\n5 pride\n",
+            "# tf-table 1\nfive\tpride\n",
+            "# tf-table 1\n5\tbad\\escape\n",
+            "# tf-table 1\n# total many\n",
+        ] {
+            assert!(Table::decode(text.as_bytes()).is_err(), "{:?}", text);
+        }
+        assert!(Table::decode(b"\xff\xfe").is_err());
+    }
+
+    #[test]
+    fn total_defaults_to_the_sum() {
+        let table = Table::decode(b"# tf-table 1\n5\tpride\n\n3\tprejudice\n").unwrap();
+        assert_eq!(table.total, 8);
+    }
+
+    fn words(table: &Table) -> Vec<(&str, usize)> {
+        table
+            .entries
+            .iter()
+            .map(|(w, c)| (w.as_str(), *c))
+            .collect()
+    }
+
+    #[test]
+    fn merge_keeps_the_first_seen_order() {
+        let mut first = Table::new(vec![("b".to_string(), 2), ("a".to_string(), 1)], 3);
+        first.merge(&Table::new(
+            vec![("c".to_string(), 4), ("a".to_string(), 2)],
+            6,
+        ));
+        assert_eq!(words(&first), [("b", 2), ("a", 3), ("c", 4)]);
+        assert_eq!(first.total, 9);
+    }
+
+    #[test]
+    fn subtract_takes_away_no_more_than_counted() {
+        let mut first = Table::new(
+            vec![("pride".to_string(), 42), ("darcy".to_string(), 3)],
+            45,
+        );
+        let other = Table::new(
+            vec![("darcy".to_string(), 5), ("emma".to_string(), 100)],
+            105,
+        );
+        first.subtract(&other);
+        assert_eq!(words(&first), [("pride", 42)]);
+        assert_eq!(first.total, 42);
+    }
+}
@@ -0,0 +1,379 @@
+use crate::{
+    contractions::{self, Contractions, Rules},
+    Error, Result,
+};
+use std::{iter, str::FromStr};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How text is cut into words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Segmentation {
+    /// Runs of `[a-zA-Z]`.
+    Ascii,
+    /// Runs of `[a-zA-Z0-9]`.
+    AsciiAlphanumeric,
+    /// Unicode word boundaries (UAX #29), so that "déjà" or "größer" stay
+    /// in one piece.
+    Unicode,
+}
+
+impl FromStr for Segmentation {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ascii" => Ok(Segmentation::Ascii),
+            "alnum" => Ok(Segmentation::AsciiAlphanumeric),
+            "unicode" => Ok(Segmentation::Unicode),
+            _ => Err(Error::Invalid(format!(
+                "unknown tokenizer `{}`, expected ascii, alnum or unicode",
+                s
+            ))),
+        }
+    }
+}
+
+/// Splits text into words.
+///
+/// Words are lowercased, contractions and possessives normalized, and must
+/// be at least `min_len` characters (not bytes) long.
+///
+/// # Example
+/// ```
+/// use tf_core::{tokenizer::Segmentation, Tokenizer};
+/// let tokenizer = Tokenizer::default().with_segmentation(Segmentation::Unicode);
+/// let words: Vec<_> = tokenizer.words("Déjà vu, à la carte").collect();
+/// assert_eq!(words, ["déjà", "vu", "la", "carte"]);
+/// ```
+///
+/// ```
+/// use tf_core::{contractions::Contractions, Tokenizer};
+/// let tokenizer = Tokenizer::default()
+///     .with_contractions(Contractions::Expand)
+///     .with_strip_possessives(true);
+/// let words: Vec<_> = tokenizer.words("Darcy’s friends don't dance").collect();
+/// assert_eq!(words, ["darcy", "friends", "do", "not", "dance"]);
+/// ```
+#[derive(Debug, Clone, Hash)]
+pub struct Tokenizer {
+    segmentation: Segmentation,
+    min_len: usize,
+    contractions: Contractions,
+    /// For [`Contractions::Expand`], and to tell "it's" from a possessive.
+    rules: Rules,
+    strip_possessives: bool,
+    /// Split off before anything else, see [`Self::with_elisions`].
+    elisions: Vec<String>,
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Self::new(Segmentation::AsciiAlphanumeric, 2)
+    }
+}
+
+impl Tokenizer {
+    pub fn new(segmentation: Segmentation, min_len: usize) -> Self {
+        Self {
+            segmentation,
+            min_len,
+            contractions: Contractions::default(),
+            rules: Rules::english(),
+            strip_possessives: false,
+            elisions: Vec::new(),
+        }
+    }
+
+    pub fn with_segmentation(self, segmentation: Segmentation) -> Self {
+        Self {
+            segmentation,
+            ..self
+        }
+    }
+
+    pub fn with_min_len(self, min_len: usize) -> Self {
+        Self { min_len, ..self }
+    }
+
+    pub fn with_contractions(self, contractions: Contractions) -> Self {
+        Self {
+            contractions,
+            ..self
+        }
+    }
+
+    /// The contractions to expand, English by default.
+    pub fn with_rules(self, rules: Rules) -> Self {
+        Self { rules, ..self }
+    }
+
+    /// Count "Darcy's" as `darcy`.
+    pub fn with_strip_possessives(self, strip_possessives: bool) -> Self {
+        Self {
+            strip_possessives,
+            ..self
+        }
+    }
+
+    /// Count "l'homme" as `l` and `homme` whatever `--contractions` says,
+    /// when `l` is one of `elisions`: in French every article and pronoun
+    /// is written short before a vowel, and each is a word of its own.
+    pub fn with_elisions(self, elisions: Vec<String>) -> Self {
+        Self { elisions, ..self }
+    }
+
+    pub fn segmentation(&self) -> Segmentation {
+        self.segmentation
+    }
+
+    /// The fewest characters a word has, see [`Self::accepts`].
+    pub fn min_len(&self) -> usize {
+        self.min_len
+    }
+
+    /// Whether an apostrophe between two letters is part of a word, so that
+    /// [`Self::normalize`] gets "don't" or "Darcy's" whole.
+    pub fn keeps_apostrophes(&self) -> bool {
+        self.contractions != Contractions::Segmented || self.strip_possessives
+    }
+
+    /// Whether `ch` can be part of a word. With Unicode segmentation this
+    /// is only an approximation, see [`Self::blank_non_words`].
+    pub fn is_word_char(&self, ch: char) -> bool {
+        match self.segmentation {
+            Segmentation::Ascii => ch.is_ascii_alphabetic(),
+            Segmentation::AsciiAlphanumeric => ch.is_ascii_alphanumeric(),
+            Segmentation::Unicode => ch.is_alphanumeric(),
+        }
+    }
+
+    /// Raw words of `text`, neither lowercased nor filtered.
+    pub fn segments<'a>(&'a self, text: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        match self.segmentation {
+            Segmentation::Unicode => Box::new(text.unicode_words()),
+            _ if self.keeps_apostrophes() => Box::new(self.apostrophe_segments(text)),
+            _ => Box::new(
+                text.split(move |ch: char| !self.is_word_char(ch))
+                    .filter(|s| !s.is_empty()),
+            ),
+        }
+    }
+
+    /// Runs of word characters, an apostrophe between two of them included.
+    fn apostrophe_segments<'a>(&'a self, text: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let mut chars = text.char_indices().peekable();
+        iter::from_fn(move || {
+            let (start, _ch) = chars.find(|&(_i, ch)| self.is_word_char(ch))?;
+            let mut end = text.len();
+            while let Some(&(i, ch)) = chars.peek() {
+                let joins = self.is_word_char(ch)
+                    || contractions::is_apostrophe(ch) && {
+                        let mut ahead = chars.clone();
+                        ahead.next();
+                        ahead
+                            .peek()
+                            .is_some_and(|&(_i, next)| self.is_word_char(next))
+                    };
+                if !joins {
+                    end = i;
+                    break;
+                }
+                chars.next();
+            }
+            Some(&text[start..end])
+        })
+    }
+
+    /// Like [`Self::segments`], with the byte offset of each word in `text`.
+    pub fn segment_indices<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> Box<dyn Iterator<Item = (usize, &'a str)> + 'a> {
+        match self.segmentation {
+            Segmentation::Unicode => Box::new(text.unicode_word_indices()),
+            // the pieces of `split` are slices of `text`
+            _ => Box::new(
+                self.segments(text)
+                    .map(move |s| (s.as_ptr() as usize - text.as_ptr() as usize, s)),
+            ),
+        }
+    }
+
+    /// `text` with everything but the words replaced by spaces, so that the
+    /// words can be recovered by splitting on `' '`.
+    pub fn blank_non_words(&self, text: &str) -> String {
+        if self.segmentation != Segmentation::Unicode && !self.keeps_apostrophes() {
+            return text
+                .chars()
+                .map(|ch| if self.is_word_char(ch) { ch } else { ' ' })
+                .collect();
+        }
+        let mut ret = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, word) in self.segment_indices(text) {
+            ret.extend(text[last..start].chars().map(|_| ' '));
+            ret.push_str(word);
+            last = start + word.len();
+        }
+        ret.extend(text[last..].chars().map(|_| ' '));
+        ret
+    }
+
+    /// Whether a (normalized) word is long enough to be counted.
+    pub fn accepts(&self, word: &str) -> bool {
+        word.chars().count() >= self.min_len
+    }
+
+    /// `segment` without its possessive `'s`, when possessives are stripped
+    /// and it is not a contraction such as "it's": "Darcy's" as `Darcy`.
+    pub fn possessive_stem<'a>(&self, segment: &'a str) -> &'a str {
+        if !self.strip_possessives {
+            return segment;
+        }
+        let mut chars = segment.char_indices().rev();
+        match (chars.next(), chars.next()) {
+            (Some((_, 's' | 'S')), Some((i, ch)))
+                if i > 0
+                    && contractions::is_apostrophe(ch)
+                    && self
+                        .rules
+                        .find(&contractions::normalize_apostrophes(
+                            &segment.to_lowercase(),
+                        ))
+                        .is_none() =>
+            {
+                &segment[..i]
+            }
+            _ => segment,
+        }
+    }
+
+    /// The elided word `segment` starts with, e.g. `qu` of "qu'il", and
+    /// the rest of it, see [`Self::with_elisions`].
+    fn elision<'a>(&self, segment: &'a str) -> Option<(&'a str, &'a str)> {
+        let (elided, rest) = segment.split_once('\'')?;
+        let found = !rest.is_empty() && self.elisions.iter().any(|e| e == elided);
+        found.then_some((elided, rest))
+    }
+
+    /// The words a lowercased segment counts as: elisions split off,
+    /// contractions kept whole, expanded or, by default, split as the
+    /// segmentation does, and possessives stripped, see
+    /// [`Self::possessive_stem`]. A segment without an apostrophe is a word
+    /// as is, and every apostrophe is written `'`.
+    pub fn normalize(&self, segment: String) -> impl Iterator<Item = String> {
+        if !segment.contains(contractions::is_apostrophe) {
+            return Words::One(Some(segment));
+        }
+        let word = contractions::normalize_apostrophes(&segment);
+        if let Some((elided, rest)) = self.elision(&word) {
+            let rest: Vec<String> = self.normalize(rest.to_string()).collect();
+            let words: Vec<String> = iter::once(elided.to_string()).chain(rest).collect();
+            return Words::Many(words.into_iter());
+        }
+        if !self.keeps_apostrophes() {
+            // only Unicode segmentation leaves an apostrophe in a segment
+            let words: Vec<String> = word.split('\'').map(str::to_string).collect();
+            return Words::Many(words.into_iter());
+        }
+        if self.contractions == Contractions::Expand {
+            if let Some((stem, to)) = self.rules.find(&word) {
+                let stem = Some(stem.to_string()).filter(|s| !s.is_empty());
+                let words: Vec<String> = stem.into_iter().chain(to.iter().cloned()).collect();
+                return Words::Many(words.into_iter());
+            }
+        }
+        let word = self.possessive_stem(&word);
+        match self.contractions {
+            Contractions::Segmented => {
+                let words: Vec<String> = word.split('\'').map(str::to_string).collect();
+                Words::Many(words.into_iter())
+            }
+            _ => Words::One(Some(word.to_string())),
+        }
+    }
+
+    /// Lowercased words of `text` that pass the length filter.
+    pub fn words<'a>(&'a self, text: &'a str) -> impl Iterator<Item = String> + 'a {
+        self.segments(text)
+            .map(|s| s.to_lowercase())
+            .flat_map(move |s| self.normalize(s))
+            .filter(move |w| self.accepts(w))
+    }
+}
+
+/// What [`Tokenizer::normalize`] makes of a segment, without allocating for
+/// the usual single word.
+enum Words {
+    One(Option<String>),
+    Many(std::vec::IntoIter<String>),
+}
+
+impl Iterator for Words {
+    type Item = String;
+    fn next(&mut self) -> Option<String> {
+        match self {
+            Words::One(word) => word.take(),
+            Words::Many(words) => words.next(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(tokenizer: &Tokenizer, text: &str) -> Vec<String> {
+        tokenizer.words(text).collect()
+    }
+
+    #[test]
+    fn apostrophes_make_the_same_word() {
+        let tokenizer = Tokenizer::default().with_segmentation(Segmentation::Unicode);
+        assert_eq!(
+            words(&tokenizer, "don\u{2019}t"),
+            words(&tokenizer, "don't")
+        );
+        assert_eq!(words(&tokenizer, "Darcy\u{2019}s"), ["darcy"]);
+        assert_eq!(words(&tokenizer, "Darcy's"), ["darcy"]);
+    }
+
+    #[test]
+    fn unicode_splits_contractions_by_default() {
+        let tokenizer = Tokenizer::default().with_segmentation(Segmentation::Unicode);
+        assert_eq!(words(&tokenizer, "Don't they'll"), ["don", "they", "ll"]);
+        let keep = tokenizer.with_contractions(Contractions::Keep);
+        assert_eq!(words(&keep, "Don't they'll"), ["don't", "they'll"]);
+    }
+
+    #[test]
+    fn contractions_split_kept_or_expanded() {
+        let text = "Don\u{2019}t they'll";
+        assert_eq!(words(&Tokenizer::default(), text), ["don", "they", "ll"]);
+        let keep = Tokenizer::default().with_contractions(Contractions::Keep);
+        assert_eq!(words(&keep, text), ["don't", "they'll"]);
+        let expand = Tokenizer::default().with_contractions(Contractions::Expand);
+        assert_eq!(words(&expand, text), ["do", "not", "they", "will"]);
+    }
+
+    #[test]
+    fn possessives_but_not_contractions_are_stripped() {
+        let tokenizer = Tokenizer::default()
+            .with_contractions(Contractions::Keep)
+            .with_strip_possessives(true);
+        assert_eq!(words(&tokenizer, "Darcy\u{2019}s it's"), ["darcy", "it's"]);
+        let kept = Tokenizer::default().with_contractions(Contractions::Keep);
+        assert_eq!(words(&kept, "Darcy's"), ["darcy's"]);
+    }
+
+    #[test]
+    fn segmentations_and_lengths() {
+        let text = "R2D2 größer a";
+        let ascii = Tokenizer::new(Segmentation::Ascii, 1);
+        assert_eq!(words(&ascii, text), ["r", "d", "gr", "er", "a"]);
+        assert_eq!(words(&Tokenizer::default(), text), ["r2d2", "gr", "er"]);
+        let unicode = Tokenizer::default().with_segmentation(Segmentation::Unicode);
+        assert_eq!(words(&unicode, text), ["r2d2", "größer"]);
+        assert_eq!(unicode.blank_non_words("a, b!"), "a  b ");
+        assert!("words".parse::<Segmentation>().is_err());
+    }
+}
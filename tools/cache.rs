@@ -0,0 +1,256 @@
+//! Cache
+//! ==============================
+//! With `--cache <dir>`, or `TF_CACHE`, the counts of each run are kept,
+//! keyed by a hash of the inputs and of the counting options, so that the
+//! same analysis of the same texts skips reading and tokenizing. A new text
+//! or a new option is a new key, so nothing needs invalidating by hand; this
+//! tells how well the cache does, and removes what is no longer used.
+
+use std::{
+    env,
+    fmt::Write,
+    process,
+    time::{Duration, SystemTime},
+};
+use tf_core::{
+    cache::{Cache, Stats},
+    config::Args,
+    output::json_string,
+    Config, Error, Format, Result,
+};
+
+const USAGE: &str = "\
+Usage: cache <command> [options]
+
+Commands:
+    stats                         print the entries, their size, and the hits
+                                  and misses of the runs so far
+    prune                         remove the entries that are too old, then the
+                                  least recently used ones until the rest fit
+    clear                         remove every entry and the stats
+
+Options:
+    --cache <dir>                 the cache directory (default `TF_CACHE`)
+    --older-than <days>           with prune, remove entries not used for more
+                                  than this many days, e.g. `30` or `0.5`
+    --max-size <size>             with prune, keep at most this many bytes,
+                                  e.g. `500k`, `20M` or `1G`
+    --format <text|json>          how stats prints (default text)
+    -h, --help                    print this message
+";
+
+const DAY: f64 = 24.0 * 60.0 * 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Stats,
+    Prune,
+    Clear,
+}
+
+/// The command line: what to do, and to which cache.
+#[derive(Debug)]
+struct Options {
+    command: Command,
+    cache: Cache,
+    older_than: Option<Duration>,
+    max_size: Option<u64>,
+    format: Format,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self> {
+        let mut args = Args::new(args);
+        let command = match args.next().as_deref() {
+            Some("stats") => Command::Stats,
+            Some("prune") => Command::Prune,
+            Some("clear") => Command::Clear,
+            Some(other) => {
+                return Err(Error::Invalid(format!(
+                    "unknown command `{}`, expected stats, prune or clear",
+                    other
+                )))
+            }
+            None => return Err(Error::Invalid("no command given".to_string())),
+        };
+        let mut older_than = None;
+        let mut max_size = None;
+        let mut shared = Vec::new();
+        while let Some(arg) = args.next() {
+            match args.flag() {
+                "--older-than" => {
+                    let v = args.value()?;
+                    older_than = match v.parse::<f64>() {
+                        Ok(days) if days >= 0.0 && days.is_finite() => {
+                            Some(Duration::from_secs_f64(days * DAY))
+                        }
+                        _ => {
+                            return Err(Error::Invalid(format!(
+                                "`--older-than` expects a number of days, got `{}`",
+                                v
+                            )))
+                        }
+                    };
+                }
+                "--max-size" => max_size = Some(parse_size(&args.value()?)?),
+                _ => shared.push(arg),
+            }
+        }
+        let config = Config::parse(shared)?;
+        if !config.inputs.is_empty() {
+            return Err(Error::Invalid(format!(
+                "unexpected argument `{}`",
+                config.inputs[0]
+            )));
+        }
+        let cache = config.cache().ok_or_else(|| {
+            Error::Invalid("no cache directory, see `--cache` or `TF_CACHE`".to_string())
+        })?;
+        if command != Command::Prune && (older_than.is_some() || max_size.is_some()) {
+            return Err(Error::Invalid(
+                "`--older-than` and `--max-size` apply to prune".to_string(),
+            ));
+        }
+        if command == Command::Prune && older_than.is_none() && max_size.is_none() {
+            return Err(Error::Invalid(
+                "prune needs `--older-than` or `--max-size`, see also clear".to_string(),
+            ));
+        }
+        Ok(Options {
+            command,
+            cache,
+            older_than,
+            max_size,
+            format: config.format,
+        })
+    }
+}
+
+/// `500`, `500k`, `20M` or `1G`, in bytes, the suffixes counting by 1024.
+fn parse_size(s: &str) -> Result<u64> {
+    let (number, unit) = match s.char_indices().last() {
+        Some((i, 'k')) | Some((i, 'K')) => (&s[..i], 1 << 10),
+        Some((i, 'm')) | Some((i, 'M')) => (&s[..i], 1 << 20),
+        Some((i, 'g')) | Some((i, 'G')) => (&s[..i], 1 << 30),
+        _ => (s, 1),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or_else(|| {
+            Error::Invalid(format!(
+                "`--max-size` expects a size such as `500k` or `20M`, got `{}`",
+                s
+            ))
+        })
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        process::exit(if args.is_empty() { 2 } else { 0 });
+    }
+    if let Err(e) = Options::parse(args).and_then(run) {
+        eprintln!("cache: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: Options) -> Result<()> {
+    let cache = &options.cache;
+    match options.command {
+        Command::Stats => print!("{}", render(cache, &cache.stats()?, options.format)?),
+        Command::Prune | Command::Clear => {
+            let pruned = if options.command == Command::Clear {
+                cache.clear()?
+            } else {
+                cache.prune(options.older_than, options.max_size)?
+            };
+            println!(
+                "removed {} entries, {}",
+                pruned.entries,
+                human_size(pruned.bytes)
+            );
+        }
+    }
+    Ok(())
+}
+
+fn render(cache: &Cache, stats: &Stats, format: Format) -> Result<String> {
+    let mut out = String::new();
+    // writing to a String never fails
+    let _ = match format {
+        Format::Text => write_text(&mut out, cache, stats),
+        Format::Json => write_json(&mut out, cache, stats),
+        _ => return Err(Error::Invalid("stats prints text or json".to_string())),
+    };
+    Ok(out)
+}
+
+/// Seconds since `time`.
+fn age(time: SystemTime) -> u64 {
+    SystemTime::now()
+        .duration_since(time)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn write_text(out: &mut String, cache: &Cache, stats: &Stats) -> std::fmt::Result {
+    let used = |time: Option<SystemTime>| match time {
+        Some(time) => format!("{} ago", human_age(age(time))),
+        None => "-".to_string(),
+    };
+    writeln!(out, "directory: {}", cache.dir().display())?;
+    writeln!(out, "entries: {}", stats.entries)?;
+    writeln!(out, "size: {}", human_size(stats.bytes))?;
+    writeln!(out, "hits: {}", stats.hits)?;
+    writeln!(out, "misses: {}", stats.misses)?;
+    writeln!(out, "hit rate: {:.1}%", stats.hit_rate() * 100.0)?;
+    writeln!(out, "least recently used: {}", used(stats.oldest))?;
+    writeln!(out, "most recently used: {}", used(stats.newest))
+}
+
+fn write_json(out: &mut String, cache: &Cache, stats: &Stats) -> std::fmt::Result {
+    let used = |time: Option<SystemTime>| time.map_or("null".to_string(), |t| age(t).to_string());
+    writeln!(out, "{{")?;
+    writeln!(
+        out,
+        "  \"directory\": {},",
+        json_string(&cache.dir().display().to_string())
+    )?;
+    writeln!(out, "  \"entries\": {},", stats.entries)?;
+    writeln!(out, "  \"bytes\": {},", stats.bytes)?;
+    writeln!(out, "  \"hits\": {},", stats.hits)?;
+    writeln!(out, "  \"misses\": {},", stats.misses)?;
+    writeln!(out, "  \"hit_rate\": {},", stats.hit_rate())?;
+    writeln!(out, "  \"oldest_seconds\": {},", used(stats.oldest))?;
+    writeln!(out, "  \"newest_seconds\": {}", used(stats.newest))?;
+    writeln!(out, "}}")
+}
+
+/// `512 B`, `1.5 KiB`, `20.0 MiB`...
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// `40s`, `12m`, `5h` or `3d`.
+fn human_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
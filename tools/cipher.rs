@@ -0,0 +1,589 @@
+//! Cipher
+//! ==============================
+//! Not a style: letters instead of words. The letter and letter-pair
+//! frequencies of a reference text, e.g. Pride and Prejudice, tell what
+//! English looks like, which is all it takes to break the classic ciphers:
+//! try the keys and keep the one whose plaintext fits best.
+//!
+//! - Caesar: each of the 26 shifts is scored with chi-squared.
+//! - Atbash: there is no key, the alphabet is simply reversed.
+//! - Vigenère: for each key length, every n-th letter was shifted by the same
+//!   letter of the key, so each column is a Caesar cipher of its own.
+//!
+//! The candidates are then compared by how likely their letter pairs are, so
+//! that a long Vigenère key that happens to fit the single letters doesn't
+//! win over the real plaintext.
+
+use std::{env, fmt::Write, process, str::FromStr};
+use tf_core::{
+    config::Args,
+    letters::{self, LetterFrequencies},
+    output::json_string,
+    Config, Error, Format, Input, Report, Result,
+};
+
+const USAGE: &str = "\
+Usage: cipher <command> [options] <input>...
+
+Commands:
+    letters                       count the letters of the inputs
+    crack                         find the cipher and key of the inputs, print them
+                                  and the plaintext
+    encrypt                       encrypt the inputs with `--cipher` and `--key`
+    decrypt                       decrypt the inputs with `--cipher` and `--key`
+
+Options:
+    --reference <path>            the text crack expects the plaintext to look like
+                                  (default ../pride-and-prejudice.txt)
+    --cipher <caesar|atbash|vigenere>
+                                  the cipher to use; crack tries all of them when
+                                  it is not given
+    --key <key>                   a shift from 0 to 25, or the letter `a` becomes,
+                                  for caesar; a word for vigenere
+    --max-key-length <n>          the longest vigenere key crack tries (default 12)
+    --bigrams                     with letters, count pairs of adjacent letters
+    -h, --help                    print this message
+
+Inputs, `--format`, `--rank`, `--top`, `--offset`, `--min-count`, `--all` and
+`--per-file` work as for the word counting styles.
+";
+
+const DEFAULT_REFERENCE: &str = "../pride-and-prejudice.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Letters,
+    Crack,
+    Encrypt,
+    Decrypt,
+}
+
+impl FromStr for Command {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "letters" => Ok(Command::Letters),
+            "crack" => Ok(Command::Crack),
+            "encrypt" => Ok(Command::Encrypt),
+            "decrypt" => Ok(Command::Decrypt),
+            _ => Err(Error::Invalid(format!(
+                "unknown command `{}`, expected letters, crack, encrypt or decrypt",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cipher {
+    Caesar,
+    Atbash,
+    Vigenere,
+}
+
+impl FromStr for Cipher {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "caesar" => Ok(Cipher::Caesar),
+            "atbash" => Ok(Cipher::Atbash),
+            "vigenere" => Ok(Cipher::Vigenere),
+            _ => Err(Error::Invalid(format!(
+                "unknown cipher `{}`, expected caesar, atbash or vigenere",
+                s
+            ))),
+        }
+    }
+}
+
+impl Cipher {
+    fn name(self) -> &'static str {
+        match self {
+            Cipher::Caesar => "caesar",
+            Cipher::Atbash => "atbash",
+            Cipher::Vigenere => "vigenere",
+        }
+    }
+
+    /// `key` as given with `--key`: one shift for caesar, one per letter for
+    /// vigenere and none for atbash.
+    fn parse_key(self, key: &str) -> Result<Vec<usize>> {
+        let invalid = |expected: &str| {
+            Error::Invalid(format!(
+                "invalid {} key `{}`, expected {}",
+                self.name(),
+                key,
+                expected
+            ))
+        };
+        match self {
+            Cipher::Caesar => match (key.parse::<usize>(), letter_indices(key)) {
+                (Ok(shift), _) if shift < 26 => Ok(vec![shift]),
+                (_, Some(shift)) if shift.len() == 1 => Ok(shift),
+                _ => Err(invalid("a shift from 0 to 25 or a single letter")),
+            },
+            Cipher::Atbash => Ok(Vec::new()),
+            Cipher::Vigenere => match letter_indices(key) {
+                Some(shifts) if !shifts.is_empty() => Ok(shifts),
+                _ => Err(invalid("a word of letters a to z")),
+            },
+        }
+    }
+
+    /// How the key is shown, `None` for atbash.
+    fn key_name(self, key: &[usize]) -> Option<String> {
+        match self {
+            Cipher::Caesar => Some(key[0].to_string()),
+            Cipher::Atbash => None,
+            Cipher::Vigenere => Some(key.iter().map(|&i| letters::letter(i)).collect()),
+        }
+    }
+
+    fn encrypt(self, key: &[usize], text: &str) -> String {
+        match self {
+            Cipher::Caesar | Cipher::Vigenere => shift(text, key, false),
+            Cipher::Atbash => atbash(text),
+        }
+    }
+
+    fn decrypt(self, key: &[usize], text: &str) -> String {
+        match self {
+            Cipher::Caesar | Cipher::Vigenere => shift(text, key, true),
+            Cipher::Atbash => atbash(text),
+        }
+    }
+}
+
+/// The command line: the command and its options, and the options shared
+/// with the styles.
+#[derive(Debug)]
+struct Options {
+    command: Command,
+    reference: String,
+    cipher: Option<Cipher>,
+    key: Option<String>,
+    max_key_length: usize,
+    bigrams: bool,
+    config: Config,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self> {
+        let mut args = Args::new(args);
+        let command = args
+            .next()
+            .ok_or_else(|| Error::Invalid("no command given".to_string()))?
+            .parse()?;
+        let mut options = Options {
+            command,
+            reference: DEFAULT_REFERENCE.to_string(),
+            cipher: None,
+            key: None,
+            max_key_length: 12,
+            bigrams: false,
+            config: Config::default(),
+        };
+        let mut shared = Vec::new();
+        while let Some(arg) = args.next() {
+            match args.flag() {
+                "--reference" => options.reference = args.value()?,
+                "--cipher" => options.cipher = Some(args.value()?.parse()?),
+                "--key" => options.key = Some(args.value()?),
+                "--max-key-length" => options.max_key_length = args.positive()?,
+                "--bigrams" => options.bigrams = args.switch()?,
+                "--" => {
+                    shared.push(arg);
+                    shared.extend(args.by_ref());
+                }
+                _ => shared.push(arg),
+            }
+        }
+        // no cache: letters and letter pairs are counted, not words
+        options.config = Config::parse(shared)?.uncached()?;
+        Ok(options)
+    }
+
+    /// The cipher and key of encrypt and decrypt.
+    fn cipher_and_key(&self) -> Result<(Cipher, Vec<usize>)> {
+        let cipher = self
+            .cipher
+            .ok_or_else(|| Error::Invalid("`--cipher` is required".to_string()))?;
+        let key = match (&self.key, cipher) {
+            (Some(key), _) => cipher.parse_key(key)?,
+            (None, Cipher::Atbash) => Vec::new(),
+            (None, _) => return Err(Error::Invalid("`--key` is required".to_string())),
+        };
+        Ok((cipher, key))
+    }
+}
+
+/// A way to read the ciphertext and how well its plaintext fits the
+/// reference.
+#[derive(Debug, Clone)]
+struct Solution {
+    cipher: Cipher,
+    key: Vec<usize>,
+    plaintext: String,
+    /// Of the plaintext letters, lower is better.
+    chi_squared: f64,
+    /// Of the plaintext letter pairs, higher is better.
+    fitness: f64,
+}
+
+impl Solution {
+    fn new(
+        cipher: Cipher,
+        key: Vec<usize>,
+        ciphertext: &str,
+        reference: &LetterFrequencies,
+    ) -> Self {
+        let plaintext = cipher.decrypt(&key, ciphertext);
+        let observed = LetterFrequencies::from_text(&plaintext);
+        Solution {
+            cipher,
+            key,
+            chi_squared: reference.chi_squared(&observed),
+            fitness: reference.bigram_fitness(&observed),
+            plaintext,
+        }
+    }
+
+    fn render(&self, format: Format, file: Option<&str>) -> Result<String> {
+        let mut out = String::new();
+        let key = self.cipher.key_name(&self.key);
+        // writing to a String never fails
+        let _ = match format {
+            Format::Text => write_text(&mut out, self, key, file),
+            Format::Json => write_json(&mut out, self, key, file),
+            _ => return Err(Error::Invalid("crack prints text or json".to_string())),
+        };
+        Ok(out)
+    }
+}
+
+fn write_text(
+    out: &mut String,
+    solution: &Solution,
+    key: Option<String>,
+    file: Option<&str>,
+) -> std::fmt::Result {
+    if let Some(file) = file {
+        writeln!(out, "==> {} <==", file)?;
+    }
+    writeln!(out, "cipher: {}", solution.cipher.name())?;
+    if let Some(key) = key {
+        writeln!(out, "key: {}", key)?;
+    }
+    writeln!(out, "chi-squared: {:.2}", solution.chi_squared)?;
+    writeln!(out, "plaintext:")?;
+    write!(out, "{}", solution.plaintext)?;
+    if file.is_some() {
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn write_json(
+    out: &mut String,
+    solution: &Solution,
+    key: Option<String>,
+    file: Option<&str>,
+) -> std::fmt::Result {
+    writeln!(out, "{{")?;
+    if let Some(file) = file {
+        writeln!(out, "  \"file\": {},", json_string(file))?;
+    }
+    writeln!(
+        out,
+        "  \"cipher\": {},",
+        json_string(solution.cipher.name())
+    )?;
+    match key {
+        Some(key) => writeln!(out, "  \"key\": {},", json_string(&key))?,
+        None => writeln!(out, "  \"key\": null,")?,
+    }
+    writeln!(out, "  \"chi_squared\": {},", solution.chi_squared)?;
+    writeln!(out, "  \"bigram_fitness\": {},", solution.fitness)?;
+    writeln!(out, "  \"plaintext\": {}", json_string(&solution.plaintext))?;
+    writeln!(out, "}}")
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        process::exit(if args.is_empty() { 2 } else { 0 });
+    }
+    if let Err(e) = Options::parse(args).and_then(run) {
+        eprintln!("cipher: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: Options) -> Result<()> {
+    // the output is the text itself: each input as it is, --per-file makes
+    // no difference
+    if let Command::Encrypt | Command::Decrypt = options.command {
+        let (cipher, key) = options.cipher_and_key()?;
+        for input in options.config.sources()? {
+            let text = Input::Verbatim(Box::new(input)).read_to_string()?;
+            let out = match options.command {
+                Command::Encrypt => cipher.encrypt(&key, &text),
+                _ => cipher.decrypt(&key, &text),
+            };
+            if out.ends_with('\n') {
+                print!("{}", out);
+            } else {
+                println!("{}", out);
+            }
+        }
+        return Ok(());
+    }
+    let reference = match options.command {
+        Command::Crack => Some(
+            Input::read_all(
+                &options
+                    .config
+                    .expand(std::slice::from_ref(&options.reference))?,
+            )
+            .map(|text| LetterFrequencies::from_text(&text))?,
+        ),
+        _ => None,
+    };
+    // with --per-file: each input alone, then all of them together
+    for config in options.config.runs()? {
+        let text = Input::read_all(&config.sources()?)?;
+        match options.command {
+            Command::Letters => {
+                let frequencies = LetterFrequencies::from_text(&text);
+                let table = if options.bigrams {
+                    frequencies.bigrams()
+                } else {
+                    frequencies.letters()
+                };
+                Report::new(config.ranking.rank(&table), table.total())
+                    .ranked()
+                    .ranked_by(config.ranking)
+                    .paged(&config.page)
+                    .of_file(config.file.clone())
+                    .after_file(config.after_file)
+                    .print(config.format);
+            }
+            Command::Crack => {
+                let reference = reference.as_ref().expect("read above");
+                let solution = crack(&text, reference, options.cipher, options.max_key_length)?;
+                print!(
+                    "{}",
+                    solution.render(config.format, config.file.as_deref())?
+                );
+            }
+            Command::Encrypt | Command::Decrypt => unreachable!("handled above"),
+        }
+    }
+    Ok(())
+}
+
+/// The best of: every Caesar shift, Atbash and a Vigenère key of each length
+/// up to `max_key_length`; only those of `only` when given. An error when
+/// the ciphertext has no letter to go by.
+fn crack(
+    ciphertext: &str,
+    reference: &LetterFrequencies,
+    only: Option<Cipher>,
+    max_key_length: usize,
+) -> Result<Solution> {
+    let tries = |cipher| only.is_none_or(|only| only == cipher);
+    let letters: Vec<usize> = ciphertext
+        .chars()
+        .filter_map(letters::letter_index)
+        .collect();
+    if letters.is_empty() {
+        return Err(Error::Invalid(
+            "nothing to crack, the text has no letters".to_string(),
+        ));
+    }
+    let mut candidates = Vec::new();
+    if tries(Cipher::Caesar) {
+        let shift = best_shift(&letters, reference);
+        candidates.push(Solution::new(
+            Cipher::Caesar,
+            vec![shift],
+            ciphertext,
+            reference,
+        ));
+    }
+    if tries(Cipher::Atbash) {
+        candidates.push(Solution::new(
+            Cipher::Atbash,
+            Vec::new(),
+            ciphertext,
+            reference,
+        ));
+    }
+    if tries(Cipher::Vigenere) {
+        for len in 1..=max_key_length.min(letters.len()) {
+            let key: Vec<usize> = (0..len)
+                .map(|column| {
+                    let column: Vec<usize> =
+                        letters.iter().skip(column).step_by(len).copied().collect();
+                    best_shift(&column, reference)
+                })
+                .collect();
+            let key = shortest_period(key);
+            candidates.push(Solution::new(Cipher::Vigenere, key, ciphertext, reference));
+        }
+    }
+    // the first one wins ties, so a shorter key over a longer one that
+    // repeats it
+    Ok(candidates
+        .into_iter()
+        .reduce(|best, next| {
+            if next.fitness > best.fitness {
+                next
+            } else {
+                best
+            }
+        })
+        .expect("at least one cipher is tried"))
+}
+
+/// The shift that makes `letters`, as indices, fit the letter frequencies of
+/// `reference` best.
+fn best_shift(letters: &[usize], reference: &LetterFrequencies) -> usize {
+    let chi_squared = |shift: usize| {
+        let shifted: String = letters
+            .iter()
+            .map(|&i| letters::letter((i + 26 - shift) % 26))
+            .collect();
+        reference.chi_squared(&LetterFrequencies::from_text(&shifted))
+    };
+    (0..26)
+        .map(|shift| (shift, chi_squared(shift)))
+        .fold((0, f64::INFINITY), |best, next| {
+            if next.1 < best.1 {
+                next
+            } else {
+                best
+            }
+        })
+        .0
+}
+
+/// `key` without repetitions, e.g. "lemon" for "lemonlemon".
+fn shortest_period(key: Vec<usize>) -> Vec<usize> {
+    let len = (1..key.len())
+        .filter(|&p| key.len().is_multiple_of(p))
+        .find(|&p| (p..key.len()).all(|i| key[i] == key[i - p]))
+        .unwrap_or(key.len());
+    key[..len].to_vec()
+}
+
+/// Every letter moved forward, or back, by the next shift of `key`. Other
+/// characters are kept and don't use up a shift; case is kept as well.
+fn shift(text: &str, key: &[usize], back: bool) -> String {
+    if key.is_empty() {
+        return text.to_string();
+    }
+    let mut shifts = key.iter().cycle();
+    text.chars()
+        .map(|ch| match letters::letter_index(ch) {
+            Some(i) => {
+                let k = shifts.next().expect("key is not empty");
+                let i = if back {
+                    (i + 26 - k) % 26
+                } else {
+                    (i + k) % 26
+                };
+                same_case(letters::letter(i), ch)
+            }
+            None => ch,
+        })
+        .collect()
+}
+
+/// `a` becomes `z`, `b` becomes `y`...
+fn atbash(text: &str) -> String {
+    text.chars()
+        .map(|ch| match letters::letter_index(ch) {
+            Some(i) => same_case(letters::letter(25 - i), ch),
+            None => ch,
+        })
+        .collect()
+}
+
+fn same_case(letter: char, like: char) -> char {
+    if like.is_ascii_uppercase() {
+        letter.to_ascii_uppercase()
+    } else {
+        letter
+    }
+}
+
+/// The indices of the letters of `s`, `None` if it has anything else.
+fn letter_indices(s: &str) -> Option<Vec<usize>> {
+    s.chars().map(letters::letter_index).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENGLISH: &str = "It is a truth universally acknowledged, that a single man in \
+        possession of a good fortune, must be in want of a wife. However little known the \
+        feelings or views of such a man may be on his first entering a neighbourhood, this \
+        truth is so well fixed in the minds of the surrounding families, that he is \
+        considered the rightful property of some one or other of their daughters.";
+
+    fn reference() -> LetterFrequencies {
+        LetterFrequencies::from_text(ENGLISH)
+    }
+
+    #[test]
+    fn keys_are_parsed_for_each_cipher() {
+        assert_eq!(Cipher::Caesar.parse_key("3").unwrap(), [3]);
+        assert_eq!(Cipher::Caesar.parse_key("d").unwrap(), [3]);
+        assert!(Cipher::Caesar.parse_key("26").is_err());
+        assert_eq!(
+            Cipher::Vigenere.parse_key("Lemon").unwrap(),
+            [11, 4, 12, 14, 13]
+        );
+        assert!(Cipher::Vigenere.parse_key("lem0n").is_err());
+        assert_eq!(Cipher::Atbash.parse_key("").unwrap(), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn ciphers_keep_case_and_other_characters() {
+        let key = Cipher::Vigenere.parse_key("lemon").unwrap();
+        let ciphertext = Cipher::Vigenere.encrypt(&key, "Attack at dawn!");
+        assert_eq!(ciphertext, "Lxfopv ef rnhr!");
+        assert_eq!(
+            Cipher::Vigenere.decrypt(&key, &ciphertext),
+            "Attack at dawn!"
+        );
+        assert_eq!(Cipher::Caesar.encrypt(&[3], "xyz"), "abc");
+        assert_eq!(Cipher::Atbash.encrypt(&[], "Abc-z"), "Zyx-a");
+        assert_eq!(shortest_period(vec![1, 2, 1, 2, 1, 2]), [1, 2]);
+        assert_eq!(shortest_period(vec![1, 2, 1]), [1, 2, 1]);
+    }
+
+    #[test]
+    fn crack_finds_the_cipher_and_the_key() {
+        let caesar = Cipher::Caesar.encrypt(&[7], ENGLISH);
+        let solution = crack(&caesar, &reference(), None, 12).unwrap();
+        assert_eq!((solution.cipher, solution.key), (Cipher::Caesar, vec![7]));
+        assert_eq!(solution.plaintext, ENGLISH);
+        let atbash = Cipher::Atbash.encrypt(&[], ENGLISH);
+        let solution = crack(&atbash, &reference(), None, 12).unwrap();
+        assert_eq!(solution.cipher, Cipher::Atbash);
+        let key = Cipher::Vigenere.parse_key("key").unwrap();
+        let vigenere = Cipher::Vigenere.encrypt(&key, ENGLISH);
+        let solution = crack(&vigenere, &reference(), Some(Cipher::Vigenere), 12).unwrap();
+        assert_eq!(solution.key, key);
+    }
+
+    #[test]
+    fn crack_needs_letters() {
+        assert!(crack("1234, 5678!", &reference(), None, 12).is_err());
+        assert!(crack("", &reference(), Some(Cipher::Vigenere), 12).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+//! Collocations
+//! ==============================
+//! Which words go together: every pair of words that come within a few words
+//! of each other is counted, then scored against how often the two words
+//! would meet by chance:
+//!
+//! - pointwise mutual information (PMI) tells how much more often, and
+//!   favours rare pairs;
+//! - the t-score and log-likelihood (G²) tell how sure it is, and favour
+//!   frequent pairs;
+//! - log-Dice tells how exclusive the two words are to each other.
+
+use std::{env, fmt::Write, process};
+use tf_core::{
+    collocations::{self, Collocation, Cooccurrences, Measure},
+    config::Args,
+    ngrams::StopWordPolicy,
+    output::{csv_field, json_string, tsv_field},
+    Config, Error, Format, Ranking, Result,
+};
+
+const USAGE: &str = "\
+Usage: collocations [options] <input>...
+
+Prints the pairs of words that most often come near each other, compared to
+chance.
+
+Options:
+    --window <n>                  pair each word with the n words after it
+                                  (default 1, only neighbours)
+    --by <log-likelihood|pmi|t-score|log-dice>
+                                  how to rank the pairs (default log-likelihood)
+    --min-count <n>               leave out pairs counted fewer than n times
+                                  (default 3)
+    --format <text|json|csv|tsv>  how to print the result (default text)
+    --top <n>                     report the first n pairs (default 25)
+    -h, --help                    print this message
+
+Stop words are left out; with `--ngram-stop-words break` no pair spans one.
+Every other option of the word counting styles but `--rank`, `--ngram` and
+`--per-file` works too.
+";
+
+/// Pairs seen fewer times are noise, whatever their score.
+const DEFAULT_MIN_COUNT: usize = 3;
+
+/// The command line: the window, the measure and the counting options.
+#[derive(Debug)]
+struct Options {
+    window: usize,
+    measure: Measure,
+    config: Config,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self> {
+        let mut window = 1;
+        let mut measure = Measure::default();
+        let mut min_count = false;
+        let mut shared = Vec::new();
+        let mut args = Args::new(args);
+        while let Some(arg) = args.next() {
+            match args.flag() {
+                "--window" => window = args.positive()?,
+                "--by" => measure = args.value()?.parse()?,
+                _ => {
+                    min_count |= args.flag() == "--min-count";
+                    shared.push(arg);
+                }
+            }
+        }
+        // no cache: pairs within a window need the words in order
+        let mut config = Config::parse(shared)?.uncached()?;
+        if !min_count {
+            config.page.min_count = DEFAULT_MIN_COUNT;
+        }
+        if config.ranking != Ranking::default() {
+            return Err(Error::Invalid(
+                "`--rank` does not apply, see `--by`".to_string(),
+            ));
+        }
+        if config.ngrams.n != 1 {
+            return Err(Error::Invalid(
+                "`--ngram` does not apply, see `--window`".to_string(),
+            ));
+        }
+        if config.per_file {
+            return Err(Error::Invalid("`--per-file` does not apply".to_string()));
+        }
+        Ok(Options {
+            window,
+            measure,
+            config,
+        })
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        process::exit(if args.is_empty() { 2 } else { 0 });
+    }
+    if let Err(e) = Options::parse(args).and_then(run) {
+        eprintln!("collocations: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: Options) -> Result<()> {
+    let config = &options.config;
+    let stop_words = config.stop_words()?;
+    let mapping = config.mapping()?;
+    let stemmer = config.stemmer();
+    let mut cooccurrences = Cooccurrences::new(options.window);
+    for source in config.sources()? {
+        let text = source.read_to_string()?;
+        for word in config.tokenizer.words(&text) {
+            let word = mapping.before(word);
+            if stop_words.contains(&word) {
+                if config.ngrams.stop_words == StopWordPolicy::Break {
+                    cooccurrences.stop();
+                }
+                continue;
+            }
+            cooccurrences.push(stemmer.stem(mapping.after(word)));
+        }
+        // no pair across two inputs
+        cooccurrences.stop();
+    }
+
+    let ranked = collocations::rank(cooccurrences.collocations(), options.measure);
+    let report = Report {
+        pairs: cooccurrences.total(),
+        first_rank: config.page.first_rank(),
+        collocations: config
+            .page
+            .select(ranked, |c| c.count)
+            .into_iter()
+            .map(|c| Collocation {
+                first: stemmer.label(&c.first),
+                second: stemmer.label(&c.second),
+                ..c
+            })
+            .collect(),
+    };
+    print!("{}", report.render(config.format)?);
+    Ok(())
+}
+
+/// What is printed: the ranked pairs.
+#[derive(Debug)]
+struct Report {
+    /// Number of pairs counted in all.
+    pairs: usize,
+    first_rank: usize,
+    collocations: Vec<Collocation>,
+}
+
+impl Report {
+    fn render(&self, format: Format) -> Result<String> {
+        let mut out = String::new();
+        // writing to a String never fails
+        let _ = match format {
+            Format::Text => self.write_text(&mut out),
+            Format::Json => self.write_json(&mut out),
+            Format::Csv => self.write_separated(&mut out, ',', csv_field),
+            Format::Tsv => self.write_separated(&mut out, '\t', tsv_field),
+            _ => {
+                return Err(Error::Invalid(
+                    "collocations prints text, json, csv or tsv".to_string(),
+                ))
+            }
+        };
+        Ok(out)
+    }
+
+    fn write_text(&self, out: &mut String) -> std::fmt::Result {
+        let width = self
+            .collocations
+            .iter()
+            .map(|c| c.first.chars().count() + 1 + c.second.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+        writeln!(
+            out,
+            "{:>4} {:<width$} {:>7} {:>9} {:>9} {:>9} {:>9}",
+            "",
+            "pair",
+            "count",
+            "G2",
+            "PMI",
+            "t-score",
+            "log-Dice",
+            width = width
+        )?;
+        for (i, c) in self.collocations.iter().enumerate() {
+            writeln!(
+                out,
+                "[{:>2}] {:<width$} {:>7} {:>9.2} {:>9.2} {:>9.2} {:>9.2}",
+                self.first_rank + i,
+                format!("{} {}", c.first, c.second),
+                c.count,
+                c.log_likelihood,
+                c.pmi,
+                c.t_score,
+                c.log_dice,
+                width = width
+            )?;
+        }
+        writeln!(out, "pairs: {}", self.pairs)
+    }
+
+    fn write_json(&self, out: &mut String) -> std::fmt::Result {
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"pairs\": {},", self.pairs)?;
+        writeln!(out, "  \"collocations\": [")?;
+        for (i, c) in self.collocations.iter().enumerate() {
+            let comma = if i + 1 < self.collocations.len() {
+                ","
+            } else {
+                ""
+            };
+            writeln!(
+                out,
+                "    {{\"rank\": {}, \"first\": {}, \"second\": {}, \"count\": {}, \
+                 \"first_count\": {}, \"second_count\": {}, \"log_likelihood\": {}, \
+                 \"pmi\": {}, \"t_score\": {}, \"log_dice\": {}}}{}",
+                self.first_rank + i,
+                json_string(&c.first),
+                json_string(&c.second),
+                c.count,
+                c.first_count,
+                c.second_count,
+                c.log_likelihood,
+                c.pmi,
+                c.t_score,
+                c.log_dice,
+                comma
+            )?;
+        }
+        writeln!(out, "  ]")?;
+        writeln!(out, "}}")
+    }
+
+    fn write_separated(
+        &self,
+        out: &mut String,
+        sep: char,
+        field: fn(&str) -> String,
+    ) -> std::fmt::Result {
+        let header = [
+            "rank",
+            "first",
+            "second",
+            "count",
+            "first_count",
+            "second_count",
+            "log_likelihood",
+            "pmi",
+            "t_score",
+            "log_dice",
+        ];
+        writeln!(out, "{}", header.join(&sep.to_string()))?;
+        for (i, c) in self.collocations.iter().enumerate() {
+            let row = [
+                (self.first_rank + i).to_string(),
+                field(&c.first),
+                field(&c.second),
+                c.count.to_string(),
+                c.first_count.to_string(),
+                c.second_count.to_string(),
+                c.log_likelihood.to_string(),
+                c.pmi.to_string(),
+                c.t_score.to_string(),
+                c.log_dice.to_string(),
+            ];
+            writeln!(out, "{}", row.join(&sep.to_string()))?;
+        }
+        Ok(())
+    }
+}
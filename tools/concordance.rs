@@ -0,0 +1,531 @@
+//! Concordance
+//! ==============================
+//! Where a word is used: every occurrence with the text around it, the
+//! occurrences one under the other (keyword in context). Words are matched
+//! the way the styles count them, with the same tokenizer, `--map` tables
+//! and `--stem`, so there are as many lines as the count of the word.
+
+use std::{env, fmt::Write, process, str::FromStr};
+use tf_core::{
+    config::Args,
+    output::{csv_field, json_string, tsv_field},
+    Config, Error, Format, Mapping, Result, Stemmer, Stemming,
+};
+
+const USAGE: &str = "\
+Usage: concordance [options] --word <word>... <input>...
+
+Prints every occurrence of the words with the text on either side, aligned
+on the word.
+
+Options:
+    --word <word>                 a word to look for; repeat for several, or
+                                  give several words to look for a phrase
+    --context <n>                 how much text to show on either side: words,
+                                  or characters with `--chars` (default 5
+                                  words or 40 characters)
+    --left <n>                    the same, on the left only
+    --right <n>                   the same, on the right only
+    --chars                       measure the context in characters
+    --sort <text|left|right>      keep the order of the text (default), or sort
+                                  by the words just before or just after
+    --format <text|json|csv|tsv>  how to print the result (default text)
+    -h, --help                    print this message
+
+The tokenizer, `--min-length`, `--map` and `--stem` options of the word
+counting styles apply to the matching.
+";
+
+/// How the context is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Words,
+    Chars,
+}
+
+/// The order of the lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Sort {
+    #[default]
+    Text,
+    /// By the nearest word on the left, then the one before...
+    Left,
+    /// By the word just after, then the next...
+    Right,
+}
+
+impl FromStr for Sort {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Sort::Text),
+            "left" => Ok(Sort::Left),
+            "right" => Ok(Sort::Right),
+            _ => Err(Error::Invalid(format!(
+                "unknown sort `{}`, expected text, left or right",
+                s
+            ))),
+        }
+    }
+}
+
+/// The command line: the words, the layout and the counting options.
+#[derive(Debug)]
+struct Options {
+    words: Vec<String>,
+    left: Option<usize>,
+    right: Option<usize>,
+    unit: Unit,
+    sort: Sort,
+    config: Config,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self> {
+        let mut options = Options {
+            words: Vec::new(),
+            left: None,
+            right: None,
+            unit: Unit::Words,
+            sort: Sort::default(),
+            config: Config::default(),
+        };
+        let mut shared = Vec::new();
+        let mut args = Args::new(args);
+        while let Some(arg) = args.next() {
+            match args.flag() {
+                "--word" => options.words.push(args.value()?),
+                "--context" => {
+                    let n = args.number()?;
+                    options.left = Some(n);
+                    options.right = Some(n);
+                }
+                "--left" => options.left = Some(args.number()?),
+                "--right" => options.right = Some(args.number()?),
+                "--chars" => {
+                    args.switch()?;
+                    options.unit = Unit::Chars
+                }
+                "--sort" => options.sort = args.value()?.parse()?,
+                _ => shared.push(arg),
+            }
+        }
+        // no cache: the lines around each word are needed, not its count
+        options.config = Config::parse(shared)?.uncached()?;
+        if options.words.is_empty() {
+            return Err(Error::Invalid("no `--word` given".to_string()));
+        }
+        if options.config.per_file {
+            return Err(Error::Invalid("`--per-file` does not apply".to_string()));
+        }
+        if options.config.ngrams.n != 1 {
+            return Err(Error::Invalid("`--ngram` does not apply".to_string()));
+        }
+        Ok(options)
+    }
+
+    /// The context on the left and on the right.
+    fn context(&self) -> (usize, usize) {
+        let default = match self.unit {
+            Unit::Words => 5,
+            Unit::Chars => 40,
+        };
+        (self.left.unwrap_or(default), self.right.unwrap_or(default))
+    }
+}
+
+/// A word of the text: where it is and how it is counted.
+#[derive(Debug)]
+struct Token {
+    start: usize,
+    end: usize,
+    /// `None` when it is not counted at all, e.g. too short.
+    counted: Option<String>,
+}
+
+/// One occurrence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Line {
+    file: String,
+    line: usize,
+    left: String,
+    keyword: String,
+    right: String,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        process::exit(if args.is_empty() { 2 } else { 0 });
+    }
+    if let Err(e) = Options::parse(args).and_then(run) {
+        eprintln!("concordance: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: Options) -> Result<()> {
+    let config = &options.config;
+    let matcher = Matcher::new(config)?;
+    let phrases = options
+        .words
+        .iter()
+        .map(|word| matcher.phrase(word))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut lines = Vec::new();
+    let sources = config.sources()?;
+    for source in &sources {
+        let text = source.read_to_string()?;
+        let skipped = source.skipped_lines()?;
+        lines.extend(concordance(
+            &text,
+            &matcher.tokens(&text),
+            &phrases,
+            &options,
+            &source.to_string(),
+            skipped,
+        ));
+    }
+    sort(&mut lines, options.sort);
+    print!("{}", render(&lines, config.format, sources.len() > 1)?);
+    Ok(())
+}
+
+/// Words as the styles count them.
+struct Matcher<'a> {
+    config: &'a Config,
+    mapping: Mapping,
+    stemmer: Stemmer,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(config: &'a Config) -> Result<Self> {
+        Ok(Matcher {
+            config,
+            mapping: config.mapping()?,
+            // only the stems are needed, not how to report them
+            stemmer: Stemmer::new(match config.stemming {
+                Stemming::Off => Stemming::Off,
+                _ => Stemming::Stems,
+            }),
+        })
+    }
+
+    /// A word per word counted, e.g. `do` and `not` for an expanded "don't".
+    fn count(&self, raw: &str) -> Vec<Option<String>> {
+        let tokenizer = &self.config.tokenizer;
+        tokenizer
+            .normalize(raw.to_lowercase())
+            .map(|word| {
+                if tokenizer.accepts(&word) {
+                    Some(self.stemmer.stem(self.mapping.apply(word)))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// The words of a `--word`, an error when one of them is not counted.
+    fn phrase(&self, word: &str) -> Result<Vec<String>> {
+        let phrase: Option<Vec<String>> = self
+            .config
+            .tokenizer
+            .segments(word)
+            .flat_map(|raw| self.count(raw))
+            .collect();
+        match phrase {
+            Some(phrase) if !phrase.is_empty() => Ok(phrase),
+            _ => Err(Error::Invalid(format!("`{}` is never counted", word))),
+        }
+    }
+
+    fn tokens(&self, text: &str) -> Vec<Token> {
+        self.config
+            .tokenizer
+            .segment_indices(text)
+            .flat_map(|(start, raw)| {
+                self.count(raw).into_iter().map(move |counted| Token {
+                    start,
+                    end: start + raw.len(),
+                    counted,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The lines of the occurrences of `phrases` in `text`, in order; `text`
+/// starts after `skipped` lines of the file.
+fn concordance(
+    text: &str,
+    tokens: &[Token],
+    phrases: &[Vec<String>],
+    options: &Options,
+    file: &str,
+    skipped: usize,
+) -> Vec<Line> {
+    let (left, right) = options.context();
+    // byte offsets where lines start
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let mut ret = Vec::new();
+    for i in 0..tokens.len() {
+        let phrase = phrases.iter().find(|phrase| {
+            tokens.len() - i >= phrase.len()
+                && phrase
+                    .iter()
+                    .zip(&tokens[i..])
+                    .all(|(word, token)| token.counted.as_deref() == Some(word.as_str()))
+        });
+        let phrase = match phrase {
+            Some(phrase) => phrase,
+            None => continue,
+        };
+        let j = i + phrase.len() - 1;
+        let (start, end) = (tokens[i].start, tokens[j].end);
+        let (left, right) = match options.unit {
+            Unit::Words => (
+                flatten(&text[tokens[i.saturating_sub(left)].start..start]),
+                flatten(&text[end..tokens[(j + right).min(tokens.len() - 1)].end]),
+            ),
+            // read backwards from the match, only as far as the context goes
+            Unit::Chars => {
+                let mut before: Vec<char> =
+                    flattened(text[..start].chars().rev()).take(left).collect();
+                before.reverse();
+                (
+                    before.into_iter().collect(),
+                    flattened(text[end..].chars()).take(right).collect(),
+                )
+            }
+        };
+        ret.push(Line {
+            file: file.to_string(),
+            line: skipped + line_starts.partition_point(|&s| s <= start),
+            left,
+            keyword: flatten(&text[start..end]),
+            right,
+        });
+    }
+    ret
+}
+
+/// Runs of whitespace, line breaks included, as a single space.
+fn flatten(text: &str) -> String {
+    flattened(text.chars()).collect()
+}
+
+/// [`flatten`], lazily, so that taking a few characters reads only those;
+/// the same forwards as backwards.
+fn flattened(chars: impl Iterator<Item = char>) -> impl Iterator<Item = char> {
+    let mut space = false;
+    chars.filter_map(move |ch| {
+        let after_space = space;
+        space = ch.is_whitespace();
+        match (space, after_space) {
+            (false, _) => Some(ch),
+            (true, false) => Some(' '),
+            (true, true) => None,
+        }
+    })
+}
+
+/// Stable: lines with the same context keep the order of the text.
+fn sort(lines: &mut [Line], sort: Sort) {
+    let words = |s: &str| -> Vec<String> {
+        s.split_whitespace()
+            .map(|w| w.to_lowercase())
+            .filter(|w| w.chars().any(char::is_alphanumeric))
+            .collect()
+    };
+    match sort {
+        Sort::Text => {}
+        Sort::Left => lines.sort_by_cached_key(|l| {
+            let mut words = words(&l.left);
+            words.reverse();
+            words
+        }),
+        Sort::Right => lines.sort_by_cached_key(|l| words(&l.right)),
+    }
+}
+
+fn render(lines: &[Line], format: Format, several_files: bool) -> Result<String> {
+    let mut out = String::new();
+    // writing to a String never fails
+    let _ = match format {
+        Format::Text => write_text(&mut out, lines, several_files),
+        Format::Json => write_json(&mut out, lines),
+        Format::Csv => write_separated(&mut out, lines, ',', csv_field),
+        Format::Tsv => write_separated(&mut out, lines, '\t', tsv_field),
+        _ => {
+            return Err(Error::Invalid(
+                "concordance prints text, json, csv or tsv".to_string(),
+            ))
+        }
+    };
+    Ok(out)
+}
+
+/// `file:line  left  keyword  right`, the keywords one under the other.
+fn write_text(out: &mut String, lines: &[Line], several_files: bool) -> std::fmt::Result {
+    let location = |l: &Line| {
+        if several_files {
+            format!("{}:{}", l.file, l.line)
+        } else {
+            l.line.to_string()
+        }
+    };
+    let width = |f: &dyn Fn(&Line) -> usize| lines.iter().map(f).max().unwrap_or(0);
+    let location_width = width(&|l| location(l).chars().count());
+    let left_width = width(&|l| l.left.chars().count());
+    let keyword_width = width(&|l| l.keyword.chars().count());
+    for l in lines {
+        writeln!(
+            out,
+            "{:>lw$}  {:>left$}{:<kw$}{}",
+            location(l),
+            l.left,
+            l.keyword,
+            l.right,
+            lw = location_width,
+            left = left_width,
+            kw = keyword_width
+        )?;
+    }
+    writeln!(out, "occurrences: {}", lines.len())
+}
+
+fn write_json(out: &mut String, lines: &[Line]) -> std::fmt::Result {
+    writeln!(out, "{{")?;
+    writeln!(out, "  \"occurrences\": [")?;
+    for (i, l) in lines.iter().enumerate() {
+        let comma = if i + 1 < lines.len() { "," } else { "" };
+        writeln!(
+            out,
+            "    {{\"file\": {}, \"line\": {}, \"left\": {}, \"keyword\": {}, \"right\": {}}}{}",
+            json_string(&l.file),
+            l.line,
+            json_string(&l.left),
+            json_string(&l.keyword),
+            json_string(&l.right),
+            comma
+        )?;
+    }
+    writeln!(out, "  ]")?;
+    writeln!(out, "}}")
+}
+
+fn write_separated(
+    out: &mut String,
+    lines: &[Line],
+    sep: char,
+    field: fn(&str) -> String,
+) -> std::fmt::Result {
+    let sep = sep.to_string();
+    writeln!(
+        out,
+        "{}",
+        ["file", "line", "left", "keyword", "right"].join(&sep)
+    )?;
+    for l in lines {
+        let row = [
+            field(&l.file),
+            l.line.to_string(),
+            field(&l.left),
+            field(&l.keyword),
+            field(&l.right),
+        ];
+        writeln!(out, "{}", row.join(&sep))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The occurrences of the `--word`s in `args`, `text` starting after
+    /// `skipped` lines.
+    fn lines(args: &[&str], text: &str, skipped: usize) -> Vec<Line> {
+        let options = Options::parse(args.iter().map(|a| a.to_string()).collect()).unwrap();
+        let matcher = Matcher::new(&options.config).unwrap();
+        let phrases: Vec<Vec<String>> = options
+            .words
+            .iter()
+            .map(|word| matcher.phrase(word).unwrap())
+            .collect();
+        let tokens = matcher.tokens(text);
+        concordance(text, &tokens, &phrases, &options, "book.txt", skipped)
+    }
+
+    fn numbers(lines: &[Line]) -> Vec<usize> {
+        lines.iter().map(|l| l.line).collect()
+    }
+
+    const TEXT: &str = "It is a truth universally acknowledged.\n\
+        Mr. Darcy said nothing;\n\
+        \n\
+        Mr. Bingley danced, and Mr.\n\
+        Darcy watched Mr. Bingley dance.\n";
+
+    #[test]
+    fn line_numbers_from_the_top_of_the_file() {
+        let found = lines(&["--word", "mr"], TEXT, 0);
+        assert_eq!(numbers(&found), [2, 4, 4, 5]);
+        // after a Gutenberg header
+        let found = lines(&["--word", "mr"], TEXT, 30);
+        assert_eq!(numbers(&found), [32, 34, 34, 35]);
+    }
+
+    #[test]
+    fn phrases() {
+        let found = lines(&["--word", "mr darcy", "--context", "1"], TEXT, 0);
+        assert_eq!(numbers(&found), [2, 4]);
+        // across a line break, on the line where it starts
+        assert_eq!(found[1].keyword, "Mr. Darcy");
+        assert_eq!(found[1].left, "and ");
+        assert_eq!(found[1].right, " watched");
+
+        let found = lines(&["--word", "darcy", "--word", "mr bingley"], TEXT, 0);
+        let keywords: Vec<&str> = found.iter().map(|l| l.keyword.as_str()).collect();
+        assert_eq!(keywords, ["Darcy", "Mr. Bingley", "Darcy", "Mr. Bingley"]);
+    }
+
+    #[test]
+    fn context_in_words_and_characters() {
+        let found = lines(&["--word", "truth", "--left", "2", "--right", "1"], TEXT, 0);
+        assert_eq!(found[0].left, "is a ");
+        assert_eq!(found[0].right, " universally");
+
+        let found = lines(&["--word", "nothing", "--chars", "--context", "8"], TEXT, 0);
+        assert_eq!(found[0].left, "cy said ");
+        assert_eq!(found[0].right, "; Mr. Bi");
+    }
+
+    #[test]
+    fn matched_as_counted() {
+        let found = lines(&["--word", "dancing", "--stem"], TEXT, 0);
+        assert_eq!(numbers(&found), [4, 5]);
+        let options = Options::parse(vec![
+            "--word".to_string(),
+            "a".to_string(),
+            "--min-length".to_string(),
+            "2".to_string(),
+        ])
+        .unwrap();
+        assert!(Matcher::new(&options.config).unwrap().phrase("a").is_err());
+    }
+
+    #[test]
+    fn options_that_do_not_apply() {
+        let parse = |args: &[&str]| Options::parse(args.iter().map(|a| a.to_string()).collect());
+        assert!(parse(&["--word", "mr", "--per-file"]).is_err());
+        assert!(parse(&["--word", "mr", "--ngram", "2"]).is_err());
+        assert!(parse(&["--word", "mr", "--chars=no"]).is_err());
+        assert!(parse(&["--word", "mr", "--ngram", "1"]).is_ok());
+    }
+}
@@ -0,0 +1,269 @@
+//! Diversity
+//! ==============================
+//! How rich the vocabulary of a text is. The words are counted as the styles
+//! count them, once with every word and once without the stop words, and
+//! each count gives the usual measures: type-token ratios, hapax and dis
+//! legomena, Yule's K, Simpson's D, Honoré's R and MTLD.
+
+use std::{env, fmt::Write, process};
+use tf_core::{
+    config::Args,
+    diversity::{self, Richness},
+    output::{csv_field, json_string, tsv_field},
+    Config, Error, Format, FrequencyTable, Input, Result, StopWords,
+};
+
+const USAGE: &str = "\
+Usage: diversity [options] <input>...
+
+Prints lexical diversity measures of the inputs, with and without the stop
+words.
+
+Options:
+    --window <n>                  words per window of the moving-average
+                                  type-token ratio (default 500)
+    --per-file                    report on each input, then on all of them
+    --format <text|json|csv|tsv>  how to print the result (default text)
+    -h, --help                    print this message
+
+The tokenizer, stop word, `--map`, `--stem` and `--ngram` options of the word
+counting styles apply.
+";
+
+/// The command line: the window and the counting options.
+#[derive(Debug)]
+struct Options {
+    window: usize,
+    config: Config,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self> {
+        let mut window = 500;
+        let mut shared = Vec::new();
+        let mut args = Args::new(args);
+        while let Some(arg) = args.next() {
+            match args.flag() {
+                "--window" => window = args.positive()?,
+                _ => shared.push(arg),
+            }
+        }
+        Ok(Options {
+            window,
+            // no cache: MTLD needs the words in order, not their counts
+            config: Config::parse(shared)?.uncached()?,
+        })
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        process::exit(if args.is_empty() { 2 } else { 0 });
+    }
+    if let Err(e) = Options::parse(args).and_then(run) {
+        eprintln!("diversity: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: Options) -> Result<()> {
+    let mapping = options.config.mapping()?;
+    let mut reports = Vec::new();
+    for config in options.config.runs()? {
+        let stop_words = config.stop_words()?;
+        let text = Input::read_all(&config.sources()?)?;
+        let stemmer = config.stemmer();
+        let measure = |stop_words: &StopWords| -> Measures {
+            let words: Vec<String> = config
+                .words(&text, stop_words, &mapping, &stemmer)
+                .collect();
+            let table: FrequencyTable = words.iter().cloned().collect();
+            Measures {
+                richness: Richness::from_table(&table),
+                moving_average_ttr: diversity::moving_average_ttr(&words, options.window),
+                mtld: diversity::mtld(&words),
+            }
+        };
+        reports.push(Diversity {
+            file: config.file.clone(),
+            all: measure(&StopWords::default()),
+            content: measure(&stop_words),
+        });
+    }
+    let mut out = String::new();
+    // writing to a String never fails
+    let _ = match options.config.format {
+        Format::Text => write_text(&mut out, &reports, options.window),
+        Format::Json => write_json(&mut out, &reports),
+        Format::Csv => write_separated(&mut out, &reports, ',', csv_field),
+        Format::Tsv => write_separated(&mut out, &reports, '\t', tsv_field),
+        _ => {
+            return Err(Error::Invalid(
+                "diversity prints text, json, csv or tsv".to_string(),
+            ))
+        }
+    };
+    print!("{}", out);
+    Ok(())
+}
+
+/// Every measure of one count.
+#[derive(Debug)]
+struct Measures {
+    richness: Richness,
+    moving_average_ttr: f64,
+    mtld: Option<f64>,
+}
+
+impl Measures {
+    /// `(name, value)` of each measure, in the order they are printed.
+    /// `None` when the measure is undefined for the text.
+    fn values(&self) -> [(&'static str, Option<f64>); 10] {
+        let r = &self.richness;
+        [
+            ("tokens", Some(r.tokens as f64)),
+            ("types", Some(r.types as f64)),
+            ("type_token_ratio", Some(r.type_token_ratio)),
+            ("moving_average_ttr", Some(self.moving_average_ttr)),
+            ("hapax_legomena", Some(r.hapax_legomena as f64)),
+            ("dis_legomena", Some(r.dis_legomena as f64)),
+            ("yules_k", Some(r.yules_k)),
+            ("simpsons_d", Some(r.simpsons_d)),
+            ("honores_r", r.honores_r),
+            ("mtld", self.mtld),
+        ]
+    }
+}
+
+/// The report on one run.
+#[derive(Debug)]
+struct Diversity {
+    file: Option<String>,
+    /// Every word.
+    all: Measures,
+    /// Without the stop words.
+    content: Measures,
+}
+
+impl Diversity {
+    /// `(name, every word, without stop words)` of each measure.
+    fn rows(&self) -> Vec<(&'static str, Option<f64>, Option<f64>)> {
+        let content = self.content.values();
+        self.all
+            .values()
+            .iter()
+            .zip(content.iter())
+            .map(|(&(name, all), &(_name, content))| (name, all, content))
+            .collect()
+    }
+}
+
+/// Counts without decimals, the other measures with 4.
+fn number(name: &str, value: Option<f64>) -> String {
+    match value {
+        None => "-".to_string(),
+        Some(v) if matches!(name, "tokens" | "types" | "hapax_legomena" | "dis_legomena") => {
+            format!("{}", v)
+        }
+        Some(v) => format!("{:.4}", v),
+    }
+}
+
+fn write_text(out: &mut String, reports: &[Diversity], window: usize) -> std::fmt::Result {
+    for (i, report) in reports.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        if let Some(file) = &report.file {
+            writeln!(out, "==> {} <==", file)?;
+        } else if reports.len() > 1 {
+            writeln!(out, "==> total <==")?;
+        }
+        writeln!(
+            out,
+            "{:<24} {:>12} {:>18}",
+            "", "all words", "without stop words"
+        )?;
+        for (name, all, content) in report.rows() {
+            let label = match name {
+                "tokens" => "tokens".to_string(),
+                "types" => "types".to_string(),
+                "type_token_ratio" => "type-token ratio".to_string(),
+                "moving_average_ttr" => format!("MATTR ({} words)", window),
+                "hapax_legomena" => "hapax legomena".to_string(),
+                "dis_legomena" => "dis legomena".to_string(),
+                "yules_k" => "Yule's K".to_string(),
+                "simpsons_d" => "Simpson's D".to_string(),
+                "honores_r" => "Honoré's R".to_string(),
+                _ => "MTLD".to_string(),
+            };
+            writeln!(
+                out,
+                "{:<24} {:>12} {:>18}",
+                label,
+                number(name, all),
+                number(name, content)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn write_json(out: &mut String, reports: &[Diversity]) -> std::fmt::Result {
+    let object = |m: &Measures| -> String {
+        let fields: Vec<String> = m
+            .values()
+            .iter()
+            .map(|(name, value)| match value {
+                Some(v) => format!("\"{}\": {}", name, v),
+                None => format!("\"{}\": null", name),
+            })
+            .collect();
+        format!("{{{}}}", fields.join(", "))
+    };
+    writeln!(out, "[")?;
+    for (i, report) in reports.iter().enumerate() {
+        let comma = if i + 1 < reports.len() { "," } else { "" };
+        writeln!(out, "  {{")?;
+        if let Some(file) = &report.file {
+            writeln!(out, "    \"file\": {},", json_string(file))?;
+        }
+        writeln!(out, "    \"all_words\": {},", object(&report.all))?;
+        writeln!(
+            out,
+            "    \"without_stop_words\": {}",
+            object(&report.content)
+        )?;
+        writeln!(out, "  }}{}", comma)?;
+    }
+    writeln!(out, "]")
+}
+
+/// One row per measure, and per file with `--per-file`.
+fn write_separated(
+    out: &mut String,
+    reports: &[Diversity],
+    sep: char,
+    field: fn(&str) -> String,
+) -> std::fmt::Result {
+    let sep = sep.to_string();
+    let per_file = reports.len() > 1;
+    let mut header = vec!["measure", "all_words", "without_stop_words"];
+    if per_file {
+        header.insert(0, "file");
+    }
+    writeln!(out, "{}", header.join(&sep))?;
+    for report in reports {
+        for (name, all, content) in report.rows() {
+            let value = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+            let mut row = vec![name.to_string(), value(all), value(content)];
+            if per_file {
+                row.insert(0, field(report.file.as_deref().unwrap_or("total")));
+            }
+            writeln!(out, "{}", row.join(&sep))?;
+        }
+    }
+    Ok(())
+}
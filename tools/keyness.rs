@@ -0,0 +1,288 @@
+//! Keyness
+//! ==============================
+//! Which words set a text apart from another. Both are counted the same way,
+//! with the options of the styles, then each word is tested for being used
+//! more in one than in the other:
+//!
+//! - log-likelihood (G²) and chi-squared tell how unlikely the difference is
+//!   to be chance, and grow with the counts;
+//! - the log ratio tells how large the difference is, whatever the counts.
+
+use std::{env, fmt::Write, process};
+use tf_core::{
+    config::Args,
+    keyness::{self, Keyword, Measure},
+    output::{csv_field, json_string, tsv_field},
+    Config, Error, Format, FrequencyTable, Input, Ranking, Result,
+};
+
+const USAGE: &str = "\
+Usage: keyness [options] <input>... --against <input>...
+
+Compares the inputs, the target, with the `--against` inputs, the reference,
+and prints the words most overused in each compared to the other.
+
+Options:
+    --against <input>             an input of the reference corpus; repeat for
+                                  several, directories and patterns work too
+    --by <log-likelihood|chi-squared|log-ratio>
+                                  how to rank the words (default log-likelihood)
+    --format <text|json|csv|tsv>  how to print the result (default text)
+    --top <n>                     report the first n words of each side
+                                  (default 25)
+    --min-count <n>               leave out words counted fewer than n times in
+                                  both corpora together
+    -h, --help                    print this message
+
+Every other option of the word counting styles but `--rank` and `--per-file`
+works too, and applies to both corpora.
+";
+
+/// The command line: the two corpora and the counting options.
+#[derive(Debug)]
+struct Options {
+    against: Vec<String>,
+    measure: Measure,
+    config: Config,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self> {
+        let mut against = Vec::new();
+        let mut measure = Measure::default();
+        let mut shared = Vec::new();
+        let mut args = Args::new(args);
+        while let Some(arg) = args.next() {
+            match args.flag() {
+                "--against" => against.push(args.value()?),
+                "--by" => measure = args.value()?.parse()?,
+                _ => shared.push(arg),
+            }
+        }
+        let config = Config::parse(shared)?;
+        if against.is_empty() {
+            return Err(Error::Invalid("no `--against` input given".to_string()));
+        }
+        if config.ranking != Ranking::default() {
+            return Err(Error::Invalid(
+                "`--rank` does not apply, see `--by`".to_string(),
+            ));
+        }
+        if config.per_file {
+            return Err(Error::Invalid("`--per-file` does not apply".to_string()));
+        }
+        Ok(Options {
+            against,
+            measure,
+            config,
+        })
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        process::exit(if args.is_empty() { 2 } else { 0 });
+    }
+    if let Err(e) = Options::parse(args).and_then(run) {
+        eprintln!("keyness: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: Options) -> Result<()> {
+    let config = &options.config;
+    let stop_words = config.stop_words()?;
+    let mapping = config.mapping()?;
+    // one for both, so that a stem is reported the same way on each side
+    let stemmer = config.stemmer();
+    let count = |inputs: &[String]| -> Result<FrequencyTable> {
+        let sources = config.expand(inputs)?;
+        if sources.is_empty() {
+            return Err(Error::Invalid("no input given".to_string()));
+        }
+        config.counted(&sources, &stop_words, &mapping, || {
+            let text = Input::read_all(&sources)?;
+            Ok(config
+                .words(&text, &stop_words, &mapping, &stemmer)
+                .collect())
+        })
+    };
+    let target = count(&config.inputs)?;
+    let reference = count(&options.against)?;
+
+    let (overused, underused) =
+        keyness::rank(keyness::compare(&target, &reference), options.measure);
+    let label = |keywords: Vec<Keyword>| -> Vec<Keyword> {
+        config
+            .page
+            .select(keywords, |k| k.target + k.reference)
+            .into_iter()
+            .map(|k| Keyword {
+                word: stemmer.label(&k.word),
+                ..k
+            })
+            .collect()
+    };
+    let comparison = Comparison {
+        target: config.inputs.join(", "),
+        reference: options.against.join(", "),
+        target_total: target.total(),
+        reference_total: reference.total(),
+        first_rank: config.page.first_rank(),
+        overused: label(overused),
+        underused: label(underused),
+    };
+    print!("{}", comparison.render(config.format)?);
+    Ok(())
+}
+
+/// What is printed: the two sides and their keywords.
+#[derive(Debug)]
+struct Comparison {
+    target: String,
+    reference: String,
+    target_total: usize,
+    reference_total: usize,
+    first_rank: usize,
+    /// Overused in the target, i.e. underused in the reference.
+    overused: Vec<Keyword>,
+    /// Underused in the target, i.e. overused in the reference.
+    underused: Vec<Keyword>,
+}
+
+impl Comparison {
+    fn render(&self, format: Format) -> Result<String> {
+        let mut out = String::new();
+        // writing to a String never fails
+        let _ = match format {
+            Format::Text => self.write_text(&mut out),
+            Format::Json => self.write_json(&mut out),
+            Format::Csv => self.write_separated(&mut out, ',', csv_field),
+            Format::Tsv => self.write_separated(&mut out, '\t', tsv_field),
+            _ => {
+                return Err(Error::Invalid(
+                    "keyness prints text, json, csv or tsv".to_string(),
+                ))
+            }
+        };
+        Ok(out)
+    }
+
+    /// `(name, keywords)` of each side, the target first.
+    fn sides(&self) -> [(&'static str, &[Keyword]); 2] {
+        [("overused", &self.overused), ("underused", &self.underused)]
+    }
+
+    fn write_text(&self, out: &mut String) -> std::fmt::Result {
+        let titles = [
+            format!(
+                "overused in {}, compared to {}",
+                self.target, self.reference
+            ),
+            format!(
+                "overused in {}, compared to {}",
+                self.reference, self.target
+            ),
+        ];
+        for (title, (_side, keywords)) in titles.iter().zip(self.sides().iter()) {
+            writeln!(out, "==> {} <==", title)?;
+            writeln!(
+                out,
+                "{:>4} {:>20} {:>7} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+                "", "word", "target", "per 1M", "reference", "per 1M", "G2", "chi2", "log ratio"
+            )?;
+            for (i, k) in keywords.iter().enumerate() {
+                writeln!(
+                    out,
+                    "[{:>2}] {:>20} {:>7} {:>9.1} {:>9} {:>9.1} {:>9.2} {:>9.2} {:>9.2}",
+                    self.first_rank + i,
+                    k.word,
+                    k.target,
+                    k.target_per_million,
+                    k.reference,
+                    k.reference_per_million,
+                    k.log_likelihood,
+                    k.chi_squared,
+                    k.log_ratio
+                )?;
+            }
+            writeln!(out)?;
+        }
+        writeln!(out, "target words: {}", self.target_total)?;
+        writeln!(out, "reference words: {}", self.reference_total)
+    }
+
+    fn write_json(&self, out: &mut String) -> std::fmt::Result {
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"target\": {},", json_string(&self.target))?;
+        writeln!(out, "  \"reference\": {},", json_string(&self.reference))?;
+        writeln!(out, "  \"target_total\": {},", self.target_total)?;
+        writeln!(out, "  \"reference_total\": {},", self.reference_total)?;
+        for (i, (side, keywords)) in self.sides().iter().enumerate() {
+            writeln!(out, "  \"{}\": [", side)?;
+            for (j, k) in keywords.iter().enumerate() {
+                let comma = if j + 1 < keywords.len() { "," } else { "" };
+                writeln!(
+                    out,
+                    "    {{\"rank\": {}, \"word\": {}, \"target\": {}, \"target_per_million\": {}, \
+                     \"reference\": {}, \"reference_per_million\": {}, \"log_likelihood\": {}, \
+                     \"chi_squared\": {}, \"log_ratio\": {}}}{}",
+                    self.first_rank + j,
+                    json_string(&k.word),
+                    k.target,
+                    k.target_per_million,
+                    k.reference,
+                    k.reference_per_million,
+                    k.log_likelihood,
+                    k.chi_squared,
+                    k.log_ratio,
+                    comma
+                )?;
+            }
+            writeln!(out, "  ]{}", if i == 0 { "," } else { "" })?;
+        }
+        writeln!(out, "}}")
+    }
+
+    /// One row per keyword, the side it is overused in first.
+    fn write_separated(
+        &self,
+        out: &mut String,
+        sep: char,
+        field: fn(&str) -> String,
+    ) -> std::fmt::Result {
+        let header = [
+            "side",
+            "rank",
+            "word",
+            "target",
+            "target_per_million",
+            "reference",
+            "reference_per_million",
+            "log_likelihood",
+            "chi_squared",
+            "log_ratio",
+        ];
+        writeln!(out, "{}", header.join(&sep.to_string()))?;
+        for (side, keywords) in self.sides().iter() {
+            for (i, k) in keywords.iter().enumerate() {
+                let row = [
+                    side.to_string(),
+                    (self.first_rank + i).to_string(),
+                    field(&k.word),
+                    k.target.to_string(),
+                    k.target_per_million.to_string(),
+                    k.reference.to_string(),
+                    k.reference_per_million.to_string(),
+                    k.log_likelihood.to_string(),
+                    k.chi_squared.to_string(),
+                    k.log_ratio.to_string(),
+                ];
+                writeln!(out, "{}", row.join(&sep.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+//! Languages
+//! ==============================
+//! A corpus may mix English, French and German documents, and an English
+//! stop-word list then leaves the French and German function words in the
+//! table. This tells the language of each document, or of each section, by
+//! its character trigrams, and counts it with the stop words of its own
+//! language.
+
+use std::{collections::HashMap, env, fmt::Write, process};
+use tf_core::{
+    config::Args,
+    language::{self, Detection},
+    output::{csv_field, json_string, tsv_field},
+    segments::Segmentation,
+    stop_words::Source,
+    Config, Error, Format, FrequencyTable, Report, Result, StopWords,
+};
+
+const USAGE: &str = "\
+Usage: languages [options] <input>...
+
+Tells the language of each input, counts its words with the stop words of
+that language, and prints the top words of each input, then the languages
+found and how sure each guess is.
+
+Options:
+    --segment <how>               tell the language of each section on its own:
+                                  chapters, roman, blank[:n], lines:n or
+                                  heading:<pattern>, see the `sections` tool
+    --languages-only              print the table of languages only
+    -h, --help                    print this message
+
+Every other option of the word counting styles but `--stop-words` and
+`--per-file` works too; `--stop-words-dir` tells where the list of each
+language is (default ../stop_words), e.g. `fr.txt`. Words are split on
+Unicode word boundaries, as with `--tokenizer unicode`.
+";
+
+/// The command line: what a document is, which tables and the counting
+/// options.
+#[derive(Debug)]
+struct Options {
+    segmentation: Option<Segmentation>,
+    languages_only: bool,
+    config: Config,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self> {
+        let mut segmentation = None;
+        let mut languages_only = false;
+        let mut shared = Vec::new();
+        let mut args = Args::new(args);
+        while let Some(arg) = args.next() {
+            match args.flag() {
+                "--segment" => segmentation = Some(args.value()?.parse()?),
+                "--languages-only" => languages_only = args.switch()?,
+                _ => shared.push(arg),
+            }
+        }
+        // read as with `--stop-words auto`, the tokenizer included
+        shared.insert(0, "--stop-words=auto".to_string());
+        // no cache: each document or section is counted with the stop words
+        // of its own language, not as one of the inputs
+        let config = Config::parse(shared)?.uncached()?;
+        if config.stop_words != [Source::Auto] || config.no_stop_words {
+            return Err(Error::Invalid(
+                "`--stop-words` does not apply, each input uses the list of its language"
+                    .to_string(),
+            ));
+        }
+        if config.per_file {
+            return Err(Error::Invalid(
+                "`--per-file` does not apply, every input is reported".to_string(),
+            ));
+        }
+        if !matches!(
+            config.format,
+            Format::Text | Format::Json | Format::Csv | Format::Tsv
+        ) {
+            return Err(Error::Invalid(
+                "languages prints text, json, csv or tsv".to_string(),
+            ));
+        }
+        Ok(Options {
+            segmentation,
+            languages_only,
+            config,
+        })
+    }
+}
+
+/// An input, or a section of one, and its language.
+#[derive(Debug)]
+struct Document {
+    title: String,
+    /// `None` when the text is too short to tell.
+    detection: Option<Detection>,
+    words: usize,
+}
+
+impl Document {
+    fn code(&self) -> &'static str {
+        self.detection.as_ref().map_or("", |d| d.language.code)
+    }
+
+    fn language(&self) -> &'static str {
+        self.detection
+            .as_ref()
+            .map_or("unknown", |d| d.language.name)
+    }
+
+    fn confidence(&self) -> f64 {
+        self.detection.as_ref().map_or(0.0, |d| d.confidence)
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        process::exit(if args.is_empty() { 2 } else { 0 });
+    }
+    if let Err(e) = Options::parse(args).and_then(run) {
+        eprintln!("languages: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: Options) -> Result<()> {
+    let config = &options.config;
+    let mapping = config.mapping()?;
+    let dir = config.stop_words_dir();
+    // read once per language
+    let mut stop_words: HashMap<&str, StopWords> = HashMap::new();
+    let sources = config.sources()?;
+    let mut documents = Vec::new();
+    for source in &sources {
+        let text = source.read_to_string()?;
+        let sections: Vec<(Option<String>, String)> = match &options.segmentation {
+            Some(segmentation) => segmentation
+                .split_after(&text, source.skipped_lines()?)
+                .into_iter()
+                .map(|s| (Some(s.title), s.text))
+                .collect(),
+            None => vec![(None, text)],
+        };
+        for (section, text) in sections {
+            let title = match section {
+                Some(section) if sources.len() > 1 => format!("{}: {}", source, section),
+                Some(section) => section,
+                None => source.to_string(),
+            };
+            let detection = language::detect(&text);
+            // English, as without `auto`, when the text is too short to tell
+            let code = detection.as_ref().map_or("en", |d| d.language.code);
+            if !stop_words.contains_key(code) {
+                let list = match StopWords::language_sources(code, &dir)? {
+                    Some(sources) => StopWords::from_sources(&sources)?,
+                    None => {
+                        eprintln!(
+                            "warning: no stop words for language `{}`, add them as `{}`; \
+                             counting every word",
+                            code,
+                            dir.join(format!("{}.txt", code)).display()
+                        );
+                        StopWords::default()
+                    }
+                };
+                stop_words.insert(code, list);
+            }
+            let stemmer = config.stemmer();
+            let counts: FrequencyTable = config
+                .words(&text, &stop_words[code], &mapping, &stemmer)
+                .collect();
+            let counts = stemmer.relabel(&counts);
+            let document = Document {
+                title,
+                detection,
+                words: counts.total(),
+            };
+            if !options.languages_only {
+                Report::new(config.ranking.rank(&counts), counts.total())
+                    .ranked()
+                    .ranked_by(config.ranking)
+                    .paged(&config.page)
+                    .of_file(Some(format!(
+                        "{} [{}, {:.0}%]",
+                        document.title,
+                        document.language(),
+                        document.confidence() * 100.0
+                    )))
+                    .after_file(!documents.is_empty())
+                    .print(config.format);
+            }
+            documents.push(document);
+        }
+    }
+    print!("{}", render(&documents, config.format));
+    Ok(())
+}
+
+fn render(documents: &[Document], format: Format) -> String {
+    let mut out = String::new();
+    // writing to a String never fails
+    let _ = match format {
+        Format::Json => write_json(&mut out, documents),
+        Format::Csv => write_separated(&mut out, documents, ',', csv_field),
+        Format::Tsv => write_separated(&mut out, documents, '\t', tsv_field),
+        _ => write_text(&mut out, documents),
+    };
+    out
+}
+
+fn write_text(out: &mut String, documents: &[Document]) -> std::fmt::Result {
+    writeln!(out, "==> languages <==")?;
+    writeln!(
+        out,
+        "{:>30} {:>10} {:>10} {:>8}",
+        "input", "language", "confidence", "words"
+    )?;
+    for d in documents {
+        writeln!(
+            out,
+            "{:>30} {:>10} {:>9.1}% {:>8}",
+            d.title,
+            d.language(),
+            d.confidence() * 100.0,
+            d.words
+        )?;
+    }
+    Ok(())
+}
+
+fn write_json(out: &mut String, documents: &[Document]) -> std::fmt::Result {
+    writeln!(out, "{{")?;
+    writeln!(out, "  \"languages\": [")?;
+    for (i, d) in documents.iter().enumerate() {
+        let comma = if i + 1 < documents.len() { "," } else { "" };
+        let distances: Vec<String> = d
+            .detection
+            .iter()
+            .flat_map(|d| d.distances.iter())
+            .map(|(language, distance)| format!("{}: {}", json_string(language.code), distance))
+            .collect();
+        writeln!(
+            out,
+            "    {{\"input\": {}, \"language\": {}, \"code\": {}, \"confidence\": {}, \
+             \"words\": {}, \"distances\": {{{}}}}}{}",
+            json_string(&d.title),
+            json_string(d.language()),
+            json_string(d.code()),
+            d.confidence(),
+            d.words,
+            distances.join(", "),
+            comma
+        )?;
+    }
+    writeln!(out, "  ]")?;
+    writeln!(out, "}}")
+}
+
+fn write_separated(
+    out: &mut String,
+    documents: &[Document],
+    sep: char,
+    field: fn(&str) -> String,
+) -> std::fmt::Result {
+    let header = ["input", "language", "code", "confidence", "words"];
+    writeln!(out, "{}", header.join(&sep.to_string()))?;
+    for d in documents {
+        let row = [
+            field(&d.title),
+            field(d.language()),
+            d.code().to_string(),
+            d.confidence().to_string(),
+            d.words.to_string(),
+        ];
+        writeln!(out, "{}", row.join(&sep.to_string()))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,111 @@
+//! Names
+//! ==============================
+//! The styles lowercase every word at once, so "Bennet" the name is counted
+//! like any word, and "Mr. Darcy" as two. This reads the text before that,
+//! finds the names of people and places by their capital letters, and ranks
+//! them in a table of their own, after the usual table of words.
+
+use std::{env, process};
+use tf_core::{config::Args, names, Config, FrequencyTable, Input, Report, Result};
+
+const USAGE: &str = "\
+Usage: names [options] <input>...
+
+Prints the table of words, then the table of the names of people and places
+found in the inputs, e.g. `Mr Darcy` or `Lady Catherine de Bourgh`.
+
+Options:
+    --names-only                  print the table of names only
+    -h, --help                    print this message
+
+Every option of the word counting styles works too; `--stem`, `--map` and
+`--ngram` only change the table of words.
+";
+
+/// The command line: which tables and the counting options.
+#[derive(Debug)]
+struct Options {
+    names_only: bool,
+    config: Config,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self> {
+        let mut names_only = false;
+        let mut shared = Vec::new();
+        let mut args = Args::new(args);
+        while let Some(arg) = args.next() {
+            match args.flag() {
+                "--names-only" => names_only = args.switch()?,
+                _ => shared.push(arg),
+            }
+        }
+        Ok(Options {
+            names_only,
+            config: Config::parse(shared)?,
+        })
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        process::exit(if args.is_empty() { 2 } else { 0 });
+    }
+    if let Err(e) = Options::parse(args).and_then(run) {
+        eprintln!("names: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: Options) -> Result<()> {
+    let mapping = options.config.mapping()?;
+    for config in options.config.runs()? {
+        let stop_words = config.stop_words()?;
+        let sources = config.sources()?;
+        // names don't run from one input into the next
+        let mut names = FrequencyTable::new();
+        for source in &sources {
+            names.merge(names::find(
+                &source.read_to_string()?,
+                &config.tokenizer,
+                &stop_words,
+            ));
+        }
+        let title = |table: &str| match &config.file {
+            Some(file) => format!("{}: {}", file, table),
+            None => table.to_string(),
+        };
+        if !options.names_only {
+            let stemmer = config.stemmer();
+            let words = config.counted(&sources, &stop_words, &mapping, || {
+                let text = Input::read_all(&sources)?;
+                Ok(config
+                    .words(&text, &stop_words, &mapping, &stemmer)
+                    .collect())
+            })?;
+            let words = stemmer.relabel(&words);
+            Report::new(config.ranking.rank(&words), words.total())
+                .ranked()
+                .ranked_by(config.ranking)
+                .paged(&config.page)
+                .of_file(Some(title("words")))
+                .after_file(config.after_file)
+                .print(config.format);
+        }
+        Report::new(config.ranking.rank(&names), names.total())
+            .ranked()
+            .ranked_by(config.ranking)
+            .paged(&config.page)
+            .of_file(if options.names_only {
+                config.file.clone()
+            } else {
+                Some(title("names"))
+            })
+            // after the words, unless they are left out
+            .after_file(config.after_file || !options.names_only)
+            .print(config.format);
+    }
+    Ok(())
+}
@@ -0,0 +1,190 @@
+//! Sections
+//! ==============================
+//! A book is not one bag of words: cut it into chapters, or any other
+//! sections, and count each on its own, to see how the vocabulary shifts from
+//! one to the next. Each section gets the report of the styles, and the
+//! counts of all sections can be written as a section-by-word matrix.
+
+use std::{env, fmt::Write, fs, process};
+use tf_core::{
+    config::Args,
+    input::with_path,
+    output::{csv_field, tsv_field},
+    segments::Segmentation,
+    Config, Error, FrequencyTable, Ranking, Report, Result,
+};
+
+const USAGE: &str = "\
+Usage: sections [options] <input>...
+
+Cuts the inputs into sections and prints the top words of each.
+
+Options:
+    --segment <how>               where sections start (default chapters):
+                                    chapters          at `Chapter 12`, `CHAPTER XII.`
+                                    roman             at lines holding only a
+                                                      Roman numeral
+                                    blank[:n]         after n blank lines (default 1)
+                                    lines:n           every n lines
+                                    heading:<pattern> at lines matching a glob
+                                                      pattern, e.g. `BOOK *`
+    --matrix <path>               write the count of each word in each section,
+                                  a row per section and a column per word, as
+                                  CSV, or TSV when the path ends with `.tsv`
+    --matrix-words <n>            only the columns of the n most frequent words
+                                  (default every word)
+    -h, --help                    print this message
+
+Every other option of the word counting styles but `--per-file` works too,
+and applies to each section.
+";
+
+/// The command line: how to cut, the matrix and the counting options.
+#[derive(Debug)]
+struct Options {
+    segmentation: Segmentation,
+    matrix: Option<String>,
+    matrix_words: Option<usize>,
+    config: Config,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self> {
+        let mut options = Options {
+            segmentation: Segmentation::default(),
+            matrix: None,
+            matrix_words: None,
+            config: Config::default(),
+        };
+        let mut shared = Vec::new();
+        let mut args = Args::new(args);
+        while let Some(arg) = args.next() {
+            match args.flag() {
+                "--segment" => options.segmentation = args.value()?.parse()?,
+                "--matrix" => options.matrix = Some(args.value()?),
+                "--matrix-words" => options.matrix_words = Some(args.number()?),
+                _ => shared.push(arg),
+            }
+        }
+        // no cache: the sections are counted one by one and are not inputs
+        options.config = Config::parse(shared)?.uncached()?;
+        if options.config.per_file {
+            return Err(Error::Invalid(
+                "`--per-file` does not apply, every section is reported".to_string(),
+            ));
+        }
+        Ok(options)
+    }
+}
+
+/// One section, counted.
+#[derive(Debug)]
+struct Section {
+    title: String,
+    line: usize,
+    counts: FrequencyTable,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        process::exit(if args.is_empty() { 2 } else { 0 });
+    }
+    if let Err(e) = Options::parse(args).and_then(run) {
+        eprintln!("sections: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: Options) -> Result<()> {
+    let config = &options.config;
+    let mapping = config.mapping()?;
+    // one for all sections, so that a stem is reported the same way in each
+    let stemmer = config.stemmer();
+    let sources = config.sources()?;
+    let mut sections = Vec::new();
+    for source in &sources {
+        let text = source.read_to_string()?;
+        let skipped = source.skipped_lines()?;
+        for segment in options.segmentation.split_after(&text, skipped) {
+            // with auto, the list of the language of this section
+            let stop_words = config.stop_words_of(&segment.text)?;
+            sections.push(Section {
+                title: if sources.len() > 1 {
+                    format!("{}: {}", source, segment.title)
+                } else {
+                    segment.title
+                },
+                line: segment.line,
+                counts: config
+                    .words(&segment.text, &stop_words, &mapping, &stemmer)
+                    .collect(),
+            });
+        }
+    }
+    if sections.is_empty() {
+        return Err(Error::Invalid("no section found".to_string()));
+    }
+    for section in &mut sections {
+        section.counts = stemmer.relabel(&section.counts);
+    }
+
+    for (i, section) in sections.iter().enumerate() {
+        Report::new(config.ranking.rank(&section.counts), section.counts.total())
+            .ranked()
+            .ranked_by(config.ranking)
+            .paged(&config.page)
+            .of_file(Some(section.title.clone()))
+            .after_file(i > 0)
+            .print(config.format);
+    }
+    if let Some(path) = &options.matrix {
+        let matrix = matrix(&sections, options.matrix_words, path.ends_with(".tsv"));
+        fs::write(path, matrix).map_err(|e| with_path(path, e))?;
+    }
+    Ok(())
+}
+
+/// A row per section, a column per word, most frequent overall first.
+fn matrix(sections: &[Section], words: Option<usize>, tsv: bool) -> String {
+    let (sep, field): (&str, fn(&str) -> String) = if tsv {
+        ("\t", tsv_field)
+    } else {
+        (",", csv_field)
+    };
+    let mut all = FrequencyTable::new();
+    for section in sections {
+        all.extend(section.counts.iter().map(|(w, c)| (w.to_string(), c)));
+    }
+    let mut columns: Vec<String> = Ranking::Count
+        .rank(&all)
+        .into_iter()
+        .map(|(w, _c)| w)
+        .collect();
+    if let Some(n) = words {
+        columns.truncate(n);
+    }
+
+    let mut out = String::new();
+    let header: Vec<String> = ["section", "line", "total"]
+        .iter()
+        .map(|h| h.to_string())
+        .chain(columns.iter().map(|w| field(w)))
+        .collect();
+    // writing to a String never fails
+    let _ = writeln!(out, "{}", header.join(sep));
+    for section in sections {
+        let row: Vec<String> = [
+            field(&section.title),
+            section.line.to_string(),
+            section.counts.total().to_string(),
+        ]
+        .iter()
+        .cloned()
+        .chain(columns.iter().map(|w| section.counts.count(w).to_string()))
+        .collect();
+        let _ = writeln!(out, "{}", row.join(sep));
+    }
+    out
+}
@@ -0,0 +1,765 @@
+//! Server
+//! ==============================
+//! Word frequencies over HTTP, for tools that would rather not start a
+//! process per text. The stop words and character tables are read once at
+//! startup, so a request only tokenizes and counts; with `--stop-words auto`
+//! the language of each text is told and its list used.
+//!
+//! - `GET /health` answers `{"status": "ok", ...}`.
+//! - `POST /count` counts the request body.
+//! - `GET /count?path=<file>` counts a file on the server, under `--root`.
+//!
+//! `/count` answers with the JSON of `--format json`, with the title and
+//! author of a Project Gutenberg book and, with `--stop-words auto`, the
+//! language of the text under `about`. The query parameters `top`,
+//! `offset`, `min_count`, `all` and `rank` override the options the server
+//! was started with, for that request only.
+
+use std::{
+    borrow::Cow,
+    env, fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    process,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use tf_core::{
+    config::Args, gutenberg, output::json_string, stop_words::Source, Config, Error, Format,
+    FrequencyTable, Mapping, Report, Result, StopWords,
+};
+
+const USAGE: &str = "\
+Usage: server [options]
+
+Endpoints:
+    GET  /health                  whether the server is up
+    POST /count                   the word frequencies of the request body
+    GET  /count?path=<file>       the word frequencies of a file under `--root`
+
+    The query parameters top, offset, min_count, all and rank of /count work
+    like the options of the same name; all takes no value, true or false.
+
+Options:
+    --listen <address>            where to listen (default 127.0.0.1:8080)
+    --threads <n>                 requests handled at the same time (default 4)
+    --max-body <bytes>            the largest request body accepted
+                                  (default 10485760)
+    --root <dir>                  the directory `path` is relative to, files
+                                  outside it are refused (default .)
+    -h, --help                    print this message
+
+Every option of the word counting styles but the inputs, `--format`,
+`--per-file` and `--cache` can be given as well; they apply to every request.
+";
+
+/// The most a request line and its headers may take.
+const MAX_HEAD: usize = 8 * 1024;
+
+/// How long a client may take to send its whole request, and to read the
+/// response: a worker is never held longer by a slow client.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The command line: the server's own options and the counting ones.
+#[derive(Debug)]
+struct Options {
+    listen: String,
+    threads: usize,
+    max_body: usize,
+    root: PathBuf,
+    config: Config,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self> {
+        let mut options = Options {
+            listen: "127.0.0.1:8080".to_string(),
+            threads: 4,
+            max_body: 10 * 1024 * 1024,
+            root: PathBuf::from("."),
+            config: Config::default(),
+        };
+        let mut shared = Vec::new();
+        let mut args = Args::new(args);
+        while let Some(arg) = args.next() {
+            match args.flag() {
+                "--listen" => options.listen = args.value()?,
+                "--threads" => options.threads = args.positive()?,
+                "--max-body" => options.max_body = args.positive()?,
+                "--root" => options.root = args.value()?.into(),
+                "--format" => {
+                    return Err(Error::Invalid(
+                        "`--format` does not apply, the server answers JSON".to_string(),
+                    ))
+                }
+                "--per-file" => {
+                    return Err(Error::Invalid(
+                        "`--per-file` does not apply, each request is counted on its own"
+                            .to_string(),
+                    ))
+                }
+                "--cache" => {
+                    return Err(Error::Invalid(
+                        "`--cache` does not apply, requests are counted as they come".to_string(),
+                    ))
+                }
+                _ => shared.push(arg),
+            }
+        }
+        options.config = Config::parse(shared)?;
+        if !options.config.inputs.is_empty() {
+            return Err(Error::Invalid(
+                "the server reads no inputs, POST them or ask for a `path`".to_string(),
+            ));
+        }
+        Ok(options)
+    }
+}
+
+/// Everything a request needs, loaded once and shared by the workers.
+#[derive(Debug)]
+struct Service {
+    config: Config,
+    /// `None` with `--stop-words auto`, whose lists depend on each text.
+    stop_words: Option<StopWords>,
+    mapping: Mapping,
+    /// Canonical, so that paths can be checked against it.
+    root: PathBuf,
+    max_body: usize,
+}
+
+impl Service {
+    fn load(options: Options) -> Result<Self> {
+        let auto = options.config.stop_words.contains(&Source::Auto);
+        Ok(Service {
+            stop_words: if auto && !options.config.no_stop_words {
+                None
+            } else {
+                Some(options.config.stop_words()?)
+            },
+            mapping: options.config.mapping()?,
+            root: fs::canonicalize(&options.root)
+                .map_err(|e| Error::Invalid(format!("--root {}: {}", options.root.display(), e)))?,
+            max_body: options.max_body,
+            config: options.config,
+        })
+    }
+
+    fn respond(&self, request: &Request) -> Response {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/health") => Response::json(
+                200,
+                format!(
+                    "{{\"status\": \"ok\", \"stop_words\": {}}}\n",
+                    self.stop_words
+                        .as_ref()
+                        .map_or("\"auto\"".to_string(), |s| s.len().to_string())
+                ),
+            ),
+            ("GET", "/count") | ("POST", "/count") => match self.count(request) {
+                Ok(json) => Response::json(200, json),
+                Err(response) => response,
+            },
+            (_, "/health") | (_, "/count") => Response::error(405, "method not allowed"),
+            _ => Response::error(404, "not found"),
+        }
+    }
+
+    /// The report of `/count`, or why there is none.
+    fn count(&self, request: &Request) -> std::result::Result<String, Response> {
+        let mut config = self.config.clone();
+        let mut path = None;
+        for (key, value) in &request.query {
+            let number = || {
+                value.parse().map_err(|_| {
+                    Response::error(400, &format!("`{}` expects a number, got `{}`", key, value))
+                })
+            };
+            match key.as_str() {
+                "path" => path = Some(value.clone()),
+                "top" => config.page.top = Some(number()?),
+                "offset" => config.page.offset = number()?,
+                "min_count" => config.page.min_count = number()?,
+                "all" => match value.as_str() {
+                    "" | "true" => config.page.top = None,
+                    "false" => {}
+                    _ => {
+                        return Err(Response::error(
+                            400,
+                            &format!("`all` expects true or false, got `{}`", value),
+                        ))
+                    }
+                },
+                "rank" => {
+                    config.ranking = value
+                        .parse()
+                        .map_err(|e: Error| Response::error(400, &e.to_string()))?
+                }
+                _ => {
+                    return Err(Response::error(
+                        400,
+                        &format!("unknown parameter `{}`", key),
+                    ))
+                }
+            }
+        }
+        let (raw, text) = match &path {
+            Some(path) => self.read_file(path)?,
+            None if request.method == "POST" => (
+                request.body.clone(),
+                String::from_utf8(request.body.clone())
+                    .map_err(|_| Response::error(400, "the body is not UTF-8"))?,
+            ),
+            None => return Err(Response::error(400, "expected a `path` or a POST body")),
+        };
+        let table = self.word_frequencies(&config, &text)?;
+        // the title and author of a book, and with auto its language
+        let about = config
+            .about_text(&raw, &text)
+            .map_err(|e| Response::error(500, &e.to_string()))?;
+        Ok(Report::new(config.ranking.rank(&table), table.total())
+            .ranked()
+            .ranked_by(config.ranking)
+            .paged(&config.page)
+            .of_file(path)
+            .with_notes(about)
+            .render(Format::Json))
+    }
+
+    fn word_frequencies(
+        &self,
+        config: &Config,
+        text: &str,
+    ) -> std::result::Result<FrequencyTable, Response> {
+        let stop_words = match &self.stop_words {
+            Some(stop_words) => Cow::Borrowed(stop_words),
+            None => Cow::Owned(
+                config
+                    .stop_words_of(text)
+                    .map_err(|e| Response::error(500, &e.to_string()))?,
+            ),
+        };
+        let stemmer = config.stemmer();
+        let table = config
+            .words(text, &stop_words, &self.mapping, &stemmer)
+            .collect();
+        Ok(stemmer.relabel(&table))
+    }
+
+    /// A file under the root, as read and as counted; `..` and links may not
+    /// lead out of it. Project Gutenberg boilerplate is left out of the
+    /// text, unless `--keep-boilerplate`.
+    fn read_file(&self, path: &str) -> std::result::Result<(Vec<u8>, String), Response> {
+        let not_found = || Response::error(404, &format!("no such file `{}`", path));
+        let path = fs::canonicalize(self.root.join(path)).map_err(|_| not_found())?;
+        if !path.starts_with(&self.root) {
+            return Err(not_found());
+        }
+        if fs::metadata(&path).map_err(|_| not_found())?.len() > self.max_body as u64 {
+            return Err(Response::error(413, "the file is too large"));
+        }
+        let raw = fs::read(&path).map_err(|e| Response::error(400, &e.to_string()))?;
+        let text = if self.config.keep_boilerplate {
+            &raw[..]
+        } else {
+            gutenberg::strip(&raw)
+        };
+        let text = String::from_utf8(text.to_vec())
+            .map_err(|_| Response::error(400, &format!("`{}` is not UTF-8", path.display())))?;
+        Ok((raw, text))
+    }
+}
+
+/// The parts of an HTTP request the service looks at.
+#[derive(Debug)]
+struct Request {
+    method: String,
+    /// Without the query.
+    path: String,
+    /// Decoded, in order.
+    query: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// Reads one request; the error is the response to send instead.
+    fn read(stream: impl Read + Write, max_body: usize) -> std::result::Result<Self, Response> {
+        let unreadable = |e: io::Error, what: &str| match e.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+                Response::error(408, "the request took too long")
+            }
+            _ => Response::error(400, &format!("unreadable {}", what)),
+        };
+        let mut reader = BufReader::new(stream);
+        let mut head = Vec::new();
+        let mut head_len = 0;
+        loop {
+            let mut line = String::new();
+            let n = (&mut reader)
+                .take((MAX_HEAD - head_len + 1) as u64)
+                .read_line(&mut line)
+                .map_err(|e| unreadable(e, "request"))?;
+            head_len += n;
+            if head_len > MAX_HEAD {
+                return Err(Response::error(431, "request headers too large"));
+            }
+            let line = line.trim_end_matches(&['\r', '\n'][..]);
+            if line.is_empty() {
+                // a request without a head at all, or its end
+                if n == 0 || !head.is_empty() {
+                    break;
+                }
+                continue;
+            }
+            head.push(line.to_string());
+        }
+        let mut request_line = head.first().map(|l| l.split(' ')).into_iter().flatten();
+        let (method, target) = match (request_line.next(), request_line.next()) {
+            (Some(method), Some(target)) => (method.to_string(), target),
+            _ => return Err(Response::error(400, "malformed request line")),
+        };
+        let (path, query) = match target.find('?') {
+            Some(i) => (&target[..i], parse_query(&target[i + 1..])),
+            None => (target, Vec::new()),
+        };
+
+        let header = |name: &str| {
+            head[1..].iter().find_map(|line| {
+                let (key, value) = line.split_at(line.find(':')?);
+                if key.eq_ignore_ascii_case(name) {
+                    Some(value[1..].trim())
+                } else {
+                    None
+                }
+            })
+        };
+        if header("transfer-encoding").is_some() {
+            return Err(Response::error(411, "send a Content-Length"));
+        }
+        let len = match header("content-length") {
+            Some(len) => len
+                .parse::<usize>()
+                .map_err(|_| Response::error(400, "malformed Content-Length"))?,
+            None => 0,
+        };
+        if len > max_body {
+            return Err(Response::error(413, "the body is too large"));
+        }
+        if len > 0 && header("expect").is_some_and(|e| e.eq_ignore_ascii_case("100-continue")) {
+            let _ = reader.get_mut().write_all(b"HTTP/1.1 100 Continue\r\n\r\n");
+        }
+        let mut body = Vec::with_capacity(len);
+        reader
+            .take(len as u64)
+            .read_to_end(&mut body)
+            .map_err(|e| unreadable(e, "body"))?;
+        if body.len() < len {
+            return Err(Response::error(
+                400,
+                "the body is shorter than its Content-Length",
+            ));
+        }
+        Ok(Request {
+            method,
+            path: path.to_string(),
+            query,
+            body,
+        })
+    }
+}
+
+/// `a=1&b=two+words` as pairs, percent-decoded.
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.find('=') {
+            Some(i) => (decode(&pair[..i]), decode(&pair[i + 1..])),
+            None => (decode(pair), String::new()),
+        })
+        .collect()
+}
+
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut ret = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = s
+            .get(i + 1..i + 3)
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                ret.push(byte);
+                i += 3;
+                continue;
+            }
+            (b'+', _) => ret.push(b' '),
+            (byte, _) => ret.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&ret).into_owned()
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn json(status: u16, body: String) -> Self {
+        Response { status, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, format!("{{\"error\": {}}}\n", json_string(message)))
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            411 => "Length Required",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+
+    fn write(&self, mut stream: impl Write) -> std::io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.reason(),
+            self.body.len(),
+            self.body
+        )?;
+        stream.flush()
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        process::exit(0);
+    }
+    if let Err(e) = Options::parse(args).and_then(serve) {
+        eprintln!("server: {}", e);
+        process::exit(1);
+    }
+}
+
+/// Accepts connections forever, handing each one to the next free worker.
+fn serve(options: Options) -> Result<()> {
+    let listener = TcpListener::bind(&options.listen)?;
+    let threads = options.threads;
+    let service = Arc::new(Service::load(options)?);
+    eprintln!("listening on http://{}", listener.local_addr()?);
+
+    let (sender, receiver) = mpsc::channel::<TcpStream>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..threads {
+        let service = Arc::clone(&service);
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || loop {
+            // the lock is released as soon as a connection is taken
+            let stream = match receiver.lock().unwrap().recv() {
+                Ok(stream) => stream,
+                Err(_) => break,
+            };
+            handle(&service, stream);
+        });
+    }
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => sender
+                .send(stream)
+                .map_err(|_| io::Error::other("every worker has stopped"))?,
+            Err(e) => eprintln!("server: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// A connection that fails once [`TIMEOUT`] has passed since it was
+/// accepted, however slowly the client sends, rather than after a pause of
+/// that long between two reads.
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    until: Instant,
+}
+
+impl Deadline<'_> {
+    /// Bounds the next read or write by the time left.
+    fn arm(&self) -> io::Result<()> {
+        let left = self
+            .until
+            .checked_duration_since(Instant::now())
+            .filter(|left| !left.is_zero())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::TimedOut, "deadline passed"))?;
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.set_write_timeout(Some(left))
+    }
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.arm()?;
+        (&mut &*self.stream).read(buf)
+    }
+}
+
+impl Write for Deadline<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.arm()?;
+        (&mut &*self.stream).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&mut &*self.stream).flush()
+    }
+}
+
+fn handle(service: &Service, stream: TcpStream) {
+    let mut deadline = Deadline {
+        stream: &stream,
+        until: Instant::now() + TIMEOUT,
+    };
+    let (line, response) = match Request::read(&mut deadline, service.max_body) {
+        Ok(request) => (
+            format!("{} {}", request.method, request.path),
+            service.respond(&request),
+        ),
+        Err(response) => ("-".to_string(), response),
+    };
+    eprintln!("{} {}", line, response.status);
+    // the client has as long again to read the response
+    deadline.until = Instant::now() + TIMEOUT;
+    if let Err(e) = response.write(&mut deadline) {
+        eprintln!("server: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A connection: what the client sent, and what it was answered.
+    struct Client {
+        sent: Cursor<Vec<u8>>,
+        received: Vec<u8>,
+    }
+
+    impl Read for Client {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.sent.read(buf)
+        }
+    }
+
+    impl Write for Client {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.received.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn client(request: &str) -> Client {
+        Client {
+            sent: Cursor::new(request.as_bytes().to_vec()),
+            received: Vec::new(),
+        }
+    }
+
+    fn read(request: &str, max_body: usize) -> std::result::Result<Request, Response> {
+        Request::read(&mut client(request), max_body)
+    }
+
+    fn status(request: &str, max_body: usize) -> u16 {
+        match read(request, max_body) {
+            Ok(_) => 200,
+            Err(response) => response.status,
+        }
+    }
+
+    fn post(body: &str) -> String {
+        format!(
+            "POST /count HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    #[test]
+    fn request() {
+        let request = read(
+            "POST /count?top=3&rank=alpha&q=two+words%21 HTTP/1.1\r\n\
+             Host: localhost\r\n\
+             content-length:  11 \r\n\
+             \r\n\
+             hello world and more",
+            100,
+        )
+        .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/count");
+        let query: Vec<(&str, &str)> = request
+            .query
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        assert_eq!(
+            query,
+            [("top", "3"), ("rank", "alpha"), ("q", "two words!")]
+        );
+        // no more than the Content-Length
+        assert_eq!(request.body, b"hello world");
+
+        // without a body, after blank lines
+        let request = read("\r\n\r\nGET /health HTTP/1.1\r\n\r\n", 100).unwrap();
+        assert_eq!(
+            (request.method.as_str(), request.path.as_str()),
+            ("GET", "/health")
+        );
+        assert!(request.query.is_empty() && request.body.is_empty());
+    }
+
+    #[test]
+    fn body_limit() {
+        assert_eq!(status(&post("0123456789"), 10), 200);
+        assert_eq!(status(&post("0123456789a"), 10), 413);
+        // refused before the body is read
+        assert_eq!(
+            status(
+                "POST /count HTTP/1.1\r\nContent-Length: 99999999999\r\n\r\n",
+                10
+            ),
+            413
+        );
+        assert_eq!(
+            status(
+                "POST /count HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort",
+                10
+            ),
+            400
+        );
+    }
+
+    #[test]
+    fn malformed_requests() {
+        assert_eq!(status("", 10), 400);
+        assert_eq!(status("GET\r\n\r\n", 10), 400);
+        assert_eq!(
+            status("POST /count HTTP/1.1\r\nContent-Length: ten\r\n\r\n", 10),
+            400
+        );
+        assert_eq!(
+            status(
+                "POST /count HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n",
+                10
+            ),
+            411
+        );
+        let huge = format!(
+            "GET /health HTTP/1.1\r\nX-Pad: {}\r\n\r\n",
+            "a".repeat(MAX_HEAD)
+        );
+        assert_eq!(status(&huge, 10), 431);
+    }
+
+    #[test]
+    fn continue_only_when_expected() {
+        let mut expecting =
+            client("POST /count HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 2\r\n\r\nhi");
+        assert!(Request::read(&mut expecting, 10).is_ok());
+        assert_eq!(expecting.received, b"HTTP/1.1 100 Continue\r\n\r\n");
+
+        let mut plain = client(&post("hi"));
+        assert!(Request::read(&mut plain, 10).is_ok());
+        assert!(plain.received.is_empty());
+    }
+
+    #[test]
+    fn query_decoding() {
+        let query = parse_query("a=%C3%A9t%C3%A9&&flag&bad=%zz%4&plus=1+1");
+        let query: Vec<(&str, &str)> = query
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        assert_eq!(
+            query,
+            [
+                ("a", "été"),
+                ("flag", ""),
+                ("bad", "%zz%4"),
+                ("plus", "1 1")
+            ]
+        );
+    }
+
+    #[test]
+    fn responses() {
+        let service =
+            Service::load(Options::parse(vec!["--no-stop-words".to_string()]).unwrap()).unwrap();
+        let respond = |request: &str| service.respond(&read(request, 100).unwrap());
+
+        let counted = respond(&post("the cat and the hat"));
+        assert_eq!(counted.status, 200);
+        assert!(counted.body.contains("\"the\""), "{}", counted.body);
+
+        let top = respond("POST /count?top=1 HTTP/1.1\r\nContent-Length: 11\r\n\r\nthe cat the");
+        assert!(
+            top.body.contains("\"the\"") && !top.body.contains("\"cat\""),
+            "{}",
+            top.body
+        );
+
+        for (request, status) in [
+            ("POST /count?top=x HTTP/1.1\r\n\r\n", 400),
+            ("POST /count?all=maybe HTTP/1.1\r\n\r\n", 400),
+            ("POST /count?all HTTP/1.1\r\n\r\n", 200),
+            ("POST /count?all=false HTTP/1.1\r\n\r\n", 200),
+            ("POST /count?color=red HTTP/1.1\r\n\r\n", 400),
+            ("GET /count HTTP/1.1\r\n\r\n", 400),
+            ("GET /count?path=../../etc/passwd HTTP/1.1\r\n\r\n", 404),
+            ("DELETE /count HTTP/1.1\r\n\r\n", 405),
+            ("GET /elsewhere HTTP/1.1\r\n\r\n", 404),
+            ("GET /health HTTP/1.1\r\n\r\n", 200),
+        ] {
+            assert_eq!(respond(request).status, status, "{}", request);
+        }
+    }
+
+    #[test]
+    fn counts_tell_what_the_text_is() {
+        let options = Options::parse(vec!["--stop-words=auto".to_string()]).unwrap();
+        let service = Service::load(options).unwrap();
+        let book = "Title: Marie\nAuthor: Anonyme\n\n\
+            *** START OF THE PROJECT GUTENBERG EBOOK MARIE ***\n\
+            Il était une fois, dans un petit village de France, une jeune fille \
+            qui s'appelait Marie. Elle habitait avec sa mère dans une maison \
+            près de la rivière, et chaque matin elle allait à la fontaine.\n\
+            *** END OF THE PROJECT GUTENBERG EBOOK MARIE ***\n";
+        let counted = service.respond(&read(&post(book), 1000).unwrap());
+        assert_eq!(counted.status, 200);
+        for about in [
+            "\"title\": \"Marie\"",
+            "\"author\": \"Anonyme\"",
+            "\"language\": \"French (fr)\"",
+        ] {
+            assert!(counted.body.contains(about), "{}", counted.body);
+        }
+    }
+}
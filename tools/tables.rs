@@ -0,0 +1,372 @@
+//! Tables
+//! ==============================
+//! Every style's table dies with the process. `--format table` saves it, and
+//! this reads saved tables back: to sum the counts of shards counted on
+//! different days, to take a table away from another, to see what changed
+//! between two, or to rank a stored table anew. Tables are read from files
+//! or stdin and merged tables written to stdout, so runs chain, e.g.
+//! `week1 --format table a.txt | tables merge - b.table | tables top -`.
+
+use std::{
+    cmp::Reverse,
+    env,
+    fmt::Write as _,
+    io::{self, Write as _},
+    process,
+};
+use tf_core::{
+    config::Args,
+    output::{csv_field, json_string, tsv_field},
+    store::{Encoding, Table},
+    Config, Error, Format, Input, Report, Result,
+};
+
+const USAGE: &str = "\
+Usage: tables <command> [options] <table>...
+
+Reads frequency tables written with `--format table`, or by this tool, from
+files or `-` for stdin, in the text or the binary encoding.
+
+Commands:
+    merge <table>...              sum the counts of the tables into one table
+    subtract <table> <table>...   take the counts of the other tables away from
+                                  the first one's
+    diff <table> <table>          print the words whose counts changed most from
+                                  the first table to the second
+    top <table>...                rank the tables, merged, like the styles do
+
+Options:
+    --encoding <text|binary>      how merge and subtract write the table
+                                  (default text)
+    -h, --help                    print this message
+
+`top` takes the options of the styles that rank and print a table: `--rank`,
+`--top`, `--offset`, `--min-count`, `--all` and `--format`, `table` included.
+`diff` takes `--top`, `--offset`, `--min-count`, `--all` and
+`--format <text|json|csv|tsv>`. `merge` and `subtract` write every word.
+";
+
+/// Options of the styles that pick and order the words printed.
+const RANKING_FLAGS: &[&str] = &["--rank", "--top", "--offset", "--min-count", "--all"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Merge,
+    Subtract,
+    Diff,
+    Top,
+}
+
+/// The command line: what to do, with which tables, and how to print.
+#[derive(Debug)]
+struct Options {
+    command: Command,
+    encoding: Encoding,
+    config: Config,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self> {
+        let mut args = Args::new(args);
+        let command = match args.next().as_deref() {
+            Some("merge") => Command::Merge,
+            Some("subtract") => Command::Subtract,
+            Some("diff") => Command::Diff,
+            Some("top") => Command::Top,
+            Some(other) => {
+                return Err(Error::Invalid(format!(
+                    "unknown command `{}`, expected merge, subtract, diff or top",
+                    other
+                )))
+            }
+            None => return Err(Error::Invalid("no command given".to_string())),
+        };
+        let mut encoding = None;
+        // the options that only change how a ranking is printed
+        let mut ranking_flags = Vec::new();
+        let mut shared = Vec::new();
+        while let Some(arg) = args.next() {
+            match args.flag() {
+                "--encoding" => encoding = Some(args.value()?.parse()?),
+                flag => {
+                    if RANKING_FLAGS.contains(&flag) {
+                        ranking_flags.push(flag.to_string());
+                    }
+                    shared.push(arg);
+                }
+            }
+        }
+        // no cache: the tables are read back from files, no text is counted
+        let config = Config::parse(shared)?.uncached()?;
+        if config.per_file {
+            return Err(Error::Invalid("`--per-file` does not apply".to_string()));
+        }
+        let writes_table = matches!(command, Command::Merge | Command::Subtract);
+        if encoding.is_some() && !writes_table {
+            return Err(Error::Invalid(
+                "`--encoding` applies to merge and subtract, see `--format`".to_string(),
+            ));
+        }
+        if let Some(flag) = ranking_flags.first().filter(|_| writes_table) {
+            return Err(Error::Invalid(format!(
+                "`{}` applies to diff and top, merge and subtract write every word",
+                flag
+            )));
+        }
+        if command == Command::Diff && ranking_flags.iter().any(|f| f == "--rank") {
+            return Err(Error::Invalid(
+                "`--rank` does not apply, diff ranks by change".to_string(),
+            ));
+        }
+        if writes_table && !matches!(config.format, Format::Text | Format::Table) {
+            return Err(Error::Invalid(
+                "merge and subtract write a table, see `--encoding`".to_string(),
+            ));
+        }
+        let expected = match command {
+            Command::Merge | Command::Top => 1..=usize::MAX,
+            Command::Subtract => 2..=usize::MAX,
+            Command::Diff => 2..=2,
+        };
+        if !expected.contains(&config.inputs.len()) {
+            return Err(Error::Invalid(match command {
+                Command::Diff => "diff compares two tables".to_string(),
+                Command::Subtract => {
+                    "subtract takes a table and the tables to take away".to_string()
+                }
+                _ => "no table given".to_string(),
+            }));
+        }
+        Ok(Options {
+            command,
+            encoding: encoding.unwrap_or_default(),
+            config,
+        })
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        process::exit(if args.is_empty() { 2 } else { 0 });
+    }
+    if let Err(e) = Options::parse(args).and_then(run) {
+        eprintln!("tables: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: Options) -> Result<()> {
+    let config = &options.config;
+    let mut tables = Vec::new();
+    for input in Input::expand(&config.inputs)? {
+        let table = Table::decode(&input.read_bytes()?).map_err(|e| match e {
+            Error::Invalid(msg) => Error::Invalid(format!("{}: {}", input, msg)),
+            e => e,
+        })?;
+        tables.push(table);
+    }
+    let mut tables = tables.into_iter();
+    let mut first = tables.next().unwrap_or_default();
+    match options.command {
+        Command::Merge | Command::Top => tables.for_each(|t| first.merge(&t)),
+        Command::Subtract => tables.for_each(|t| first.subtract(&t)),
+        Command::Diff => {
+            let second = tables.next().unwrap_or_default();
+            let diff = Diff {
+                first: config.inputs[0].clone(),
+                second: config.inputs[1].clone(),
+                first_total: first.total,
+                second_total: second.total,
+                first_rank: config.page.first_rank(),
+                changes: config
+                    .page
+                    .select(changes(&first, &second), |c| c.first.max(c.second)),
+            };
+            print!("{}", diff.render(config.format)?);
+            return Ok(());
+        }
+    }
+    if options.command == Command::Top {
+        let counts = first.counts();
+        Report::new(config.ranking.rank(&counts), first.total)
+            .ranked()
+            .ranked_by(config.ranking)
+            .paged(&config.page)
+            .print(config.format);
+        return Ok(());
+    }
+    io::stdout().write_all(&first.encode(options.encoding))?;
+    Ok(())
+}
+
+/// A word counted a different number of times in two tables.
+#[derive(Debug)]
+struct Change {
+    word: String,
+    first: usize,
+    second: usize,
+}
+
+impl Change {
+    fn difference(&self) -> i64 {
+        self.second as i64 - self.first as i64
+    }
+}
+
+/// Every word of either table whose count changed, largest change first,
+/// ties alphabetically.
+fn changes(first: &Table, second: &Table) -> Vec<Change> {
+    let (before, after) = (first.counts(), second.counts());
+    let mut all = first.counts();
+    all.extend(second.entries.iter().cloned());
+    let mut ret: Vec<Change> = all
+        .iter()
+        .map(|(word, _count)| Change {
+            word: word.to_string(),
+            first: before.count(word),
+            second: after.count(word),
+        })
+        .filter(|c| c.first != c.second)
+        .collect();
+    ret.sort_by(|a, b| {
+        (Reverse(a.difference().abs()), &a.word).cmp(&(Reverse(b.difference().abs()), &b.word))
+    });
+    ret
+}
+
+/// What `diff` prints: the two tables and the changes between them.
+#[derive(Debug)]
+struct Diff {
+    first: String,
+    second: String,
+    first_total: usize,
+    second_total: usize,
+    first_rank: usize,
+    changes: Vec<Change>,
+}
+
+impl Diff {
+    fn render(&self, format: Format) -> Result<String> {
+        let mut out = String::new();
+        // writing to a String never fails
+        let _ = match format {
+            Format::Text => self.write_text(&mut out),
+            Format::Json => self.write_json(&mut out),
+            Format::Csv => self.write_separated(&mut out, ',', csv_field),
+            Format::Tsv => self.write_separated(&mut out, '\t', tsv_field),
+            _ => {
+                return Err(Error::Invalid(
+                    "diff prints text, json, csv or tsv".to_string(),
+                ))
+            }
+        };
+        Ok(out)
+    }
+
+    fn write_text(&self, out: &mut String) -> std::fmt::Result {
+        writeln!(out, "==> {} -> {} <==", self.first, self.second)?;
+        writeln!(
+            out,
+            "{:>4} {:>20} {:>7} {:>7} {:>7}",
+            "", "word", "first", "second", "change"
+        )?;
+        for (i, c) in self.changes.iter().enumerate() {
+            writeln!(
+                out,
+                "[{:>2}] {:>20} {:>7} {:>7} {:>+7}",
+                self.first_rank + i,
+                c.word,
+                c.first,
+                c.second,
+                c.difference()
+            )?;
+        }
+        writeln!(out, "first words: {}", self.first_total)?;
+        writeln!(out, "second words: {}", self.second_total)
+    }
+
+    fn write_json(&self, out: &mut String) -> std::fmt::Result {
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"first\": {},", json_string(&self.first))?;
+        writeln!(out, "  \"second\": {},", json_string(&self.second))?;
+        writeln!(out, "  \"first_total\": {},", self.first_total)?;
+        writeln!(out, "  \"second_total\": {},", self.second_total)?;
+        writeln!(out, "  \"changes\": [")?;
+        for (i, c) in self.changes.iter().enumerate() {
+            let comma = if i + 1 < self.changes.len() { "," } else { "" };
+            writeln!(
+                out,
+                "    {{\"rank\": {}, \"word\": {}, \"first\": {}, \"second\": {}, \
+                 \"change\": {}}}{}",
+                self.first_rank + i,
+                json_string(&c.word),
+                c.first,
+                c.second,
+                c.difference(),
+                comma
+            )?;
+        }
+        writeln!(out, "  ]")?;
+        writeln!(out, "}}")
+    }
+
+    fn write_separated(
+        &self,
+        out: &mut String,
+        sep: char,
+        field: fn(&str) -> String,
+    ) -> std::fmt::Result {
+        let header = ["rank", "word", "first", "second", "change"];
+        writeln!(out, "{}", header.join(&sep.to_string()))?;
+        for (i, c) in self.changes.iter().enumerate() {
+            let row = [
+                (self.first_rank + i).to_string(),
+                field(&c.word),
+                c.first.to_string(),
+                c.second.to_string(),
+                c.difference().to_string(),
+            ];
+            writeln!(out, "{}", row.join(&sep.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options> {
+        Options::parse(args.iter().map(|a| a.to_string()).collect())
+    }
+
+    #[test]
+    fn options_for_each_command() {
+        assert!(parse(&["merge", "a", "b", "--encoding", "binary"]).is_ok());
+        assert!(parse(&["top", "a", "--encoding", "binary"]).is_err());
+        assert!(parse(&["merge", "a", "--format", "json"]).is_err());
+        for flag in [
+            "--top=3",
+            "--rank=alpha",
+            "--offset=2",
+            "--min-count=2",
+            "--all",
+        ] {
+            assert!(parse(&["merge", "a", flag]).is_err(), "{}", flag);
+            assert!(parse(&["subtract", "a", "b", flag]).is_err(), "{}", flag);
+            assert!(parse(&["top", "a", flag]).is_ok(), "{}", flag);
+        }
+        assert!(parse(&["diff", "a", "b", "--top", "3"]).is_ok());
+        assert!(parse(&["diff", "a", "b", "--rank", "alpha"]).is_err());
+    }
+
+    #[test]
+    fn numbers_of_tables() {
+        assert!(parse(&["subtract", "a"]).is_err());
+        assert!(parse(&["diff", "a", "b", "c"]).is_err());
+        assert!(parse(&["top"]).is_err());
+        assert!(parse(&["sort", "a"]).is_err());
+    }
+}
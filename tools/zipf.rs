@@ -0,0 +1,303 @@
+//! Zipf
+//! ==============================
+//! How well a text follows the two laws of word frequencies:
+//!
+//! - Zipf's law, fitted by a line through the log of the counts against the
+//!   log of the ranks, over the whole ranking the styles sort;
+//! - Heaps' law, fitted the same way to how the vocabulary grows while the
+//!   words stream in.
+
+use std::{env, fmt::Write, fs, process};
+use tf_core::{
+    config::Args,
+    input::with_path,
+    laws::{Fit, Growth, Heaps, Zipf},
+    output::json_string,
+    Config, Error, Format, FrequencyTable, Input, Ranking, Result,
+};
+
+const USAGE: &str = "\
+Usage: zipf [options] <input>...
+
+Fits Zipf's law to the ranking of the words, and Heaps' law to the growth of
+the vocabulary, and prints the parameters, how good the fits are and the
+words furthest from Zipf's law.
+
+Options:
+    --outliers <n>                how many outlier words to print (default 10)
+    --zipf-data <path>            write rank, count and expected count of every
+                                  word to a TSV file, for plotting
+    --heaps-data <path>           write words read, distinct words and expected
+                                  distinct words to a TSV file, for plotting
+    --format <text|json>          how to print the result (default text)
+    -h, --help                    print this message
+
+The tokenizer, stop word, `--map`, `--stem` and `--ngram` options of the word
+counting styles apply.
+";
+
+/// The command line: what to print and write, and the counting options.
+#[derive(Debug)]
+struct Options {
+    outliers: usize,
+    zipf_data: Option<String>,
+    heaps_data: Option<String>,
+    config: Config,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self> {
+        let mut options = Options {
+            outliers: 10,
+            zipf_data: None,
+            heaps_data: None,
+            config: Config::default(),
+        };
+        let mut shared = Vec::new();
+        let mut args = Args::new(args);
+        while let Some(arg) = args.next() {
+            match args.flag() {
+                "--outliers" => options.outliers = args.number()?,
+                "--zipf-data" => options.zipf_data = Some(args.value()?),
+                "--heaps-data" => options.heaps_data = Some(args.value()?),
+                _ => shared.push(arg),
+            }
+        }
+        // no cache: Heaps' law needs the words in order, not their counts
+        options.config = Config::parse(shared)?.uncached()?;
+        if options.config.ranking != Ranking::default() {
+            return Err(Error::Invalid(
+                "`--rank` does not apply, Zipf's law is about counts".to_string(),
+            ));
+        }
+        if options.config.per_file {
+            return Err(Error::Invalid("`--per-file` does not apply".to_string()));
+        }
+        Ok(options)
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        process::exit(if args.is_empty() { 2 } else { 0 });
+    }
+    if let Err(e) = Options::parse(args).and_then(run) {
+        eprintln!("zipf: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: Options) -> Result<()> {
+    let config = &options.config;
+    let stop_words = config.stop_words()?;
+    let mapping = config.mapping()?;
+    let stemmer = config.stemmer();
+    let sources = config.sources()?;
+    let text = Input::read_all(&sources)?;
+    let mut table = FrequencyTable::new();
+    let mut growth = Growth::new();
+    for word in config.words(&text, &stop_words, &mapping, &stemmer) {
+        growth.push(word.clone());
+        table.add(word);
+    }
+
+    let ranked = Ranking::Count.rank(&table);
+    let counts: Vec<usize> = ranked.iter().map(|(_w, c)| *c).collect();
+    let points = growth.points();
+    let (zipf, heaps) = match (Zipf::fit(&counts), Heaps::fit(&points)) {
+        (Some(zipf), Some(heaps)) => (zipf, heaps),
+        _ => {
+            return Err(Error::Invalid(
+                "not enough distinct words to fit the laws".to_string(),
+            ))
+        }
+    };
+
+    if let Some(path) = &options.zipf_data {
+        let mut out = String::from("rank\tcount\texpected\n");
+        for (i, c) in counts.iter().enumerate() {
+            // writing to a String never fails
+            let _ = writeln!(out, "{}\t{}\t{}", i + 1, c, zipf.expected(i + 1));
+        }
+        fs::write(path, out).map_err(|e| with_path(path, e))?;
+    }
+    if let Some(path) = &options.heaps_data {
+        let mut out = String::from("words\tvocabulary\texpected\n");
+        for &(n, v) in &points {
+            let _ = writeln!(out, "{}\t{}\t{}", n, v, heaps.expected(n));
+        }
+        fs::write(path, out).map_err(|e| with_path(path, e))?;
+    }
+
+    let report = Laws {
+        words: growth.words(),
+        vocabulary: growth.vocabulary(),
+        outliers: outliers(&ranked, &zipf, options.outliers)
+            .into_iter()
+            .map(|o| Outlier {
+                word: stemmer.label(&o.word),
+                ..o
+            })
+            .collect(),
+        zipf,
+        heaps,
+    };
+    print!("{}", report.render(config.format)?);
+    Ok(())
+}
+
+/// A word counted much more or much less than Zipf's law expects.
+#[derive(Debug)]
+struct Outlier {
+    rank: usize,
+    word: String,
+    count: usize,
+    expected: f64,
+}
+
+impl Outlier {
+    /// How many times more than expected, below 1 for less.
+    fn ratio(&self) -> f64 {
+        self.count as f64 / self.expected
+    }
+}
+
+/// The `n` words furthest from the fit, in log terms. Words with the same
+/// count share the middle of their ranks, otherwise the long tail of words
+/// counted once would drift below the line one rank after the other.
+fn outliers(ranked: &[(String, usize)], zipf: &Zipf, n: usize) -> Vec<Outlier> {
+    let mut ret = Vec::new();
+    let mut start = 0;
+    while start < ranked.len() {
+        let count = ranked[start].1;
+        let end = start + ranked[start..].iter().take_while(|e| e.1 == count).count();
+        let middle = (start + 1 + end) / 2;
+        let expected = zipf.expected(middle);
+        ret.extend(ranked[start..end].iter().map(|(w, c)| Outlier {
+            rank: middle,
+            word: w.clone(),
+            count: *c,
+            expected,
+        }));
+        start = end;
+    }
+    ret.sort_by(|o1, o2| {
+        o2.ratio()
+            .ln()
+            .abs()
+            .partial_cmp(&o1.ratio().ln().abs())
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(o1.rank.cmp(&o2.rank))
+    });
+    ret.truncate(n);
+    ret
+}
+
+/// What is printed: the two fits and the outliers.
+#[derive(Debug)]
+struct Laws {
+    words: usize,
+    vocabulary: usize,
+    zipf: Zipf,
+    heaps: Heaps,
+    outliers: Vec<Outlier>,
+}
+
+impl Laws {
+    fn render(&self, format: Format) -> Result<String> {
+        let mut out = String::new();
+        // writing to a String never fails
+        let _ = match format {
+            Format::Text => self.write_text(&mut out),
+            Format::Json => self.write_json(&mut out),
+            _ => return Err(Error::Invalid("zipf prints text or json".to_string())),
+        };
+        Ok(out)
+    }
+
+    fn write_text(&self, out: &mut String) -> std::fmt::Result {
+        writeln!(out, "words: {}", self.words)?;
+        writeln!(out, "distinct words: {}", self.vocabulary)?;
+        writeln!(out)?;
+        writeln!(out, "Zipf's law: count = C / rank^s")?;
+        writeln!(out, "  s = {:.4}", self.zipf.exponent)?;
+        writeln!(out, "  C = {:.1}", self.zipf.constant)?;
+        write_fit(out, &self.zipf.fit)?;
+        writeln!(out)?;
+        writeln!(out, "Heaps' law: distinct words = K * words^beta")?;
+        writeln!(out, "  beta = {:.4}", self.heaps.beta)?;
+        writeln!(out, "  K = {:.2}", self.heaps.k)?;
+        write_fit(out, &self.heaps.fit)?;
+        if self.outliers.is_empty() {
+            return Ok(());
+        }
+        writeln!(out)?;
+        writeln!(out, "Furthest from Zipf's law:")?;
+        writeln!(
+            out,
+            "{:>6} {:>20} {:>7} {:>9} {:>7}",
+            "rank", "word", "count", "expected", "ratio"
+        )?;
+        for o in &self.outliers {
+            writeln!(
+                out,
+                "{:>6} {:>20} {:>7} {:>9.1} {:>7.2}",
+                o.rank,
+                o.word,
+                o.count,
+                o.expected,
+                o.ratio()
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_json(&self, out: &mut String) -> std::fmt::Result {
+        let fit = |f: &Fit| {
+            format!(
+                "\"r_squared\": {}, \"rms_log_error\": {}",
+                f.r_squared, f.rms_error
+            )
+        };
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"words\": {},", self.words)?;
+        writeln!(out, "  \"distinct_words\": {},", self.vocabulary)?;
+        writeln!(
+            out,
+            "  \"zipf\": {{\"exponent\": {}, \"constant\": {}, {}}},",
+            self.zipf.exponent,
+            self.zipf.constant,
+            fit(&self.zipf.fit)
+        )?;
+        writeln!(
+            out,
+            "  \"heaps\": {{\"beta\": {}, \"k\": {}, {}}},",
+            self.heaps.beta,
+            self.heaps.k,
+            fit(&self.heaps.fit)
+        )?;
+        writeln!(out, "  \"outliers\": [")?;
+        for (i, o) in self.outliers.iter().enumerate() {
+            let comma = if i + 1 < self.outliers.len() { "," } else { "" };
+            writeln!(
+                out,
+                "    {{\"rank\": {}, \"word\": {}, \"count\": {}, \"expected\": {}, \"ratio\": {}}}{}",
+                o.rank,
+                json_string(&o.word),
+                o.count,
+                o.expected,
+                o.ratio(),
+                comma
+            )?;
+        }
+        writeln!(out, "  ]")?;
+        writeln!(out, "}}")
+    }
+}
+
+fn write_fit(out: &mut String, fit: &Fit) -> std::fmt::Result {
+    writeln!(out, "  R² = {:.4}", fit.r_squared)?;
+    writeln!(out, "  RMS error of the logs = {:.4}", fit.rms_error)
+}
@@ -1,11 +1,5 @@
-use std::{
-    cmp::Reverse,
-    collections::{HashMap, HashSet},
-    env,
-    fs::File,
-    io::{BufRead, BufReader},
-    process,
-};
+use std::{env, io::BufRead, process};
+use tf_core::{Config, FrequencyTable, Input, Mapping, Report, Stemmer, StopWords};
 
 mod retcode {
     pub const OPEN_FILE: i32 = 1;
@@ -13,64 +7,118 @@ mod retcode {
     pub const ARG: i32 = 3;
 }
 
-fn get_stop_words() -> HashSet<String> {
-    let stop_words_file = File::open("../stop_words.txt").unwrap_or_else(|e| {
-        eprintln!("Error open stop words file: {}", e);
-        process::exit(retcode::OPEN_FILE);
-    });
-    let mut stop_words_reader = BufReader::new(stop_words_file);
-    let mut buf = Default::default();
-    stop_words_reader.read_line(&mut buf).unwrap_or_else(|e| {
+fn get_config() -> Config {
+    Config::from_env().unwrap_or_else(|e| {
+        eprintln!("Error parse arguments: {}", e);
+        process::exit(retcode::ARG);
+    })
+}
+
+fn get_stop_words(config: &Config) -> StopWords {
+    config.stop_words().unwrap_or_else(|e| {
         eprintln!("Error read stop words: {}", e);
         process::exit(retcode::READ);
-    });
-    buf.split(',').map(|s| s.to_string()).collect()
+    })
+}
+
+fn get_mapping(config: &Config) -> Mapping {
+    config.mapping().unwrap_or_else(|e| {
+        eprintln!("Error read character mapping: {}", e);
+        process::exit(retcode::READ);
+    })
 }
 
-fn get_reader() -> BufReader<File> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        println!("Usage: {} <some_text_file>", args[0]);
+fn get_runs(config: &Config) -> Vec<Config> {
+    if config.inputs.is_empty() {
+        let program = env::args().next().unwrap_or_default();
+        println!("Usage: {} [options] <some_text_file>...", program);
         process::exit(retcode::ARG);
     }
-    let filename = &args[1];
-    // read file
-    let f = File::open(filename).unwrap_or_else(|e| {
-        eprintln!("Error open file `{}`: {}", filename, e);
+    config.runs().unwrap_or_else(|e| {
+        eprintln!("Error find input files: {}", e);
         process::exit(retcode::OPEN_FILE);
-    });
-    BufReader::new(f)
+    })
+}
+
+fn get_sources(config: &Config) -> Vec<Input> {
+    config.sources().unwrap_or_else(|e| {
+        eprintln!("Error find input files: {}", e);
+        process::exit(retcode::OPEN_FILE);
+    })
+}
+
+fn get_reader(sources: &[Input]) -> Box<dyn BufRead> {
+    // read files
+    Input::open_all(sources).unwrap_or_else(|e| {
+        eprintln!("Error open file: {}", e);
+        process::exit(retcode::OPEN_FILE);
+    })
 }
 
 fn main() {
-    // read stop words
-    let stop_words = get_stop_words();
+    let config = get_config();
+    let mapping = get_mapping(&config);
+    for run in get_runs(&config) {
+        // read stop words, with auto those of this run's inputs
+        let stop_words = get_stop_words(&run);
+        count_and_print(&run, &stop_words, &mapping);
+    }
+}
+
+fn count_and_print(config: &Config, stop_words: &StopWords, mapping: &Mapping) {
+    let sources = get_sources(config);
+    let stemmer = config.stemmer();
+    // with --cache, inputs counted before with the same options are not
+    // read again
+    let counter = config
+        .counted(&sources, stop_words, mapping, || {
+            Ok(count(config, &sources, stop_words, mapping, &stemmer))
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("Error cache counts: {}", e);
+            process::exit(retcode::READ);
+        });
+    let counter = stemmer.relabel(&counter);
+    // sort & print the requested ranks only
+    Report::new(config.ranking.rank(&counter), counter.total())
+        .ranked()
+        .ranked_by(config.ranking)
+        .paged(&config.page)
+        .of_file(config.file.clone())
+        .after_file(config.after_file)
+        .with_notes(config.about().unwrap_or_else(|e| {
+            eprintln!("Error read input files: {}", e);
+            process::exit(retcode::READ);
+        }))
+        .print(config.format);
+}
+
+fn count(
+    config: &Config,
+    sources: &[Input],
+    stop_words: &StopWords,
+    mapping: &Mapping,
+    stemmer: &Stemmer,
+) -> FrequencyTable {
+    let tokenizer = &config.tokenizer;
     // read file
-    let reader = get_reader();
+    let reader = get_reader(sources);
     // parse & count
-    let mut counter: HashMap<String, usize> = HashMap::new();
-    for line in reader.lines() {
+    let words = reader.lines().flat_map(|line| {
         let line = line.unwrap_or_else(|e| {
             eprintln!("Failed to parse line: {}", e);
             process::exit(retcode::READ);
         });
-        line.split(|c: char| !c.is_ascii_alphabetic())
-            .filter(|word| word.len() > 1)
-            .map(|word| word.to_lowercase())
-            .filter(|word| !stop_words.contains(word))
-            .for_each(|word| {
-                *counter.entry(word).or_default() += 1;
-            })
-    }
-    // sort & print
-    let mut entries: Vec<(Reverse<usize>, String)> = counter
-        .into_iter()
-        .map(|(word, times)| (Reverse(times), word))
-        .collect();
-    entries.sort_unstable();
-    // top 25 only
-    entries.truncate(25);
-    for (idx, (Reverse(times), word)) in entries.into_iter().enumerate() {
-        println!("[{:>2}] {:>20} - {:>4}", idx + 1, word, times);
-    }
+        tokenizer
+            .words(&line)
+            .map(|word| mapping.before(word))
+            .collect::<Vec<_>>()
+    });
+    // n-grams run across lines, so the words of all lines are one sequence
+    config
+        .ngrams
+        .of(words, |word| stop_words.contains(word))
+        .map(|word| mapping.after(word))
+        .map(|word| stemmer.stem(word))
+        .collect()
 }
@@ -3,125 +3,158 @@
 //! Constraints:
 //! - Larger problem decomposed in procedural abstractions
 //! - Larger problem solved as a sequence of commands, each corresponding to a procedure
+//!
 //! Possible names:
 //! - Cookbook
 //! - Procedural
 
-use std::{
-    cmp::Reverse,
-    env,
-    fs::File,
-    io::{BufRead, BufReader},
-};
+use std::io::BufRead;
+use tf_core::{Config, FrequencyTable, Input, Mapping, Report, Stemmer, StopWords};
 
 // Please know that Rust does not allow static mutable variables because they
 // are UNSAFE and may cause memory violation. Therefore, I'm using local variables
 // with closures.
 
 fn main() {
+    let config = Config::from_env().unwrap();
+    // with --per-file: each input alone, then all of them together
+    for config in config.runs().unwrap() {
+        word_frequency(&config);
+    }
+}
+
+fn word_frequency(config: &Config) {
     // shared mutable data
-    let mut stop_words = Vec::new();
-    let mut words = Vec::new();
+    let stemmer = config.stemmer();
+    let mapping = config.mapping().unwrap();
+    let sources = config.sources().unwrap();
+    let mut stop_words = StopWords::default();
+    let mut counter = FrequencyTable::new();
     // [ (word, times) ]
-    let mut counter: Vec<(String, usize)> = Vec::new();
+    let mut sorted: Vec<(String, usize)> = Vec::new();
 
     // This is closure in Rust, works like a function / procedure except that it
     // can capture local variables, to some extent.
     //
-    // read the stop word file to the stop_words variable.
+    // read the stop word lists to the stop_words variable.
     let mut read_stop_words = || {
-        let stop_words_file = File::open("../stop_words.txt").unwrap();
-        let mut stop_words_reader = BufReader::new(stop_words_file);
-        let mut buf = String::new();
-        stop_words_reader.read_line(&mut buf).unwrap();
-        stop_words = buf.split(',').map(|s| s.to_string()).collect();
+        stop_words = config.stop_words().unwrap();
+    };
+
+    read_stop_words();
+
+    // count the words of the input files to the counter variable; with
+    // --cache, inputs counted before with the same options are not read
+    // again.
+    let mut count = || {
+        counter = config
+            .counted(&sources, &stop_words, &mapping, || {
+                Ok(count_words(
+                    config,
+                    &sources,
+                    &stop_words,
+                    &mapping,
+                    &stemmer,
+                ))
+            })
+            .unwrap();
+    };
+
+    count();
+
+    // now sort the counter.
+    let mut sort = || {
+        sorted = config.ranking.rank(&stemmer.relabel(&counter));
+    };
+
+    sort();
+
+    // print the counter.
+    let print = || {
+        Report::new(sorted.clone(), counter.total())
+            .ranked()
+            .ranked_by(config.ranking)
+            .paged(&config.page)
+            .of_file(config.file.clone())
+            .after_file(config.after_file)
+            .with_notes(config.about().unwrap())
+            .print(config.format);
     };
 
-    // read the input file to the words variable.
+    print();
+}
+
+fn count_words(
+    config: &Config,
+    sources: &[Input],
+    stop_words: &StopWords,
+    mapping: &Mapping,
+    stemmer: &Stemmer,
+) -> FrequencyTable {
+    // shared mutable data
+    let tokenizer = &config.tokenizer;
+    let mut words = Vec::new();
+    let mut counter = FrequencyTable::new();
+
+    // read the input files to the words variable.
     let mut read_input_file = || {
-        let args: Vec<String> = env::args().collect();
-        let filename = &args[1];
-        let file = File::open(filename).unwrap();
-        let reader = BufReader::new(file);
+        let reader = Input::open_all(sources).unwrap();
 
         // process
         for line in reader.lines() {
             let line = line.unwrap();
 
-            // process through chars
-            for word in line.split(|ch: char| !ch.is_ascii_alphabetic()) {
-                // add to words
-                words.push(word.to_lowercase());
+            // add to words
+            for word in tokenizer.segments(&line) {
+                words.push(word.to_string());
             }
         }
     };
 
-    // An mutable object cannot be referenced as mutable multiple times
-    // because it may cause data race and thus is UNSAFE. I'm calling
-    // the first two procedures here to avoid that.
-    read_stop_words();
     read_input_file();
 
-    // filter the read words by length and stop words.
+    // filter the read words by length and stop words, and join them into
+    // n-grams with --ngram. Characters are replaced before or after that,
+    // with --map, and contractions and possessives handled before, with
+    // --contractions and --strip-possessives.
     let mut filter = || {
         for word in words.iter_mut() {
             *word = word.to_lowercase();
         }
-        let mut to_remove_indexes = Vec::new();
-        for i in 0..words.len() {
-            if words[i].len() <= 1 {
-                to_remove_indexes.push(i);
-                continue;
-            }
-            for stop_word in stop_words.iter() {
-                if stop_word == &words[i] {
-                    to_remove_indexes.push(i);
-                    break;
-                }
-            }
-        }
-        for i in to_remove_indexes.into_iter().rev() {
-            words.remove(i);
+        words = std::mem::take(&mut words)
+            .into_iter()
+            .flat_map(|word| tokenizer.normalize(word))
+            .collect();
+        words.retain(|word| tokenizer.accepts(word));
+        for word in words.iter_mut() {
+            *word = mapping.before(std::mem::take(word));
         }
+        words = config
+            .ngrams
+            .of(std::mem::take(&mut words), |word| stop_words.contains(word))
+            .map(|word| mapping.after(word))
+            .collect();
     };
 
     filter();
 
+    // reduce the words to their stems, with --stem.
+    let mut stem = || {
+        for word in words.iter_mut() {
+            *word = stemmer.stem(std::mem::take(word));
+        }
+    };
+
+    stem();
+
     // make words to counter.
     let mut count = || {
         for word in words.iter() {
-            let mut seen = false;
-            for (w, times) in counter.iter_mut() {
-                if w == word {
-                    *times += 1;
-                    seen = true;
-                    break;
-                }
-            }
-            if !seen {
-                counter.push((word.clone(), 1));
-            }
+            counter.add(word.clone());
         }
     };
 
     count();
 
-    // now sort the counter.
-    let mut sort = || {
-        counter.sort_unstable_by_key(|tuple| Reverse(tuple.1));
-    };
-
-    sort();
-
-    // print the counter.
-    let print = || {
-        for i in 0..25 {
-            if i >= counter.len() {
-                break;
-            }
-            println!("[{:>2}] {:>20} - {:>4}", i + 1, counter[i].0, counter[i].1);
-        }
-    };
-
-    print();
+    counter
 }
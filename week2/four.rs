@@ -3,108 +3,145 @@
 //! Constraints:
 //! + No abstractions
 //! + No use of library functions
+//!
 //! Possible names:
 //! + Monolith
 //! + Labyrinth
 //! + Brain dump
+//!
+//! `tf_core` only reads the arguments, opens the inputs and prints the
+//! result; words, stop words, n-grams and counts are worked out by hand.
 
-use std::{
-    env,
-    fs::File,
-    io::{BufRead, BufReader},
-};
+use std::{cmp::Ordering, io::BufRead};
+use tf_core::{ngrams::StopWordPolicy, tokenizer::Segmentation, Config, Input, Report, Stemming};
 
 fn main() {
-    // read stop words
-    let stop_words_file = File::open("../stop_words.txt").unwrap();
-    let mut stop_words_reader = BufReader::new(stop_words_file);
-    let mut buf = String::new();
-    stop_words_reader.read_line(&mut buf).unwrap();
-    let mut stop_words = Vec::new();
-    let mut word = String::new();
-    for ch in buf.chars() {
-        if ch.is_alphabetic() {
-            word.push(ch);
-        } else {
-            stop_words.push(word);
-            word = String::new();
-        }
+    // counts from scratch: the cache is a library function too
+    let config = Config::from_env().and_then(Config::uncached).unwrap();
+    // character tables, stems and contraction rules all take library code
+    if !config.maps.is_empty() {
+        panic!("`--map` does not apply, style 4 uses no library functions");
+    }
+    if config.stemming != Stemming::Off {
+        panic!("`--stem` and `--surface-forms` do not apply, style 4 uses no library functions");
     }
-    if word.len() > 0 {
-        stop_words.push(word);
+    if config.tokenizer.keeps_apostrophes() {
+        panic!(
+            "`--contractions` and `--strip-possessives` do not apply, \
+             style 4 cuts words at every apostrophe"
+        );
     }
-    // read file
-    let args: Vec<String> = env::args().collect();
-    let filename = &args[1];
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
+    let segmentation = config.tokenizer.segmentation();
+    let min_len = config.tokenizer.min_len();
+    let n = config.ngrams.n;
+    // with --per-file: each input alone, then all of them together
+    for config in config.runs().unwrap() {
+        // read stop words, with auto those of this run's inputs
+        let stop_words: Vec<String> = config
+            .stop_words()
+            .unwrap()
+            .iter()
+            .map(String::from)
+            .collect();
+        // read files
+        let reader = Input::open_all(&config.sources().unwrap()).unwrap();
 
-    // process
-    // [ (word, times) ]
-    let mut counter: Vec<(String, usize)> = Vec::new();
+        // process
+        // [ (word, times) ]
+        let mut counter: Vec<(String, usize)> = Vec::new();
+        // the last words, with --ngram
+        let mut window: Vec<String> = Vec::new();
 
-    // iterate through lines is also used in tf-04.py
-    for line in reader.lines() {
-        let line = line.unwrap();
-        let char_count = line.chars().count();
+        // iterate through lines is also used in tf-04.py
+        for line in reader.lines() {
+            let line = line.unwrap();
 
-        let mut word = String::new();
-        // process through chars
-        for (idx, ch) in line.chars().enumerate() {
-            if ch.is_alphabetic() {
-                // since a utf-8 char does not necessary have a lower case in a single char
-                // we're using string
-                word.push_str(&ch.to_lowercase().to_string());
-            }
-            // since rust `lines` methods does not have NEWLINE char, manually detect
-            if !ch.is_alphabetic() || idx == char_count - 1 {
-                // test single characters
-                let mut acceptable = word.len() > 1;
+            let mut word = String::new();
+            // process through chars; the space after the line ends its last word
+            for ch in line.chars().chain(Some(' ')) {
+                let is_word_char = match segmentation {
+                    Segmentation::Ascii => ch.is_ascii_alphabetic(),
+                    Segmentation::AsciiAlphanumeric => ch.is_ascii_alphanumeric(),
+                    Segmentation::Unicode => ch.is_alphanumeric(),
+                };
+                if is_word_char {
+                    // since a utf-8 char does not necessary have a lower case in a single char
+                    // we're using string
+                    word.push_str(&ch.to_lowercase().to_string());
+                    continue;
+                }
+                if word.is_empty() {
+                    continue;
+                }
+                // test the length, in characters
+                let mut acceptable = word.chars().count() >= min_len;
                 // test stop words
                 if acceptable {
                     for stop_word in stop_words.iter() {
                         if stop_word == &word {
                             acceptable = false;
+                            // no n-gram spans a stop word with `break`
+                            if config.ngrams.stop_words == StopWordPolicy::Break {
+                                window.clear();
+                            }
                             break;
                         }
                     }
                 }
+                if !acceptable {
+                    word.clear();
+                    continue;
+                }
 
-                if acceptable {
-                    // count the word
-                    let mut found = false;
-                    for i in 0..counter.len() {
-                        if counter[i].0 == word {
-                            counter[i].1 += 1;
-                            found = true;
-                            break;
-                        }
-                    }
-                    if !found {
-                        counter.push((word, 1));
+                // wait until there are enough words for an n-gram
+                window.push(word);
+                word = String::new();
+                if window.len() > n {
+                    window.remove(0);
+                }
+                if window.len() < n {
+                    continue;
+                }
+                let ngram = window.join(" ");
+                // count the word
+                let mut found = false;
+                for entry in counter.iter_mut() {
+                    if entry.0 == ngram {
+                        entry.1 += 1;
+                        found = true;
+                        break;
                     }
                 }
-                // reset word
-                word = String::new();
+                if !found {
+                    counter.push((ngram, 1));
+                }
             }
         }
-    }
 
-    // sort counter
-    let n = counter.len();
-    for i in 0..n {
-        for j in (i + 1..n).rev() {
-            if counter[j - 1].1 < counter[j].1 {
-                // swap i and j
-                counter.swap(j - 1, j);
+        // sort counter. The bubble sort is stable and the counter is in
+        // first-seen order, so entries the ranking finds equal stay that way
+        let n = counter.len();
+        for i in 0..n {
+            for j in (i + 1..n).rev() {
+                let (prev, cur) = (&counter[j - 1], &counter[j]);
+                if config.ranking.compare((&prev.0, prev.1), (&cur.0, cur.1)) == Ordering::Greater {
+                    // swap i and j
+                    counter.swap(j - 1, j);
+                }
             }
         }
-    }
-    // print
-    for i in 0..25 {
-        if i >= counter.len() {
-            break;
+        // print
+        let mut total = 0;
+        for entry in counter.iter() {
+            total += entry.1;
         }
-        println!("[{:>2}] {:>20} - {:>4}", i + 1, counter[i].0, counter[i].1);
+        Report::new(counter, total)
+            .ranked()
+            .ranked_by(config.ranking)
+            .paged(&config.page)
+            .of_file(config.file.clone())
+            .after_file(config.after_file)
+            .with_notes(config.about().unwrap())
+            .print(config.format);
     }
 }
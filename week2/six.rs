@@ -3,6 +3,7 @@
 //! Constraints:
 //! - Larger problem decomposed in functional abstractions. Functions, according to Mathematics, are relations from inputs to outputs.
 //! - Larger problem solved as a pipeline of function applications
+//!
 //! Possible names:
 //! - Candy factory
 //! - Functional
@@ -60,30 +61,22 @@ where
 }
 
 // ===============  exercise related logic ======================
-use std::{
-    cmp::Reverse,
-    collections::{HashMap, HashSet},
-    env,
-    fs::File,
-    io::{BufRead, BufReader},
+use std::io::BufRead;
+use tf_core::{
+    output::Note, Config, FrequencyTable, Input, Mapping, Ngrams, Page, Ranking, Report, Stemmer,
+    StopWords, Tokenizer,
 };
 
-fn get_reader() -> BufReader<File> {
-    let args: Vec<String> = env::args().collect();
-    let filename = &args[1];
-    // read file
-    let f = File::open(filename).unwrap();
-    BufReader::new(f)
+fn get_reader(config: &Config) -> Box<dyn BufRead> {
+    // read files
+    Input::open_all(&config.sources().unwrap()).unwrap()
 }
 
-fn get_words(reader: BufReader<File>) -> Vec<String> {
+fn get_words(reader: Box<dyn BufRead>, tokenizer: &Tokenizer) -> Vec<String> {
     let mut words = Vec::new();
     for line in reader.lines() {
         let line = line.unwrap();
-        words.extend(
-            line.split(|c: char| !c.is_ascii_alphabetic())
-                .map(|s| s.to_string()),
-        );
+        words.extend(tokenizer.segments(&line).map(|s| s.to_string()));
     }
     words
 }
@@ -92,51 +85,101 @@ fn lower(words: Vec<String>) -> Vec<String> {
     words.into_iter().map(|w| w.to_lowercase()).collect()
 }
 
-/// filter out single characters and stop words
-fn filter(words: Vec<String>) -> Vec<String> {
-    let f = File::open("../stop_words.txt").unwrap();
-    let mut reader = BufReader::new(f);
-    let mut buf = String::new();
-    reader.read_line(&mut buf).unwrap();
-    let stop_words: HashSet<String> = buf.split(',').map(|s| s.to_string()).collect();
-
+/// keep, expand or split contractions with --contractions, and strip
+/// possessives with --strip-possessives
+fn normalize(words: Vec<String>, tokenizer: &Tokenizer) -> Vec<String> {
     words
         .into_iter()
-        .filter(|w| w.len() > 1 && !stop_words.contains(w))
+        .flat_map(|w| tokenizer.normalize(w))
         .collect()
 }
 
-fn count(words: Vec<String>) -> Vec<(String, usize)> {
-    let mut counter = HashMap::new();
-    for w in words {
-        *counter.entry(w).or_default() += 1;
-    }
-    counter.into_iter().collect()
+/// filter out single characters
+fn filter_short(words: Vec<String>, tokenizer: &Tokenizer) -> Vec<String> {
+    words.into_iter().filter(|w| tokenizer.accepts(w)).collect()
 }
 
-fn sort(mut items: Vec<(String, usize)>) -> Vec<(String, usize)> {
-    items.sort_unstable_by_key(|item| Reverse(item.1));
-    items
+/// filter out stop words, joining the rest into n-grams with --ngram
+fn filter(words: Vec<String>, stop_words: &StopWords, ngrams: Ngrams) -> Vec<String> {
+    ngrams.of(words, |w| stop_words.contains(w)).collect()
 }
 
-fn truncate_25(mut items: Vec<(String, usize)>) -> Vec<(String, usize)> {
-    items.truncate(25);
-    items
+/// replace characters with --map, `map` is the step before or after `filter`
+fn map_chars(words: Vec<String>, map: impl Fn(String) -> String) -> Vec<String> {
+    words.into_iter().map(map).collect()
 }
 
-fn print(data: Vec<(String, usize)>) {
-    for (idx, row) in data.into_iter().enumerate() {
-        println!("[{:>2}] {:>20} - {:>4}", idx + 1, row.0, row.1);
-    }
+fn stem(words: Vec<String>, stemmer: &Stemmer) -> Vec<String> {
+    words.into_iter().map(|w| stemmer.stem(w)).collect()
+}
+
+fn count(words: Vec<String>) -> FrequencyTable {
+    words.into_iter().collect()
+}
+
+/// the counts of the inputs, or with --cache those of the same inputs counted
+/// before with the same options
+fn count_inputs(
+    config: &Config,
+    stop_words: &StopWords,
+    mapping: &Mapping,
+    stemmer: &Stemmer,
+) -> FrequencyTable {
+    let tokenizer = &config.tokenizer;
+    let sources = config.sources().unwrap();
+    let counted = config.counted(&sources, stop_words, mapping, || {
+        let table = Value::v(config)
+            | Pipe::f(get_reader)
+            | Pipe::f(|reader| get_words(reader, tokenizer))
+            | Pipe::f(lower)
+            | Pipe::f(|words| normalize(words, tokenizer))
+            | Pipe::f(|words| filter_short(words, tokenizer))
+            | Pipe::f(|words| map_chars(words, |w| mapping.before(w)))
+            | Pipe::f(|words| filter(words, stop_words, config.ngrams))
+            | Pipe::f(|words| map_chars(words, |w| mapping.after(w)))
+            | Pipe::f(|words| stem(words, stemmer))
+            | Pipe::f(count);
+        Ok(table.value())
+    });
+    counted.unwrap()
+}
+
+/// report stems as the word they came from most often, with --surface-forms
+fn surface_forms(table: FrequencyTable, stemmer: &Stemmer) -> FrequencyTable {
+    stemmer.relabel(&table)
+}
+
+fn sort(table: FrequencyTable, ranking: Ranking) -> Report {
+    Report::new(ranking.rank(&table), table.total())
+        .ranked()
+        .ranked_by(ranking)
+}
+
+fn paginate(report: Report, page: &Page) -> Report {
+    report.paged(page)
+}
+
+fn print(report: Report, config: &Config, about: Vec<Note>) {
+    report
+        .of_file(config.file.clone())
+        .after_file(config.after_file)
+        .with_notes(about)
+        .print(config.format)
 }
 
 fn main() {
-    let _ = Value::v(get_reader())
-        | Pipe::f(get_words)
-        | Pipe::f(lower)
-        | Pipe::f(filter)
-        | Pipe::f(count)
-        | Pipe::f(sort)
-        | Pipe::f(truncate_25)
-        | Pipe::f(print);
+    let config = Config::from_env().unwrap();
+    let mapping = config.mapping().unwrap();
+    // with --per-file: each input alone, then all of them together
+    for config in config.runs().unwrap() {
+        let stop_words = config.stop_words().unwrap();
+        let stemmer = config.stemmer();
+        let about = config.about().unwrap();
+        let _ = Value::v(&config)
+            | Pipe::f(|config| count_inputs(config, &stop_words, &mapping, &stemmer))
+            | Pipe::f(|table| surface_forms(table, &stemmer))
+            | Pipe::f(|table| sort(table, config.ranking))
+            | Pipe::f(|report| paginate(report, &config.page))
+            | Pipe::f(|report| print(report, &config, about.clone()));
+    }
 }
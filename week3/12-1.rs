@@ -13,13 +13,10 @@
 //! - Objects
 //! - Actors
 
-use std::{
-    cmp::Reverse,
-    collections::{HashMap, HashSet},
-    env,
-    fs::File,
-    io::{BufRead, BufReader},
-    panic,
+use std::{io::BufRead, panic};
+use tf_core::{
+    ngrams::Window, output::Note, Config, Format, FrequencyTable, Input, Mapping, Ngrams, Page,
+    Ranking, Report, Stemmer, Stemming, StopWords, Tokenizer,
 };
 
 /// This is core abstraction: each *THING* should have one single exposed procedure.
@@ -32,6 +29,18 @@ trait Letterbox {
 /// The main logic lies inside.
 #[derive(Debug, Default)]
 struct WordFrequencyController {
+    format: Format,
+    ranking: Ranking,
+    stemming: Stemming,
+    ngrams: Ngrams,
+    mapping: Mapping,
+    page: Page,
+    /// set when reporting on a single input out of several
+    file: Option<String>,
+    after_file: bool,
+    about: Vec<Note>,
+    /// of this run, with auto those of its inputs
+    stop_word_list: StopWords,
     data: Option<DataStorageManager>,
     stop_words: Option<StopWordsManager>,
     counter: Option<WordFrequencyManager>,
@@ -43,14 +52,24 @@ impl Letterbox for WordFrequencyController {
         let file = message;
         match command {
             "init" => {
-                self.data = Some(DataStorageManager::default());
-                self.stop_words = Some(StopWordsManager::default());
-                self.counter = Some(WordFrequencyManager::default());
-                self.data.as_mut().unwrap().dispatch("init", file);
-                self.stop_words
-                    .as_mut()
-                    .unwrap()
-                    .dispatch("init", "".to_string());
+                self.data = Some(DataStorageManager {
+                    mapping: self.mapping.clone(),
+                    ..Default::default()
+                });
+                self.stop_words = Some(StopWordsManager {
+                    stop_words: self.stop_word_list.clone(),
+                });
+                self.counter = Some(WordFrequencyManager {
+                    ranking: self.ranking,
+                    stemmer: Stemmer::new(self.stemming),
+                    window: self.ngrams.window(),
+                    mapping: self.mapping.clone(),
+                    ..Default::default()
+                });
+                self.data.as_mut().unwrap().dispatch("init", "".to_string());
+            }
+            "load" => {
+                self.data.as_mut().unwrap().dispatch("load", file);
             }
             "run" => {
                 let words = self
@@ -66,21 +85,27 @@ impl Letterbox for WordFrequencyController {
                         .dispatch("is_stop_word", w.clone())
                     {
                         self.counter.as_mut().unwrap().dispatch("incr", w);
+                    } else {
+                        self.counter
+                            .as_mut()
+                            .unwrap()
+                            .dispatch("stop", "".to_string());
                     }
                 }
-                for (index, (word, times)) in self
-                    .counter
-                    .as_mut()
-                    .unwrap()
-                    .dispatch("top", "25".to_string())
-                    .into_iter()
-                    .enumerate()
-                {
-                    println!("[{:>2}] {:>20} - {:>4}", index + 1, word, times);
-                }
+                let counter = self.counter.as_mut().unwrap();
+                let sorted = counter.dispatch("sorted", "".to_string());
+                let total = counter.dispatch("total", "".to_string())[0].1;
+                Report::new(sorted, total)
+                    .ranked()
+                    .ranked_by(self.ranking)
+                    .paged(&self.page)
+                    .of_file(self.file.clone())
+                    .after_file(self.after_file)
+                    .with_notes(self.about.clone())
+                    .print(self.format);
             }
             _ => {
-                panic!(format!("unknown command: {}", command));
+                panic!("unknown command: {}", command);
             }
         }
     }
@@ -89,7 +114,12 @@ impl Letterbox for WordFrequencyController {
 /// Store & split the words.
 #[derive(Debug, Default)]
 struct DataStorageManager {
-    /// The words from file
+    tokenizer: Tokenizer,
+    /// replaces characters before stop-word filtering, with --map
+    mapping: Mapping,
+    /// read Project Gutenberg files whole, with --keep-boilerplate
+    keep_boilerplate: bool,
+    /// The words from the files
     words: Vec<String>,
 }
 impl Letterbox for DataStorageManager {
@@ -99,21 +129,26 @@ impl Letterbox for DataStorageManager {
         let file = message;
         match command {
             "init" => {
-                let f = File::open(file).unwrap();
-                let reader = BufReader::new(f);
+                let config = Config::from_env().unwrap();
+                self.tokenizer = config.tokenizer;
+                self.keep_boilerplate = config.keep_boilerplate;
+                vec![]
+            }
+            "load" => {
+                let input: Input = file.parse().unwrap();
+                let input = input.keeping_boilerplate(self.keep_boilerplate);
+                let reader = input.open().unwrap();
                 for line in reader.lines() {
                     let line = line.unwrap();
-                    self.words.extend(
-                        line.split(|ch: char| !ch.is_ascii_alphabetic())
-                            .filter(|w| w.len() > 1)
-                            .map(|s| s.to_lowercase()),
-                    );
+                    let mapping = &self.mapping;
+                    self.words
+                        .extend(self.tokenizer.words(&line).map(|w| mapping.before(w)));
                 }
                 vec![]
             }
             "words" => self.words.clone(),
             _ => {
-                panic!(format!("unknown command: {}", command));
+                panic!("unknown command: {}", command);
             }
         }
     }
@@ -121,7 +156,7 @@ impl Letterbox for DataStorageManager {
 
 #[derive(Debug, Default)]
 struct StopWordsManager {
-    stop_words: HashSet<String>,
+    stop_words: StopWords,
 }
 impl Letterbox for StopWordsManager {
     type Input = String;
@@ -129,18 +164,9 @@ impl Letterbox for StopWordsManager {
     fn dispatch(&mut self, command: &'static str, message: String) -> bool {
         let word = message;
         match command {
-            "init" => {
-                let f = File::open("../stop_words.txt").unwrap();
-                let reader = BufReader::new(f);
-                for line in reader.lines() {
-                    self.stop_words
-                        .extend(line.unwrap().split(',').map(|s| s.to_string()));
-                }
-                true
-            }
             "is_stop_word" => self.stop_words.contains(&word),
             _ => {
-                panic!(format!("unknown command: {}", command));
+                panic!("unknown command: {}", command);
             }
         }
     }
@@ -148,7 +174,13 @@ impl Letterbox for StopWordsManager {
 
 #[derive(Debug, Default)]
 struct WordFrequencyManager {
-    counter: HashMap<String, usize>,
+    ranking: Ranking,
+    stemmer: Stemmer,
+    /// the last words, with --ngram
+    window: Window,
+    /// replaces characters after stop-word filtering, with --map
+    mapping: Mapping,
+    counter: FrequencyTable,
 }
 impl Letterbox for WordFrequencyManager {
     type Input = String;
@@ -156,26 +188,48 @@ impl Letterbox for WordFrequencyManager {
     fn dispatch(&mut self, command: &'static str, message: String) -> Vec<(String, usize)> {
         match command {
             "incr" => {
-                *self.counter.entry(message).or_default() += 1;
+                if let Some(ngram) = self.window.push(message) {
+                    let word = self.mapping.after(ngram);
+                    self.counter.add(self.stemmer.stem(word));
+                }
                 vec![]
             }
-            "top" => {
-                let mut entries: Vec<(String, usize)> =
-                    self.counter.iter().map(|(w, c)| (w.clone(), *c)).collect();
-                entries.sort_unstable_by_key(|en| Reverse(en.1));
-                entries.truncate(message.parse().unwrap());
-                entries
+            "stop" => {
+                self.window.stop();
+                vec![]
             }
+            "sorted" => self.ranking.rank(&self.stemmer.relabel(&self.counter)),
+            "total" => vec![("total".to_string(), self.counter.total())],
             _ => {
-                panic!(format!("unknown command: {}", command));
+                panic!("unknown command: {}", command);
             }
         }
     }
 }
 
 fn main() {
-    let mut controller = WordFrequencyController::default();
-    let file = env::args().skip(1).next().expect("No file provided.");
-    controller.dispatch("init", file);
-    controller.dispatch("run", "".to_string());
+    // counts from scratch: the counts live in a capsule and grow one message
+    // at a time, no table comes back to be cached
+    let config = Config::from_env().and_then(Config::uncached).unwrap();
+    // with --per-file: each input alone, then all of them together
+    for config in config.runs().unwrap() {
+        let mut controller = WordFrequencyController {
+            format: config.format,
+            ranking: config.ranking,
+            stemming: config.stemming,
+            ngrams: config.ngrams,
+            mapping: config.mapping().unwrap(),
+            page: config.page,
+            file: config.file.clone(),
+            after_file: config.after_file,
+            about: config.about().unwrap(),
+            stop_word_list: config.stop_words().unwrap(),
+            ..Default::default()
+        };
+        controller.dispatch("init", "".to_string());
+        for file in config.sources().expect("No file provided.") {
+            controller.dispatch("load", file.to_string());
+        }
+        controller.dispatch("run", "".to_string());
+    }
 }
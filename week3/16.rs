@@ -9,10 +9,11 @@
 //! - Entities post event subscriptions (aka 'wanted') to the bulletin
 //!   board and publish events (aka 'offered') to the bulletin board. the
 //!   bulletin board does all the event management and distribution
+//!
 //! Possible names:
 //! - Bulletin board
 //! - Publish-Subscribe
-
+//!
 //!  ====================== README ==================
 //! Please know that this program will cause memory leakage and shall only be used as an exercise
 //! for the publish-subscribe programming style.
@@ -31,15 +32,20 @@
 //! The cost is that this code will be harder to read - especially for those who are not
 //! familiar with the Rust language.
 
+// Handlers subscribe themselves in `new` and hand back the shared pointer.
+#![allow(clippy::new_ret_no_self)]
+
 use std::{
     cell::{Cell, RefCell},
-    cmp::Reverse,
-    collections::{HashMap, HashSet},
-    fs::File,
-    io::{BufRead, BufReader, Read},
+    collections::HashMap,
+    io::BufRead,
     panic,
     rc::Rc,
 };
+use tf_core::{
+    ngrams::Window, output::Note, Config, Format, FrequencyTable, Input, Mapping, Page, Ranking,
+    Report, Stemmer, StopWords, Tokenizer,
+};
 
 // ================ core logic ===============
 
@@ -52,17 +58,16 @@ enum EventKind {
     ValidWord,
     Eof,
     Print,
+    RankedWords,
+    Stat,
+    Flush,
 }
 #[derive(Debug, Clone)]
 enum Event {
     /// run the application
-    Run {
-        filename: String,
-    },
-    /// load the file (both the text file and the stop words)
-    Load {
-        filename: String,
-    },
+    Run { filenames: Vec<String> },
+    /// load a file (both the text file and the stop words)
+    Load { filename: String },
     /// start counting
     Start,
     /// count word
@@ -71,7 +76,21 @@ enum Event {
     ValidWord(String),
     /// process & counting finished
     Eof,
+    /// ask every counter to report its result
     Print,
+    /// every counted word, ranked, and the number of counted words
+    RankedWords {
+        entries: Vec<(String, usize)>,
+        total: usize,
+    },
+    /// an extra statistic
+    Stat {
+        key: &'static str,
+        label: &'static str,
+        value: usize,
+    },
+    /// everything has been reported, write it out
+    Flush,
 }
 impl Event {
     pub fn kind(&self) -> EventKind {
@@ -83,6 +102,9 @@ impl Event {
             Event::ValidWord(_) => EventKind::ValidWord,
             Event::Eof => EventKind::Eof,
             Event::Print => EventKind::Print,
+            Event::RankedWords { .. } => EventKind::RankedWords,
+            Event::Stat { .. } => EventKind::Stat,
+            Event::Flush => EventKind::Flush,
         }
     }
 }
@@ -134,11 +156,16 @@ impl Application {
 impl EventHandler for Application {
     fn handle(&self, event: Event) {
         match event {
-            Event::Run { filename } => {
-                self.manager.borrow().publish(Event::Load { filename });
+            Event::Run { filenames } => {
+                for filename in filenames {
+                    self.manager.borrow().publish(Event::Load { filename });
+                }
                 self.manager.borrow().publish(Event::Start);
             }
-            Event::Eof => self.manager.borrow().publish(Event::Print),
+            Event::Eof => {
+                self.manager.borrow().publish(Event::Print);
+                self.manager.borrow().publish(Event::Flush);
+            }
             _ => panic!("Unregistered event"),
         }
     }
@@ -147,12 +174,25 @@ impl EventHandler for Application {
 /// Stores the data (words) and publishes them
 struct DataStorage {
     manager: Rc<RefCell<EventManager>>,
+    tokenizer: Tokenizer,
+    /// replaces characters before stop-word filtering, with --map
+    mapping: Rc<Mapping>,
+    /// read Project Gutenberg files whole, with --keep-boilerplate
+    keep_boilerplate: bool,
     words: RefCell<Vec<String>>,
 }
 impl DataStorage {
-    pub fn new(manager: Rc<RefCell<EventManager>>) -> Rc<dyn EventHandler> {
+    pub fn new(
+        manager: Rc<RefCell<EventManager>>,
+        tokenizer: Tokenizer,
+        mapping: Rc<Mapping>,
+        keep_boilerplate: bool,
+    ) -> Rc<dyn EventHandler> {
         let me = Rc::new(Self {
             manager: manager.clone(),
+            tokenizer,
+            mapping,
+            keep_boilerplate,
             words: RefCell::new(vec![]),
         });
         manager.borrow_mut().subscribe(EventKind::Load, me.clone());
@@ -164,16 +204,13 @@ impl EventHandler for DataStorage {
     fn handle(&self, event: Event) {
         match event {
             Event::Load { filename } => {
-                let f = File::open(filename).expect("Failed to open file.");
-                let reader = BufReader::new(f);
+                let input: Input = filename.parse().expect("Invalid file name.");
+                let input = input.keeping_boilerplate(self.keep_boilerplate);
+                let reader = input.open().expect("Failed to open file.");
                 let mut words = self.words.borrow_mut();
                 for line in reader.lines() {
                     let line = line.unwrap();
-                    words.extend(
-                        line.split(|ch: char| !ch.is_ascii_alphabetic())
-                            .filter(|s| s.len() > 1)
-                            .map(|s| s.to_lowercase()),
-                    );
+                    words.extend(self.tokenizer.words(&line).map(|w| self.mapping.before(w)));
                 }
             }
             Event::Start => {
@@ -189,13 +226,27 @@ impl EventHandler for DataStorage {
 
 struct StopWordsFilter {
     manager: Rc<RefCell<EventManager>>,
-    stop_words: RefCell<HashSet<String>>,
+    /// the run, whose stop words are read on load; with auto those of its
+    /// inputs
+    config: Config,
+    stop_words: RefCell<StopWords>,
+    /// the last valid words, with --ngram valid n-grams are published
+    window: RefCell<Window>,
+    /// replaces characters after stop-word filtering, with --map
+    mapping: Rc<Mapping>,
 }
 impl StopWordsFilter {
-    pub fn new(manager: Rc<RefCell<EventManager>>) -> Rc<dyn EventHandler> {
+    pub fn new(
+        manager: Rc<RefCell<EventManager>>,
+        config: &Config,
+        mapping: Rc<Mapping>,
+    ) -> Rc<dyn EventHandler> {
         let me = Rc::new(Self {
             manager: manager.clone(),
-            stop_words: RefCell::new(HashSet::new()),
+            config: config.clone(),
+            stop_words: RefCell::new(StopWords::default()),
+            window: RefCell::new(config.ngrams.window()),
+            mapping,
         });
         manager.borrow_mut().subscribe(EventKind::Load, me.clone());
         manager.borrow_mut().subscribe(EventKind::Word, me.clone());
@@ -206,16 +257,16 @@ impl EventHandler for StopWordsFilter {
     fn handle(&self, event: Event) {
         match event {
             Event::Load { .. } => {
-                let f = File::open("../stop_words.txt").expect("failed to open stop words file.");
-                let mut reader = BufReader::new(f);
-                let mut line = String::new();
-                reader.read_to_string(&mut line).unwrap();
-                self.stop_words
-                    .borrow_mut()
-                    .extend(line.split(',').map(|s| s.to_string()));
+                *self.stop_words.borrow_mut() = self
+                    .config
+                    .stop_words()
+                    .expect("failed to read stop words.");
             }
             Event::Word(word) => {
-                if !self.stop_words.borrow().contains(&word) {
+                if self.stop_words.borrow().contains(&word) {
+                    self.window.borrow_mut().stop();
+                } else if let Some(ngram) = self.window.borrow_mut().push(word) {
+                    let word = self.mapping.after(ngram);
                     self.manager.borrow().publish(Event::ValidWord(word));
                 }
             }
@@ -225,12 +276,23 @@ impl EventHandler for StopWordsFilter {
 }
 
 struct WordCounter {
-    counter: RefCell<HashMap<String, usize>>,
+    manager: Rc<RefCell<EventManager>>,
+    ranking: Ranking,
+    /// words are counted by their stem, with --stem
+    stemmer: Stemmer,
+    counter: RefCell<FrequencyTable>,
 }
 impl WordCounter {
-    pub fn new(manager: Rc<RefCell<EventManager>>) -> Rc<dyn EventHandler> {
+    pub fn new(
+        manager: Rc<RefCell<EventManager>>,
+        ranking: Ranking,
+        stemmer: Stemmer,
+    ) -> Rc<dyn EventHandler> {
         let me = Rc::new(Self {
-            counter: RefCell::new(HashMap::new()),
+            manager: manager.clone(),
+            ranking,
+            stemmer,
+            counter: RefCell::new(FrequencyTable::new()),
         });
         manager
             .borrow_mut()
@@ -243,20 +305,14 @@ impl EventHandler for WordCounter {
     fn handle(&self, event: Event) {
         match event {
             Event::ValidWord(word) => {
-                *self.counter.borrow_mut().entry(word).or_default() += 1;
+                self.counter.borrow_mut().add(self.stemmer.stem(word));
             }
             Event::Print => {
-                let mut entries: Vec<(String, usize)> = self
-                    .counter
-                    .borrow()
-                    .iter()
-                    .map(|(w, t)| (w.to_string(), *t))
-                    .collect();
-                entries.sort_unstable_by_key(|(_w, t)| Reverse(*t));
-                entries.truncate(25);
-                for (index, (word, times)) in entries.into_iter().enumerate() {
-                    println!("[{:>2}] {:>20} - {:>4}", index + 1, word, times);
-                }
+                let counter = self.stemmer.relabel(&self.counter.borrow());
+                self.manager.borrow().publish(Event::RankedWords {
+                    entries: self.ranking.rank(&counter),
+                    total: counter.total(),
+                });
             }
             _ => panic!("Unregistered event"),
         }
@@ -264,11 +320,13 @@ impl EventHandler for WordCounter {
 }
 
 struct ZWordHolic {
+    manager: Rc<RefCell<EventManager>>,
     zwords_count: Cell<usize>,
 }
 impl ZWordHolic {
     pub fn new(manager: Rc<RefCell<EventManager>>) -> Rc<dyn EventHandler> {
         let me = Rc::new(Self {
+            manager: manager.clone(),
             zwords_count: Cell::new(0),
         });
         manager
@@ -287,10 +345,70 @@ impl EventHandler for ZWordHolic {
                 }
             }
             Event::Print => {
-                println!(
-                    "Number of non-stop words with z: {}",
-                    self.zwords_count.get()
-                );
+                self.manager.borrow().publish(Event::Stat {
+                    key: "z_words",
+                    label: "Number of non-stop words with z",
+                    value: self.zwords_count.get(),
+                });
+            }
+            _ => panic!("Unregistered event"),
+        }
+    }
+}
+
+/// Collects whatever is reported and prints it in one go.
+struct ReportPrinter {
+    format: Format,
+    ranking: Ranking,
+    page: Page,
+    file: Option<String>,
+    after_file: bool,
+    about: Vec<Note>,
+    ranked_words: RefCell<(Vec<(String, usize)>, usize)>,
+    stats: RefCell<Vec<(&'static str, &'static str, usize)>>,
+}
+impl ReportPrinter {
+    pub fn new(manager: Rc<RefCell<EventManager>>, config: &Config) -> Rc<dyn EventHandler> {
+        let me = Rc::new(Self {
+            format: config.format,
+            ranking: config.ranking,
+            page: config.page,
+            file: config.file.clone(),
+            after_file: config.after_file,
+            about: config.about().expect("Failed to read file."),
+            ranked_words: RefCell::new((vec![], 0)),
+            stats: RefCell::new(vec![]),
+        });
+        manager
+            .borrow_mut()
+            .subscribe(EventKind::RankedWords, me.clone());
+        manager.borrow_mut().subscribe(EventKind::Stat, me.clone());
+        manager.borrow_mut().subscribe(EventKind::Flush, me.clone());
+        me
+    }
+}
+impl EventHandler for ReportPrinter {
+    fn handle(&self, event: Event) {
+        match event {
+            Event::RankedWords { entries, total } => {
+                *self.ranked_words.borrow_mut() = (entries, total);
+            }
+            Event::Stat { key, label, value } => {
+                self.stats.borrow_mut().push((key, label, value));
+            }
+            Event::Flush => {
+                let (entries, total) = self.ranked_words.replace((vec![], 0));
+                let mut report = Report::new(entries, total)
+                    .ranked()
+                    .ranked_by(self.ranking)
+                    .paged(&self.page)
+                    .of_file(self.file.clone())
+                    .after_file(self.after_file)
+                    .with_notes(self.about.clone());
+                for (key, label, value) in self.stats.borrow_mut().drain(..) {
+                    report.add_stat(key, label, value);
+                }
+                report.print(self.format);
             }
             _ => panic!("Unregistered event"),
         }
@@ -300,14 +418,31 @@ impl EventHandler for ZWordHolic {
 // ================= main ====================
 
 fn main() {
-    let event_manager = Rc::new(RefCell::new(EventManager::default()));
-    let _data_storage = DataStorage::new(event_manager.clone());
-    let _application = Application::new(event_manager.clone());
-    let _stopwords_filter = StopWordsFilter::new(event_manager.clone());
-    let _word_counter = WordCounter::new(event_manager.clone());
-    let _z_word_holic = ZWordHolic::new(event_manager.clone());
+    // counts from scratch: the words reach the counter only as events on the
+    // board, nothing is there to take a finished table from the cache
+    let config = Config::from_env()
+        .and_then(Config::uncached)
+        .expect("Usage: ./16 [options] <file>...");
+    // with --per-file: each input alone, then all of them together
+    for config in config.runs().expect("Usage: ./16 <file>...") {
+        let event_manager = Rc::new(RefCell::new(EventManager::default()));
+        let mapping = Rc::new(config.mapping().expect("failed to read character mapping."));
+        let _data_storage = DataStorage::new(
+            event_manager.clone(),
+            config.tokenizer.clone(),
+            mapping.clone(),
+            config.keep_boilerplate,
+        );
+        let _application = Application::new(event_manager.clone());
+        let _stopwords_filter = StopWordsFilter::new(event_manager.clone(), &config, mapping);
+        let _word_counter =
+            WordCounter::new(event_manager.clone(), config.ranking, config.stemmer());
+        let _z_word_holic = ZWordHolic::new(event_manager.clone());
+        let _report_printer = ReportPrinter::new(event_manager.clone(), &config);
 
-    event_manager.borrow().publish(Event::Run {
-        filename: std::env::args().skip(1).next().expect("Usage: ./16 <file>"),
-    });
+        let sources = config.sources().expect("Usage: ./16 <file>...");
+        event_manager.borrow().publish(Event::Run {
+            filenames: sources.iter().map(|s| s.to_string()).collect(),
+        });
+    }
 }
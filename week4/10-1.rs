@@ -1,8 +1,6 @@
-use std::{
-    cmp::Reverse,
-    collections::{HashMap, HashSet},
-    fs::File,
-    io::{BufReader, Read},
+use tf_core::{
+    output::Note, Config, FrequencyTable, Input, Mapping, Ranking, Report, Stemmer, StopWords,
+    Tokenizer,
 };
 
 struct TheOne<T> {
@@ -26,71 +24,104 @@ impl TheOne<String> {
     }
 }
 
-fn read_file(path: String) -> String {
-    let mut f = BufReader::new(File::open(path).unwrap());
-    let mut s = String::new();
-    f.read_to_string(&mut s).unwrap();
-    s
+fn read_files(inputs: Vec<Input>) -> String {
+    Input::read_all(&inputs).unwrap()
 }
 
-fn filter_chars(s: String) -> String {
-    s.chars()
-        .into_iter()
-        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { ' ' })
-        .collect()
+fn filter_chars(s: String, tokenizer: &Tokenizer) -> String {
+    tokenizer.blank_non_words(&s)
 }
 
 fn normalize(s: String) -> String {
     s.to_lowercase()
 }
 
-fn scan(s: String) -> Vec<String> {
-    s.split(' ').map(|s| s.to_string()).collect()
+/// contractions and possessives are handled with --contractions and
+/// --strip-possessives
+fn scan(s: String, tokenizer: &Tokenizer) -> Vec<String> {
+    s.split(' ')
+        .flat_map(|s| tokenizer.normalize(s.to_string()))
+        .filter(|s| tokenizer.accepts(s))
+        .collect()
+}
+
+/// replace characters with --map, `map` is the step before or after
+/// `remove_stop_words`
+fn map_chars(words: Vec<String>, map: impl Fn(String) -> String) -> Vec<String> {
+    words.into_iter().map(map).collect()
 }
 
-fn remove_stop_words(words: Vec<String>) -> Vec<String> {
-    let mut f = BufReader::new(File::open("../stop_words.txt").unwrap());
-    let mut buf = String::new();
-    f.read_to_string(&mut buf).unwrap();
-    let stop_words: HashSet<String> = buf.split(',').map(|s| s.to_string()).collect();
-    words
-        .into_iter()
-        .filter(|w| w.len() > 1 && !stop_words.contains(w))
+/// also joins the words into n-grams, with --ngram
+fn remove_stop_words(words: Vec<String>, config: &Config, stop_words: &StopWords) -> Vec<String> {
+    config
+        .ngrams
+        .of(words, |w| stop_words.contains(w))
         .collect()
 }
 
-fn frequencies(words: Vec<String>) -> HashMap<String, usize> {
-    let mut ret = HashMap::new();
-    for w in words {
-        *ret.entry(w).or_default() += 1;
-    }
-    ret
+fn stem(words: Vec<String>, stemmer: &Stemmer) -> Vec<String> {
+    words.into_iter().map(|w| stemmer.stem(w)).collect()
 }
 
-fn sort(freq: HashMap<String, usize>) -> Vec<(String, usize)> {
-    let mut ret: Vec<_> = freq.into_iter().collect();
-    ret.sort_unstable_by_key(|(_s, t)| Reverse(*t));
-    ret
+fn frequencies(words: Vec<String>) -> FrequencyTable {
+    words.into_iter().collect()
 }
 
-fn top25_freq(mut entries: Vec<(String, usize)>) -> String {
-    let mut ret = String::new();
-    entries.truncate(25);
-    for (word, count) in entries {
-        ret.push_str(&format!("{} - {}\n", word, count));
-    }
-    ret
+/// the frequencies of the inputs, or with --cache those of the same inputs
+/// counted before with the same options
+fn count(
+    inputs: Vec<Input>,
+    config: &Config,
+    mapping: &Mapping,
+    stemmer: &Stemmer,
+) -> FrequencyTable {
+    let stop_words = config.stop_words().unwrap();
+    config
+        .counted(&inputs, &stop_words, mapping, || {
+            Ok(TheOne::new(inputs.clone())
+                .bind(read_files)
+                .bind(|s| filter_chars(s, &config.tokenizer))
+                .bind(normalize)
+                .bind(|s| scan(s, &config.tokenizer))
+                .bind(|words| map_chars(words, |w| mapping.before(w)))
+                .bind(|words| remove_stop_words(words, config, &stop_words))
+                .bind(|words| map_chars(words, |w| mapping.after(w)))
+                .bind(|words| stem(words, stemmer))
+                .bind(frequencies)
+                .value)
+        })
+        .unwrap()
+}
+
+fn surface_forms(freq: FrequencyTable, stemmer: &Stemmer) -> FrequencyTable {
+    stemmer.relabel(&freq)
+}
+
+fn sort(freq: FrequencyTable, ranking: Ranking) -> Report {
+    Report::new(ranking.rank(&freq), freq.total()).ranked_by(ranking)
+}
+
+fn top_freq(report: Report, config: &Config, about: Vec<Note>) -> String {
+    report
+        .paged(&config.page)
+        .of_file(config.file.clone())
+        .after_file(config.after_file)
+        .with_notes(about)
+        .render(config.format)
 }
 
 fn main() {
-    TheOne::new(std::env::args().nth(1).unwrap())
-        .bind(read_file)
-        .bind(filter_chars)
-        .bind(normalize)
-        .bind(scan)
-        .bind(remove_stop_words)
-        .bind(frequencies)
-        .bind(sort)
-        .bind(top25_freq)
-        .printme();
+    let config = Config::from_env().unwrap();
+    // with --per-file: each input alone, then all of them together
+    for config in config.runs().unwrap() {
+        let stemmer = config.stemmer();
+        let mapping = config.mapping().unwrap();
+        let about = config.about().unwrap();
+        TheOne::new(config.sources().unwrap())
+            .bind(|inputs| count(inputs, &config, &mapping, &stemmer))
+            .bind(|freq| surface_forms(freq, &stemmer))
+            .bind(|freq| sort(freq, config.ranking))
+            .bind(|report| top_freq(report, &config, about))
+            .printme();
+    }
 }
@@ -14,13 +14,12 @@
 
 use rusqlite::{params, Connection};
 use std::{
-    collections::HashSet,
-    env,
     error::Error,
-    fs::{remove_file, File},
-    io::{BufRead, BufReader},
-    path::{Path, PathBuf},
+    fs::remove_file,
+    io::{BufRead, ErrorKind},
+    path::PathBuf,
 };
+use tf_core::{output::Note, Config, Input, Ranking, Report, Stemmer, Stemming};
 
 type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
@@ -43,119 +42,212 @@ fn create_db_schema(conn: &Connection) -> Result<()> {
     CREATE TABLE `words` (
         id      INTEGER PRIMARY KEY AUTOINCREMENT,
         doc_id  INTEGER,
-        value   TEXT
+        value   TEXT,
+        surface TEXT
     ); "#,
     )?;
     Ok(())
 }
 
-fn get_stop_words() -> Result<HashSet<String>> {
-    let reader = BufReader::new(File::open("../stop_words.txt")?);
-    let mut ret = HashSet::new();
-    reader.lines().filter_map(|l| l.ok()).for_each(|l| {
-        l.split(',').for_each(|l| {
-            ret.insert(l.to_string());
-        })
+/// `(value, surface)` pairs: the word as counted, i.e. its stem with --stem,
+/// and the word as found in the text.
+fn get_words(input: &Input, config: &Config) -> Result<impl Iterator<Item = (String, String)>> {
+    // with auto, the list of this document's language
+    let stop_words = config.alone(input).stop_words()?;
+    let tokenizer = config.tokenizer.clone();
+    let ngrams = config.ngrams;
+    let mapping = config.mapping()?;
+    let after = mapping.clone();
+    // the database finds the surface forms, see `label`
+    let stemmer = Stemmer::new(match config.stemming {
+        Stemming::Off => Stemming::Off,
+        _ => Stemming::Stems,
     });
 
-    Ok(ret)
-}
-
-fn get_words(path: &Path) -> Result<impl Iterator<Item = String>> {
-    let stop_words = get_stop_words()?;
-
-    let f = File::open(path)?;
-    let reader = BufReader::new(f);
+    let reader = input.open()?;
     let words = reader
         .lines()
-        .filter_map(|l| l.ok())
-        .flat_map(move |l| {
-            l.split(|ch: char| !ch.is_ascii_alphanumeric())
-                .map(|s| s.to_lowercase())
-                .collect::<Vec<_>>()
-                .into_iter()
+        // skip a line that is not valid utf8, stop at any other error
+        .map_while(|l| match l {
+            Ok(line) => Some(Some(line)),
+            Err(e) if e.kind() == ErrorKind::InvalidData => Some(None),
+            Err(_) => None,
         })
-        .filter(move |s| s.len() > 1 && !stop_words.contains(s));
+        .flatten()
+        .flat_map(move |l| tokenizer.words(&l).collect::<Vec<_>>().into_iter())
+        .map(move |s| mapping.before(s));
+    // each input is a document of its own: n-grams don't run across inputs
+    let words = ngrams
+        .of(words, move |s| stop_words.contains(s))
+        .map(move |s| after.after(s))
+        .map(move |s| (stemmer.stem(s.clone()), s));
     Ok(words)
 }
 
-fn save_filename_to_db(path: &Path, conn: &Connection) -> Result<u32> {
-    // add document
+fn save_filename_to_db(name: &str, conn: &Connection) -> Result<u32> {
+    // add document, the same file may be given twice so don't look it up by name
     conn.execute(
         r#"INSERT INTO `documents` (name) VALUES (?)"#,
-        params![path.to_str()],
+        params![name],
     )?;
-    let mut stmt = conn.prepare(
-        r#"
-        SELECT id FROM documents WHERE name = ?;
-        "#,
-    )?;
-    let doc_id = stmt.query_row(params![path.to_str()], |row| {
-        let id: u32 = row.get(0)?;
-        Ok(id)
-    })?;
-    Ok(doc_id)
+    Ok(conn.last_insert_rowid() as u32)
 }
 
-fn load_file(path: &Path, doc_id: u32, conn: &mut Connection) -> Result<u32> {
+fn load_file(input: &Input, config: &Config, doc_id: u32, conn: &mut Connection) -> Result<u32> {
     // insert words
-    let words = get_words(&path)?;
+    let words = get_words(input, config)?;
     // use transaction to accelerate insert
     let trans = conn.transaction()?;
     {
         let mut insert_stmt = trans.prepare_cached(
             r"
-            INSERT INTO words (doc_id, value)
+            INSERT INTO words (doc_id, value, surface)
             VALUES
-            (?, ?);",
+            (?, ?, ?);",
         )?;
-        for word in words {
-            insert_stmt.execute(params![doc_id, word])?;
+        for (value, surface) in words {
+            insert_stmt.execute(params![doc_id, value, surface])?;
         }
     }
     trans.commit()?;
     Ok(doc_id)
 }
 
-fn print_stats(doc_id: u32, conn: &Connection) -> Result<()> {
-    let mut stmt = conn.prepare(
+/// The `ORDER BY` clause matching `ranking`.
+fn order_by(ranking: Ranking) -> &'static str {
+    match ranking {
+        Ranking::Count | Ranking::Relative => "cnt DESC, label ASC",
+        Ranking::Alphabetical => "label ASC",
+        Ranking::FirstOccurrence => "first_id ASC",
+        Ranking::Length => "LENGTH(label) DESC, cnt DESC, label ASC",
+    }
+}
+
+/// What each group of words is reported as: the value, or with
+/// --surface-forms the surface the value was found as most often.
+fn label(stemming: Stemming, doc_ids: &str) -> String {
+    match stemming {
+        Stemming::SurfaceForms => format!(
+            r#"(
+            SELECT surface FROM words s
+            WHERE s.value = w.value AND s.doc_id IN ({})
+            GROUP BY surface
+            ORDER BY COUNT(*) DESC, surface ASC
+            LIMIT 1
+        )"#,
+            doc_ids
+        ),
+        _ => "value".to_string(),
+    }
+}
+
+/// Statistics over the words of the given documents together.
+fn print_stats(
+    doc_ids: &[u32],
+    file: Option<String>,
+    after_file: bool,
+    about: Vec<Note>,
+    conn: &Connection,
+    config: &Config,
+) -> Result<()> {
+    // ids are numbers, safe to put in the query as they are
+    let doc_ids: Vec<String> = doc_ids.iter().map(|id| id.to_string()).collect();
+    let doc_ids = doc_ids.join(", ");
+    let mut stmt = conn.prepare(&format!(
         r#"
-        SELECT value, COUNT(*) AS cnt
-        FROM words
-        WHERE doc_id = ?
-        GROUP BY value
-        ORDER BY cnt DESC
-        LIMIT 25;
+        SELECT label, cnt FROM (
+            SELECT {} AS label, COUNT(*) AS cnt, MIN(id) AS first_id
+            FROM words w
+            WHERE doc_id IN ({})
+            GROUP BY value
+            HAVING cnt >= ?
+        )
+        ORDER BY {}
+        LIMIT ? OFFSET ?;
     "#,
-    )?;
+        label(config.stemming, &doc_ids),
+        doc_ids,
+        order_by(config.ranking)
+    ))?;
+    // a negative limit means no limit
+    let limit = config.page.top.map_or(-1, |n| n as i64);
     struct Item {
         word: String,
         count: u32,
     }
     let rows = stmt
-        .query_map(params![doc_id], |row| {
-            Ok(Item {
-                word: row.get(0)?,
-                count: row.get(1)?,
-            })
-        })?
+        .query_map(
+            params![
+                config.page.min_count as i64,
+                limit,
+                config.page.offset as i64
+            ],
+            |row| {
+                Ok(Item {
+                    word: row.get(0)?,
+                    count: row.get(1)?,
+                })
+            },
+        )?
         .collect::<Vec<_>>();
+    let mut entries = Vec::with_capacity(rows.len());
     for row in rows {
         let row = row?;
-        println!("{} - {}", row.word, row.count);
+        entries.push((row.word, row.count as usize));
     }
 
+    let total: u32 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM words WHERE doc_id IN ({});", doc_ids),
+        params![],
+        |row| row.get(0),
+    )?;
+
     // print number of unique words with 'z'
-    let mut stmt = conn.prepare(
+    let mut stmt = conn.prepare(&format!(
         r#"
         SELECT COUNT(*) FROM (
             SELECT value, COUNT(*) FROM words
-            WHERE value like '%z%'
+            WHERE doc_id IN ({}) AND value like '%z%'
             GROUP BY value
         ); "#,
-    )?;
+        doc_ids
+    ))?;
     let r: u32 = stmt.query_row(params![], |row| row.get(0))?;
-    println!("Count of unique words with z: {}", r);
+
+    // how often words of each length were counted, for the histogram of
+    // --format chart; over all of them, not just the page
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT LENGTH(label), SUM(cnt) FROM (
+            SELECT {} AS label, COUNT(*) AS cnt
+            FROM words w
+            WHERE doc_id IN ({})
+            GROUP BY value
+        )
+        GROUP BY 1
+        ORDER BY 1;
+    "#,
+        label(config.stemming, &doc_ids),
+        doc_ids
+    ))?;
+    let lengths = stmt
+        .query_map(params![], |row| {
+            Ok((
+                row.get::<_, u32>(0)? as usize,
+                row.get::<_, u32>(1)? as usize,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Report::new(entries, total as usize)
+        .ranked_by(config.ranking)
+        .starting_at(config.page.first_rank())
+        .with_lengths(lengths)
+        .with_stat("unique_z_words", "Count of unique words with z", r as usize)
+        .of_file(file)
+        .after_file(after_file)
+        .with_notes(about)
+        .print(config.format);
 
     Ok(())
 }
@@ -164,14 +256,37 @@ fn main() -> Result<()> {
     let mut conn = get_connection()?;
     create_db_schema(&conn)?;
 
-    let path: PathBuf = env::args()
-        .nth(1)
-        .expect("Usage: 26-1 <path-to-file>")
-        .into();
+    // counts from scratch: the counts are a query over the stored words, so
+    // the words must go into the tables every time
+    let config = Config::from_env()?.uncached()?;
+    if config.inputs.is_empty() {
+        panic!("Usage: 26-1 [options] <path-to-file>...");
+    }
 
-    let doc_id = save_filename_to_db(&path, &conn)?;
-    load_file(&path, doc_id, &mut conn)?;
-    print_stats(doc_id, &conn)?;
+    // one document per input
+    let inputs = config.sources()?;
+    let mut doc_ids = vec![];
+    for input in &inputs {
+        let doc_id = save_filename_to_db(&input.to_string(), &conn)?;
+        load_file(input, &config, doc_id, &mut conn)?;
+        doc_ids.push(doc_id);
+    }
+    if config.per_file {
+        for (i, (input, doc_id)) in inputs.iter().zip(&doc_ids).enumerate() {
+            let file = Some(input.to_string());
+            let about = config.alone(input).about()?;
+            print_stats(&[*doc_id], file, i > 0, about, &conn, &config)?;
+        }
+    }
+    // after the reports of each input, with --per-file
+    print_stats(
+        &doc_ids,
+        None,
+        config.per_file,
+        config.about()?,
+        &conn,
+        &config,
+    )?;
 
     Ok(())
 }
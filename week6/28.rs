@@ -3,6 +3,7 @@
 //! Constraints:
 //! - Data comes to functions in streams, rather than as a complete whole all at at once
 //! - Functions are filters / transformers from one kind of data stream to another
+//!
 //! Possible names:
 //! - Lazy rivers
 //! - Data streams
@@ -10,63 +11,103 @@
 //! - Data generators
 //!
 
-use std::{
-    cmp::Reverse,
-    collections::{HashMap, HashSet},
-    env,
-    error::Error,
-    fs::File,
-    io::{BufRead, BufReader, Read},
-    path::{Path, PathBuf},
+use std::{cell::RefCell, io, io::BufRead, rc::Rc};
+use tf_core::{
+    Config, FrequencyTable, Input, Mapping, Report, Result, Stemmer, StopWords, Tokenizer,
 };
 
-type Result<T> = std::result::Result<T, Box<dyn Error>>;
+/// Where a stream leaves the error that ended it, e.g. a line that is not
+/// UTF-8, for whoever drains it to report.
+type Failure = Rc<RefCell<Option<io::Error>>>;
 
-fn all_lines(filename: &Path) -> Result<impl Iterator<Item = String>> {
-    let reader = BufReader::new(File::open(filename)?);
+fn all_lines(inputs: &[Input], failure: Failure) -> Result<impl Iterator<Item = String>> {
+    let reader = Input::open_all(inputs)?;
 
     let words = reader //
         .lines()
-        .filter_map(|l| l.ok());
+        .map_while(move |l| l.map_err(|e| *failure.borrow_mut() = Some(e)).ok());
     Ok(words)
 }
 
-fn all_words(filename: &Path) -> Result<impl Iterator<Item = String>> {
-    let lines = all_lines(filename)?;
+fn all_words(
+    inputs: &[Input],
+    tokenizer: Tokenizer,
+    failure: Failure,
+) -> Result<impl Iterator<Item = String>> {
+    let lines = all_lines(inputs, failure)?;
     let iter = lines //
-        .flat_map(move |l| {
-            l.split(|ch: char| !ch.is_ascii_alphanumeric())
-                .map(|s| s.to_lowercase())
-                .collect::<Vec<_>>()
-                .into_iter()
-        });
+        .flat_map(move |l| tokenizer.words(&l).collect::<Vec<_>>().into_iter());
     Ok(iter)
 }
 
-fn non_stop_words(filename: &Path) -> Result<impl Iterator<Item = String>> {
-    let mut buf = String::new();
-    File::open("../stop_words.txt")?.read_to_string(&mut buf)?;
-    let stop_words: HashSet<String> = buf.split(',').map(|s| s.to_string()).collect();
+fn non_stop_words(
+    inputs: &[Input],
+    config: &Config,
+    stop_words: Rc<StopWords>,
+    mapping: Rc<Mapping>,
+    stemmer: Rc<Stemmer>,
+    failure: Failure,
+) -> Result<impl Iterator<Item = String>> {
+    let after = mapping.clone();
 
-    let iter = all_words(filename)?;
-    let iter = iter.filter(move |s| s.len() > 1 && !stop_words.contains(s));
+    let iter =
+        all_words(inputs, config.tokenizer.clone(), failure)?.map(move |s| mapping.before(s));
+    // with --ngram, yields n-grams of the words that are not stop words
+    let iter = config
+        .ngrams
+        .of(iter, move |s| stop_words.contains(s))
+        .map(move |s| after.after(s))
+        .map(move |s| stemmer.stem(s));
     Ok(iter)
 }
 
-fn count_and_sort(filename: &Path) -> Result<impl Iterator<Item = (String, usize)>> {
-    let mut count = HashMap::new();
-    for w in non_stop_words(filename)? {
-        *count.entry(w).or_default() += 1;
-    }
-    let mut entries: Vec<(String, usize)> = count.into_iter().collect();
-    entries.sort_by_key(|(_w, c)| Reverse(*c));
-    Ok(entries.into_iter())
+fn count_and_sort(
+    inputs: &[Input],
+    config: &Config,
+) -> Result<(usize, impl Iterator<Item = (String, usize)>)> {
+    let stemmer = Rc::new(config.stemmer());
+    let stop_words = Rc::new(config.stop_words()?);
+    let mapping = Rc::new(config.mapping()?);
+    // with --cache, no stream flows for inputs counted before with the same
+    // options
+    let count = config.counted(inputs, &stop_words, &mapping, || {
+        let failure = Failure::default();
+        let words = non_stop_words(
+            inputs,
+            config,
+            stop_words.clone(),
+            mapping.clone(),
+            stemmer.clone(),
+            failure.clone(),
+        )?;
+        let mut count = FrequencyTable::new();
+        for w in words {
+            count.add(w);
+        }
+        match failure.take() {
+            Some(e) => Err(e.into()),
+            None => Ok(count),
+        }
+    })?;
+    let count = stemmer.relabel(&count);
+    Ok((count.total(), config.ranking.rank(&count).into_iter()))
 }
 
 fn main() -> Result<()> {
-    let filename: PathBuf = env::args().nth(1).expect("Usage: ./28 <path>").into();
-    for (w, c) in count_and_sort(&filename)?.take(25) {
-        println!("{} - {}", w, c);
+    let config = Config::from_env()?;
+    if config.inputs.is_empty() {
+        panic!("Usage: ./28 [options] <path>...");
+    }
+    // with --per-file: each input alone, then all of them together
+    for config in config.runs()? {
+        let (total, sorted) = count_and_sort(&config.sources()?, &config)?;
+        Report::new(sorted.collect(), total)
+            .ranked_by(config.ranking)
+            .paged(&config.page)
+            .of_file(config.file.clone())
+            .after_file(config.after_file)
+            .with_notes(config.about()?)
+            .print(config.format);
     }
     Ok(())
 }
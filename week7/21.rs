@@ -14,84 +14,160 @@
 //! - Shit happens, life goes on
 
 use std::{
-    cmp::Reverse,
-    collections::{HashMap, HashSet},
-    env,
-    fs::File,
-    io::{BufRead, BufReader, Read},
-    path::{Path, PathBuf},
+    io::{BufRead, ErrorKind},
+    path::PathBuf,
 };
+use tf_core::{Config, FrequencyTable, Input, Mapping, Ranking, Report, Stemmer, StopWords};
 
-fn extract_words(path_to_file: &Path) -> Vec<String> {
-    // check if file exist, if not, open default file
-    let f = File::open(path_to_file).or_else(|_e| File::open("../pride-and-prejudice.txt"));
-    // if file still fails to open, return empty result
-    let f = match f {
-        Ok(f) => f,
-        Err(e) => {
-            eprintln!("failed to open file: {}", e);
-            return vec![];
-        }
-    };
-    let reader = BufReader::new(f);
-    let words: Vec<_> = reader
-        .lines()
-        // check whether is valid utf8 string, or else skip this line
-        .filter_map(|l| l.ok())
-        .flat_map(move |line| {
-            line.split(|ch: char| !ch.is_ascii_alphanumeric())
-                .map(|s| s.to_lowercase())
-                .collect::<Vec<_>>()
-                .into_iter()
-        })
-        .collect();
+const DEFAULT_FILE: &str = "../pride-and-prejudice.txt";
+
+fn get_inputs(config: &Config) -> Vec<Input> {
+    // if no input is given or found, use the default file
+    config.sources().unwrap_or_else(|e| {
+        eprintln!("no input, using {}: {}", DEFAULT_FILE, e);
+        vec![Input::File(PathBuf::from(DEFAULT_FILE))]
+    })
+}
+
+fn extract_words(inputs: &[Input], config: &Config) -> Vec<String> {
+    let tokenizer = config.tokenizer.clone();
+    let mut words = vec![];
+    let mut opened = 0;
+    for input in inputs {
+        // skip the files that fail to open
+        let reader = match input.open() {
+            Ok(reader) => reader,
+            Err(e) => {
+                eprintln!("failed to open file: {}", e);
+                continue;
+            }
+        };
+        opened += 1;
+        words.extend(
+            reader
+                .lines()
+                // check whether is valid utf8 string, or else skip this line;
+                // stop at any other error, reading on would fail again
+                .map_while(|l| match l {
+                    Ok(line) => Some(Some(line)),
+                    Err(e) if e.kind() == ErrorKind::InvalidData => {
+                        eprintln!("skipping a line: {}", e);
+                        Some(None)
+                    }
+                    Err(e) => {
+                        eprintln!("failed to read file: {}", e);
+                        None
+                    }
+                })
+                .flatten()
+                .flat_map(|line| tokenizer.words(&line).collect::<Vec<_>>()),
+        );
+    }
+    // if none could be opened, try the default file
+    let default = [Input::File(PathBuf::from(DEFAULT_FILE))];
+    if opened == 0 && inputs != default {
+        return extract_words(&default, config);
+    }
     words
 }
 
-fn remove_stop_words(words: Vec<String>) -> Vec<String> {
-    let stop_words_str = File::open("../stop_words.txt")
-        .and_then(|f| {
-            let mut reader = BufReader::new(f);
-            let mut buf = String::new();
-            // fail-fast
-            reader.read_to_string(&mut buf)?;
-            Ok(buf)
-        })
-        .unwrap_or_default();
-    let stop_words_set: HashSet<_> = stop_words_str.split(',').map(|s| s.to_string()).collect();
+fn get_mapping(config: &Config) -> Mapping {
+    // if the tables fail to load, map nothing
+    config.mapping().unwrap_or_else(|e| {
+        eprintln!("failed to load character mapping: {}", e);
+        Default::default()
+    })
+}
 
-    let non_stop_words = words
-        .into_iter()
-        .filter(move |w| w.len() > 1 && !stop_words_set.contains(w))
-        .collect::<Vec<_>>();
-    non_stop_words
+/// `map` is the step before or after `remove_stop_words`
+fn map_chars(words: Vec<String>, map: impl Fn(String) -> String) -> Vec<String> {
+    words.into_iter().map(map).collect()
 }
 
-fn get_freq(words: Vec<String>) -> HashMap<String, usize> {
-    let mut freq = HashMap::new();
-    for w in words {
-        *freq.entry(w).or_default() += 1;
-    }
-    freq
+fn get_stop_words(config: &Config) -> StopWords {
+    // if stop words fail to load, filter nothing
+    config.stop_words().unwrap_or_else(|e| {
+        eprintln!("failed to load stop words: {}", e);
+        Default::default()
+    })
+}
+
+fn remove_stop_words(words: Vec<String>, config: &Config, stop_words: &StopWords) -> Vec<String> {
+    // with --ngram, the words left are joined into n-grams
+    config
+        .ngrams
+        .of(words, |w| stop_words.contains(w))
+        .collect()
+}
+
+fn stem_words(words: Vec<String>, stemmer: &Stemmer) -> Vec<String> {
+    words.into_iter().map(|w| stemmer.stem(w)).collect()
+}
+
+fn get_freq(words: Vec<String>) -> FrequencyTable {
+    words.into_iter().collect()
+}
+
+fn count_words(
+    config: &Config,
+    inputs: &[Input],
+    stop_words: &StopWords,
+    mapping: &Mapping,
+    stemmer: &Stemmer,
+) -> FrequencyTable {
+    let words = extract_words(inputs, config);
+    let words = map_chars(words, |w| mapping.before(w));
+    let non_stop_words = remove_stop_words(words, config, stop_words);
+    let non_stop_words = map_chars(non_stop_words, |w| mapping.after(w));
+    let words = stem_words(non_stop_words, stemmer);
+    get_freq(words)
+}
+
+fn get_counts(
+    config: &Config,
+    inputs: &[Input],
+    stop_words: &StopWords,
+    mapping: &Mapping,
+    stemmer: &Stemmer,
+) -> FrequencyTable {
+    let count = || Ok(count_words(config, inputs, stop_words, mapping, stemmer));
+    // if the cache can't be read or written, count from scratch
+    config
+        .counted(inputs, stop_words, mapping, count)
+        .unwrap_or_else(|e| {
+            eprintln!("{}, counting without the cache", e);
+            count_words(config, inputs, stop_words, mapping, stemmer)
+        })
 }
 
-fn sort_freq(freq: HashMap<String, usize>) -> Vec<(String, usize)> {
-    let mut entries: Vec<_> = freq.into_iter().collect();
-    entries.sort_unstable_by_key(|(_w, t)| Reverse(*t));
-    entries
+fn sort_freq(freq: FrequencyTable, ranking: Ranking) -> Report {
+    Report::new(ranking.rank(&freq), freq.total()).ranked_by(ranking)
 }
 
 fn main() {
-    let filename: PathBuf = env::args()
-        .nth(1)
-        .unwrap_or_else(|| "../pride-and-prejudice.txt".to_string())
-        .into();
-    let words = extract_words(&filename);
-    let non_stop_words = remove_stop_words(words);
-    let freq = get_freq(non_stop_words);
-    let mut freq = sort_freq(freq);
-    freq.truncate(25);
-    for (w, t) in freq {
-        println!("{} - {}", w, t);
+    // ignore malformed arguments and go on with the defaults
+    let config = Config::from_env().unwrap_or_default();
+    // with --per-file: each input alone, then all of them together
+    let runs = config.runs().unwrap_or_else(|e| {
+        eprintln!("failed to find inputs: {}", e);
+        vec![Config {
+            per_file: false,
+            ..config.clone()
+        }]
+    });
+    for config in runs {
+        let inputs = get_inputs(&config);
+        let stop_words = get_stop_words(&config);
+        let mapping = get_mapping(&config);
+        let stemmer = config.stemmer();
+        let counts = get_counts(&config, &inputs, &stop_words, &mapping, &stemmer);
+        let freq = stemmer.relabel(&counts);
+        let report = sort_freq(freq, config.ranking);
+        report
+            .paged(&config.page)
+            .of_file(config.file.clone())
+            .after_file(config.after_file)
+            .with_notes(config.about().unwrap_or_default())
+            .print(config.format);
     }
 }
@@ -13,74 +13,91 @@
 //! - Design by contract
 //! - Mental breakdown
 
-use std::{
-    cmp::Reverse,
-    collections::{HashMap, HashSet},
-    env,
-    fs::File,
-    io::{BufRead, BufReader, Read},
-    path::{Path, PathBuf},
-};
+use std::io::BufRead;
+use tf_core::{Config, FrequencyTable, Input, Mapping, Ranking, Report, Stemmer, StopWords};
 
-fn extract_words(path_to_file: &Path) -> Vec<String> {
-    let f = File::open(path_to_file).expect("failed to open file");
-    let reader = BufReader::new(f);
-    let words: Vec<_> = reader
+fn extract_words(inputs: &[Input], config: &Config) -> Vec<String> {
+    let reader = Input::open_all(inputs).expect("failed to open file");
+    let tokenizer = config.tokenizer.clone();
+    reader
         .lines()
         // check whether is valid utf8 string
         .map(|l| l.expect("invalid line"))
-        .flat_map(move |line| {
-            line.split(|ch: char| !ch.is_ascii_alphanumeric())
-                .map(|s| s.to_lowercase())
-                .collect::<Vec<_>>()
-                .into_iter()
-        })
-        .collect();
-    words
+        .flat_map(move |line| tokenizer.words(&line).collect::<Vec<_>>().into_iter())
+        .collect()
 }
 
-fn remove_stop_words(words: Vec<String>) -> Vec<String> {
-    let f = File::open("../stop_words.txt").expect("failed to open stop_words.txt");
-    let stop_words_str = {
-        let mut reader = BufReader::new(f);
-        let mut buf = String::new();
-        // fail-fast
-        reader
-            .read_to_string(&mut buf)
-            .expect("failed to read from file");
-        buf
-    };
-    let stop_words_set: HashSet<_> = stop_words_str.split(',').map(|s| s.to_string()).collect();
+fn get_mapping(config: &Config) -> Mapping {
+    // fail-fast
+    config.mapping().expect("failed to read character mapping")
+}
 
-    let non_stop_words = words
-        .into_iter()
-        .filter(move |w| w.len() > 1 && !stop_words_set.contains(w))
-        .collect::<Vec<_>>();
-    non_stop_words
+/// `map` is the step before or after `remove_stop_words`
+fn map_chars(words: Vec<String>, map: impl Fn(String) -> String) -> Vec<String> {
+    words.into_iter().map(map).collect()
 }
 
-fn get_freq(words: Vec<String>) -> HashMap<String, usize> {
-    let mut freq = HashMap::new();
-    for w in words {
-        *freq.entry(w).or_default() += 1;
-    }
-    freq
+fn get_stop_words(config: &Config) -> StopWords {
+    // fail-fast
+    config.stop_words().expect("failed to read stop words")
+}
+
+fn remove_stop_words(words: Vec<String>, config: &Config, stop_words: &StopWords) -> Vec<String> {
+    // with --ngram, the words left are joined into n-grams
+    config
+        .ngrams
+        .of(words, |w| stop_words.contains(w))
+        .collect()
+}
+
+fn stem_words(words: Vec<String>, stemmer: &Stemmer) -> Vec<String> {
+    words.into_iter().map(|w| stemmer.stem(w)).collect()
+}
+
+fn get_freq(words: Vec<String>) -> FrequencyTable {
+    words.into_iter().collect()
+}
+
+fn count_words(
+    config: &Config,
+    inputs: &[Input],
+    stop_words: &StopWords,
+    mapping: &Mapping,
+    stemmer: &Stemmer,
+) -> FrequencyTable {
+    let count = || {
+        let words = extract_words(inputs, config);
+        let words = map_chars(words, |w| mapping.before(w));
+        let non_stop_words = remove_stop_words(words, config, stop_words);
+        let non_stop_words = map_chars(non_stop_words, |w| mapping.after(w));
+        let words = stem_words(non_stop_words, stemmer);
+        Ok(get_freq(words))
+    };
+    config
+        .counted(inputs, stop_words, mapping, count)
+        .expect("failed to use the cache")
 }
 
-fn sort_freq(freq: HashMap<String, usize>) -> Vec<(String, usize)> {
-    let mut entries: Vec<_> = freq.into_iter().collect();
-    entries.sort_unstable_by_key(|(_w, t)| Reverse(*t));
-    entries
+fn sort_freq(freq: FrequencyTable, ranking: Ranking) -> Report {
+    Report::new(ranking.rank(&freq), freq.total()).ranked_by(ranking)
 }
 
 fn main() {
-    let filename: PathBuf = env::args().nth(1).expect("Usage: ./22 <path>").into();
-    let words = extract_words(&filename);
-    let non_stop_words = remove_stop_words(words);
-    let freq = get_freq(non_stop_words);
-    let mut freq = sort_freq(freq);
-    freq.truncate(25);
-    for (w, t) in freq {
-        println!("{} - {}", w, t);
+    let config = Config::from_env().expect("invalid arguments");
+    // with --per-file: each input alone, then all of them together
+    for config in config.runs().expect("failed to find inputs") {
+        let inputs = config.sources().expect("Usage: ./22 [options] <path>...");
+        let stop_words = get_stop_words(&config);
+        let mapping = get_mapping(&config);
+        let stemmer = config.stemmer();
+        let counts = count_words(&config, &inputs, &stop_words, &mapping, &stemmer);
+        let freq = stemmer.relabel(&counts);
+        let report = sort_freq(freq, config.ranking);
+        report
+            .paged(&config.page)
+            .of_file(config.file.clone())
+            .after_file(config.after_file)
+            .with_notes(config.about().expect("failed to read inputs"))
+            .print(config.format);
     }
 }
@@ -6,21 +6,19 @@
 //! - All IO actions must be contained in computation sequences that are
 //!   clearly separated from the pure functions
 //! - All sequences that have IO must be called from the main program
+//!
 //! Possible names:
 //! - Quarantine
 //! - Monadic IO
 //! - Imperative functional style
 //!
 
-use std::{
-    cmp::Reverse,
-    collections::{HashMap, HashSet},
-    fs::File,
-    io::{BufReader, Error as IOError, ErrorKind, Read},
+use std::rc::Rc;
+use tf_core::{
+    output::Note, Config, FrequencyTable, Input, Mapping, Ranking, Report, Result, Stemmer,
+    StopWords, Tokenizer,
 };
 
-type Result<T> = std::result::Result<T, IOError>;
-
 // =========================  core implementation =======================
 
 /// The higher-order function that actually does IO.
@@ -80,86 +78,141 @@ impl TheOne<String> {
 // functions that include IO have signature that returns Func<>.
 
 /// include IO
-fn get_filepath(_: ()) -> Func<String> {
-    Func::new(move || match std::env::args().nth(1) {
-        Some(s) => Ok(s),
-        None => Err(ErrorKind::InvalidInput.into()),
-    })
+fn get_config(_: ()) -> Func<Config> {
+    Func::new(Config::from_env)
 }
 
 /// include IO
-fn read_file(path: String) -> Func<String> {
-    Func::new(move || {
-        let mut f = BufReader::new(File::open(path)?);
-        let mut s = String::new();
-        f.read_to_string(&mut s)?;
-        Ok(s)
-    })
+fn get_runs(config: Config) -> Func<Vec<Config>> {
+    Func::new(move || config.runs())
 }
 
-fn filter_chars(s: String) -> String {
-    s.chars()
-        .into_iter()
-        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { ' ' })
-        .collect()
+/// include IO
+fn get_inputs(config: Config) -> Func<Vec<Input>> {
+    Func::new(move || config.sources())
+}
+
+/// include IO
+fn get_mapping(config: Config) -> Func<Mapping> {
+    Func::new(move || config.mapping())
+}
+
+/// include IO
+fn get_stop_words(config: Config) -> Func<StopWords> {
+    Func::new(move || config.stop_words())
+}
+
+/// include IO
+fn get_about(config: Config) -> Func<Vec<Note>> {
+    Func::new(move || config.about())
+}
+
+fn filter_chars(s: String, tokenizer: &Tokenizer) -> String {
+    tokenizer.blank_non_words(&s)
 }
 
 fn normalize(s: String) -> String {
     s.to_lowercase()
 }
 
-fn scan(s: String) -> Vec<String> {
-    s.split(' ').map(|s| s.to_string()).collect()
+/// contractions and possessives are handled with --contractions and
+/// --strip-possessives
+fn scan(s: String, tokenizer: &Tokenizer) -> Vec<String> {
+    s.split(' ')
+        .flat_map(|s| tokenizer.normalize(s.to_string()))
+        .filter(|s| tokenizer.accepts(s))
+        .collect()
+}
+
+/// replace characters with --map, `map` is the step before or after
+/// `remove_stop_words`
+fn map_chars(words: Vec<String>, map: impl Fn(String) -> String) -> Vec<String> {
+    words.into_iter().map(map).collect()
 }
 
-/// include IO
-fn remove_stop_words(words: Vec<String>) -> Func<Vec<String>> {
+fn remove_stop_words(words: Vec<String>, config: &Config, stop_words: &StopWords) -> Vec<String> {
+    // with --ngram, the words left are joined into n-grams
+    config
+        .ngrams
+        .of(words, |w| stop_words.contains(w))
+        .collect()
+}
+
+fn stem(words: Vec<String>, stemmer: &Stemmer) -> Vec<String> {
+    words.into_iter().map(|w| stemmer.stem(w)).collect()
+}
+
+fn frequencies(words: Vec<String>) -> FrequencyTable {
+    words.into_iter().collect()
+}
+
+fn count_words(
+    text: String,
+    config: &Config,
+    stop_words: &StopWords,
+    mapping: &Mapping,
+    stemmer: &Stemmer,
+) -> FrequencyTable {
+    TheOne::new(text)
+        .bind(|s| filter_chars(s, &config.tokenizer))
+        .bind(normalize)
+        .bind(|s| scan(s, &config.tokenizer))
+        .bind(|words| map_chars(words, |w| mapping.before(w)))
+        .bind(|words| remove_stop_words(words, config, stop_words))
+        .bind(|words| map_chars(words, |w| mapping.after(w)))
+        .bind(|words| stem(words, stemmer))
+        .bind(frequencies)
+        .value
+}
+
+/// include IO: reads the inputs, or with --cache their counts from before
+fn get_counts(
+    config: Config,
+    inputs: Vec<Input>,
+    stop_words: StopWords,
+    mapping: Mapping,
+    stemmer: Rc<Stemmer>,
+) -> Func<FrequencyTable> {
     Func::new(move || {
-        let mut f = BufReader::new(File::open("../stop_words.txt")?);
-        let mut buf = String::new();
-        f.read_to_string(&mut buf)?;
-        let stop_words: HashSet<String> = buf.split(',').map(|s| s.to_string()).collect();
-        let ret = words
-            .into_iter()
-            .filter(|w| w.len() > 1 && !stop_words.contains(w))
-            .collect();
-        Ok(ret)
+        config.counted(&inputs, &stop_words, &mapping, || {
+            let text = Input::read_all(&inputs)?;
+            Ok(count_words(text, &config, &stop_words, &mapping, &stemmer))
+        })
     })
 }
 
-fn frequencies(words: Vec<String>) -> HashMap<String, usize> {
-    let mut ret = HashMap::new();
-    for w in words {
-        *ret.entry(w).or_default() += 1;
-    }
-    ret
+fn surface_forms(freq: FrequencyTable, stemmer: &Stemmer) -> FrequencyTable {
+    stemmer.relabel(&freq)
 }
 
-fn sort(freq: HashMap<String, usize>) -> Vec<(String, usize)> {
-    let mut ret: Vec<_> = freq.into_iter().collect();
-    ret.sort_unstable_by_key(|(_s, t)| Reverse(*t));
-    ret
+fn sort(freq: FrequencyTable, ranking: Ranking) -> Report {
+    Report::new(ranking.rank(&freq), freq.total()).ranked_by(ranking)
 }
 
-fn top25_freq(mut entries: Vec<(String, usize)>) -> String {
-    let mut ret = String::new();
-    entries.truncate(25);
-    for (word, count) in entries {
-        ret.push_str(&format!("{} - {}\n", word, count));
-    }
-    ret
+fn top_freq(report: Report, config: &Config, about: Vec<Note>) -> String {
+    report
+        .paged(&config.page)
+        .of_file(config.file.clone())
+        .after_file(config.after_file)
+        .with_notes(about)
+        .render(config.format)
 }
 
 fn main() {
-    TheOne::new(())
-        .bind(get_filepath)
-        .bind(read_file)
-        .bind(filter_chars)
-        .bind(normalize)
-        .bind(scan)
-        .bind(remove_stop_words)
-        .bind(frequencies)
-        .bind(sort)
-        .bind(top25_freq)
-        .printme();
+    let config: Config = get_config(()).my_unwrap();
+    let runs: Vec<Config> = get_runs(config).my_unwrap();
+    // with --per-file: each input alone, then all of them together
+    for config in runs {
+        let stemmer = Rc::new(config.stemmer());
+        let mapping: Mapping = get_mapping(config.clone()).my_unwrap();
+        let stop_words: StopWords = get_stop_words(config.clone()).my_unwrap();
+        let about: Vec<Note> = get_about(config.clone()).my_unwrap();
+        TheOne::new(config.clone())
+            .bind(get_inputs)
+            .bind(|inputs| get_counts(config.clone(), inputs, stop_words, mapping, stemmer.clone()))
+            .bind(|freq| surface_forms(freq, &stemmer))
+            .bind(|freq| sort(freq, config.ranking))
+            .bind(|report| top_freq(report, &config, about))
+            .printme();
+    }
 }
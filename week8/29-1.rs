@@ -11,31 +11,28 @@
 //!   ability to receive messages via the queue
 //! - Each 'thing' has its own thread of execution independent of the
 //!   others.
+//!
 //! Possible names:
 //! - Free agents
 //! - Active letterbox
 //! - Actors
 //!
 //!
-//! data flow:  -init-> controller -init->          data
-//!                                -init->          stop_words
+//! data flow:  -init-> controller -init->          stop_words
+//!             -load-> controller -load->          data
 //!             -run--> controller -run->           words
 //!                     words      -filter->        stop_words
 //!                     stop_words -word ->         counter
 //!                     words      -finish->        stop_words
-//!                     stop_words -top25->         counter
+//!                     stop_words -top->           counter
 //!             -end--> controller -end->           data
 //!                                -end->           stop_words
 //!                                -end->           counter
 
 use std::{
-    cmp::Reverse,
-    collections::{HashMap, HashSet},
     default::Default,
-    env,
     error::Error,
-    fs::File,
-    io::{BufRead, BufReader},
+    io::BufRead,
     panic,
     sync::{
         mpsc::{channel, Receiver, Sender},
@@ -43,16 +40,20 @@ use std::{
     },
     thread::{self, JoinHandle},
 };
+use tf_core::{
+    ngrams::Window, output::Note, Config, Format, FrequencyTable, Input, Mapping, Page, Ranking,
+    Report, Stemmer, StopWords, Tokenizer,
+};
 
-/// This code is a bit long to read. But the main idea is that there are four components,
-/// `WordFrequencyController`, `DataStorageManager`, `StopWordsManager` and `WordFrequencyManager`.
-/// To quickly go through the logic, see their implementation of method `dispatch` in trait
-/// implementation of trait `Letterbox`.
+// This code is a bit long to read. But the main idea is that there are four components,
+// `WordFrequencyController`, `DataStorageManager`, `StopWordsManager` and `WordFrequencyManager`.
+// To quickly go through the logic, see their implementation of method `dispatch` in trait
+// implementation of trait `Letterbox`.
 
-/// ====   core abstraction ============
-/// Each letter box has a sender that can be provided to outer world and receive message through
-/// the corresponding receiver. The sender and receiver are two side of a queue but thread safe and
-/// support multiple senders.
+// ====   core abstraction ============
+// Each letter box has a sender that can be provided to outer world and receive message through
+// the corresponding receiver. The sender and receiver are two side of a queue but thread safe and
+// support multiple senders.
 
 /// The `MsgSender` and `MsgReceiver` send & receive messages. Messages are composed of
 /// a command name and a payload.
@@ -104,6 +105,7 @@ struct WordFrequencyController {
     chan: Channel<String>,
     data_letterbox: MsgSender<String>,
     stop_words_letterbox: MsgSender<String>,
+    #[allow(dead_code)]
     counter_letterbox: MsgSender<String>,
 }
 impl WordFrequencyController {
@@ -126,12 +128,14 @@ impl Letterbox for WordFrequencyController {
         let file = message;
         match command {
             "init" => {
-                // have data and stop_words to init as well.
-                self.data_letterbox.send(("init", file)).unwrap();
+                // have stop_words to init as well.
                 self.stop_words_letterbox
                     .send(("init", "".to_string()))
                     .unwrap();
             }
+            "load" => {
+                self.data_letterbox.send(("load", file)).unwrap();
+            }
             "run" => {
                 // tell data to start processing data.
                 self.data_letterbox.send(("run", "".to_string())).unwrap();
@@ -140,7 +144,7 @@ impl Letterbox for WordFrequencyController {
                 self.data_letterbox.send(("end", "".to_string())).unwrap();
             }
             _ => {
-                panic!(format!("unknown command: {}", command));
+                panic!("unknown command: {}", command);
             }
         }
     }
@@ -158,14 +162,27 @@ impl Letterbox for WordFrequencyController {
 #[derive(Debug)]
 struct DataStorageManager {
     chan: Channel<String>,
-    /// The words from file
+    tokenizer: Tokenizer,
+    /// replaces characters before stop-word filtering, with --map
+    mapping: Mapping,
+    /// read Project Gutenberg files whole, with --keep-boilerplate
+    keep_boilerplate: bool,
+    /// The words from the files
     words: Mutex<Vec<String>>,
     stop_words_letterbox: MsgSender<String>,
 }
 impl DataStorageManager {
-    pub fn new(stop_words_letterbox: MsgSender<String>) -> Self {
+    pub fn new(
+        tokenizer: Tokenizer,
+        mapping: Mapping,
+        keep_boilerplate: bool,
+        stop_words_letterbox: MsgSender<String>,
+    ) -> Self {
         Self {
             chan: Channel::default(),
+            tokenizer,
+            mapping,
+            keep_boilerplate,
             words: Default::default(),
             stop_words_letterbox,
         }
@@ -182,19 +199,15 @@ impl Letterbox for DataStorageManager {
     fn dispatch(&self, command: &'static str, message: String) {
         let file = message;
         match command {
-            "init" => {
-                let f = File::open(file).unwrap();
-                let reader = BufReader::new(f);
-                let mut words = Vec::new();
+            "load" => {
+                let input: Input = file.parse().unwrap();
+                let input = input.keeping_boilerplate(self.keep_boilerplate);
+                let reader = input.open().unwrap();
+                let mut words = self.words.lock().unwrap();
                 for line in reader.lines() {
                     let line = line.unwrap();
-                    words.extend(
-                        line.split(|ch: char| !ch.is_ascii_alphabetic())
-                            .filter(|w| w.len() > 1)
-                            .map(|s| s.to_lowercase()),
-                    );
+                    words.extend(self.tokenizer.words(&line).map(|w| self.mapping.before(w)));
                 }
-                *self.words.lock().unwrap() = words;
             }
             "run" => {
                 for word in self.words.lock().unwrap().drain(..) {
@@ -210,7 +223,7 @@ impl Letterbox for DataStorageManager {
                     .unwrap();
             }
             _ => {
-                panic!(format!("unknown command: {}", command));
+                panic!("unknown command: {}", command);
             }
         }
     }
@@ -219,14 +232,24 @@ impl Letterbox for DataStorageManager {
 #[derive(Debug)]
 struct StopWordsManager {
     chan: Channel<String>,
-    stop_words: RwLock<HashSet<String>>,
+    /// the run, whose stop words are read on init; with auto those of its
+    /// inputs
+    config: Config,
+    stop_words: RwLock<StopWords>,
+    /// the last words, with --ngram the counter gets n-grams
+    window: Mutex<Window>,
+    /// replaces characters after stop-word filtering, with --map
+    mapping: Mapping,
     counter_letterbox: MsgSender<String>,
 }
 impl StopWordsManager {
-    pub fn new(counter_letterbox: MsgSender<String>) -> Self {
+    pub fn new(config: &Config, mapping: Mapping, counter_letterbox: MsgSender<String>) -> Self {
         Self {
             chan: Channel::default(),
+            config: config.clone(),
             stop_words: Default::default(),
+            window: Mutex::new(config.ngrams.window()),
+            mapping,
             counter_letterbox,
         }
     }
@@ -243,22 +266,20 @@ impl Letterbox for StopWordsManager {
         let word = message;
         match command {
             "init" => {
-                let f = File::open("../stop_words.txt").unwrap();
-                let reader = BufReader::new(f);
-                let mut stop_words = HashSet::new();
-                for line in reader.lines() {
-                    stop_words.extend(line.unwrap().split(',').map(|s| s.to_string()));
-                }
-                *self.stop_words.write().unwrap() = stop_words;
+                *self.stop_words.write().unwrap() = self.config.stop_words().unwrap();
             }
             "filter" => {
-                if !self.stop_words.read().unwrap().contains(&word) {
+                let mut window = self.window.lock().unwrap();
+                if self.stop_words.read().unwrap().contains(&word) {
+                    window.stop();
+                } else if let Some(ngram) = window.push(word) {
+                    let word = self.mapping.after(ngram);
                     self.counter_letterbox.send(("word", word)).unwrap();
                 }
             }
             "finish" => {
                 self.counter_letterbox
-                    .send(("top25", "".to_string()))
+                    .send(("top", "".to_string()))
                     .unwrap();
             }
             "end" => {
@@ -267,7 +288,7 @@ impl Letterbox for StopWordsManager {
                     .unwrap();
             }
             _ => {
-                panic!(format!("unknown command: {}", command));
+                panic!("unknown command: {}", command);
             }
         }
     }
@@ -276,7 +297,14 @@ impl Letterbox for StopWordsManager {
 #[derive(Debug, Default)]
 struct WordFrequencyManager {
     chan: Channel<String>,
-    counter: Mutex<HashMap<String, usize>>,
+    counter: Mutex<FrequencyTable>,
+    stemmer: Stemmer,
+    format: Format,
+    ranking: Ranking,
+    page: Page,
+    file: Option<String>,
+    after_file: bool,
+    about: Vec<Note>,
 }
 impl Letterbox for WordFrequencyManager {
     type Input = String;
@@ -289,39 +317,64 @@ impl Letterbox for WordFrequencyManager {
     fn dispatch(&self, command: &'static str, message: Self::Input) {
         match command {
             "word" => {
-                *self.counter.lock().unwrap().entry(message).or_default() += 1;
+                self.counter.lock().unwrap().add(self.stemmer.stem(message));
             }
-            "top25" => {
-                let mut entries: Vec<(String, usize)> = self
-                    .counter
-                    .lock()
-                    .unwrap()
-                    .iter()
-                    .map(|(w, c)| (w.clone(), *c))
-                    .collect();
-                entries.sort_unstable_by_key(|en| Reverse(en.1));
-                entries.truncate(25);
-                for (s, times) in entries {
-                    println!("{} - {}", s, times);
-                }
+            "top" => {
+                let counter = self.stemmer.relabel(&self.counter.lock().unwrap());
+                Report::new(self.ranking.rank(&counter), counter.total())
+                    .ranked_by(self.ranking)
+                    .paged(&self.page)
+                    .of_file(self.file.clone())
+                    .after_file(self.after_file)
+                    .with_notes(self.about.clone())
+                    .print(self.format);
             }
             "end" => {}
             _ => {
-                panic!(format!("unknown command: {}", command));
+                panic!("unknown command: {}", command);
             }
         }
     }
 }
 fn main() -> Result<(), Box<dyn Error>> {
-    let counter = WordFrequencyManager::default();
+    // counts from scratch: the counts are the state of an actor on its own
+    // thread, filled one message at a time
+    let config = Config::from_env()?.uncached()?;
+    if config.inputs.is_empty() {
+        panic!("No file provided. Usage: ./29 [options] <path>...");
+    }
+    // with --per-file: each input alone, then all of them together
+    for config in config.runs()? {
+        word_frequencies(&config)?;
+    }
+    Ok(())
+}
+
+fn word_frequencies(config: &Config) -> Result<(), Box<dyn Error>> {
+    let counter = WordFrequencyManager {
+        stemmer: config.stemmer(),
+        format: config.format,
+        ranking: config.ranking,
+        page: config.page,
+        file: config.file.clone(),
+        after_file: config.after_file,
+        about: config.about()?,
+        ..Default::default()
+    };
+    let mapping = config.mapping()?;
     let counter_letterbox = counter.letterbox();
     let counter_handler = counter.run();
 
-    let stop_words = StopWordsManager::new(counter_letterbox.clone());
+    let stop_words = StopWordsManager::new(config, mapping.clone(), counter_letterbox.clone());
     let stop_words_letterbox = stop_words.letterbox();
     let stop_words_handler = stop_words.run();
 
-    let data = DataStorageManager::new(stop_words_letterbox.clone());
+    let data = DataStorageManager::new(
+        config.tokenizer.clone(),
+        mapping,
+        config.keep_boilerplate,
+        stop_words_letterbox.clone(),
+    );
     let data_letterbox = data.letterbox();
     let data_handler = data.run();
 
@@ -333,12 +386,10 @@ fn main() -> Result<(), Box<dyn Error>> {
     let controller_letterbox = controller.letterbox();
     let controller_handler = controller.run();
 
-    controller_letterbox.send((
-        "init",
-        env::args()
-            .nth(1)
-            .expect("No file provided. Usage: ./29 <path>"),
-    ))?;
+    controller_letterbox.send(("init", "".to_string()))?;
+    for input in config.sources()? {
+        controller_letterbox.send(("load", input.to_string()))?;
+    }
     controller_letterbox.send(("run", "".to_string()))?;
     controller_letterbox.send(("end", "".to_string()))?;
 
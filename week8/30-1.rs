@@ -12,37 +12,73 @@
 //!
 
 use std::{
-    cmp::Reverse,
-    collections::{HashMap, HashSet, VecDeque},
+    collections::VecDeque,
     error::Error,
-    fs::File,
-    io::{BufRead, BufReader, Read},
+    io::BufRead,
     sync::{Arc, Mutex},
     thread,
 };
+use tf_core::{Config, FrequencyTable, Input, Mapping, Report, Stemmer, StopWords};
 
 type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
 fn main() -> Result<()> {
+    let config = Config::from_env()?;
+    let mapping = config.mapping()?;
+
+    // with --per-file: each input alone, then all of them together
+    for config in config.runs()? {
+        // get stop_words, with auto those of this run's inputs
+        let stop_words = config.stop_words()?;
+        let sources = config.sources()?;
+        // shared by the workers, with --surface-forms it records what they stem
+        let stemmer = Arc::new(config.stemmer());
+        // with --cache, the spaces are left alone for inputs counted before
+        // with the same options
+        let freq = config.counted(&sources, &stop_words, &mapping, || {
+            word_frequencies(&config, &sources, &stop_words, &mapping, &stemmer)
+        })?;
+        let freq = stemmer.relabel(&freq);
+        // print the requested ranks
+        Report::new(config.ranking.rank(&freq), freq.total())
+            .ranked_by(config.ranking)
+            .paged(&config.page)
+            .of_file(config.file.clone())
+            .after_file(config.after_file)
+            .with_notes(config.about()?)
+            .print(config.format);
+    }
+    Ok(())
+}
+
+fn word_frequencies(
+    config: &Config,
+    sources: &[Input],
+    stop_words: &StopWords,
+    mapping: &Mapping,
+    stemmer: &Arc<Stemmer>,
+) -> tf_core::Result<FrequencyTable> {
     // public spaces lies in here
     let word_space = Arc::new(Mutex::new(VecDeque::new()));
     let freq_space = Arc::new(Mutex::new(VecDeque::new()));
 
-    // get stop_words
-    let stop_words: Arc<HashSet<String>> = {
-        let mut buf = String::new();
-        BufReader::new(File::open("../stop_words.txt")?).read_to_string(&mut buf)?;
-        Arc::new(buf.split(',').map(|s| s.to_string()).collect())
-    };
-
     // put words to word_space
-    let filepath = std::env::args().nth(1).unwrap();
-    for l in BufReader::new(File::open(filepath)?).lines() {
-        for w in l?
-            .split(|ch: char| !ch.is_ascii_alphanumeric())
-            .map(|s| s.to_lowercase())
-        {
-            word_space.lock().unwrap().push_back(w);
+    let tokenizer = &config.tokenizer;
+    // words are numbered so that the first occurrences survive the workers
+    let mut position = 0;
+    // n-grams need the words in order, so they are made here, leaving out
+    // the stop words, rather than by the workers
+    let mut window = config.ngrams.window();
+    for l in Input::open_all(sources)?.lines() {
+        for w in tokenizer.words(&l?) {
+            let w = mapping.before(w);
+            if stop_words.contains(&w) {
+                window.stop();
+            } else if let Some(ngram) = window.push(w) {
+                let ngram = mapping.after(ngram);
+                word_space.lock().unwrap().push_back((position, ngram));
+                position += 1;
+            }
         }
     }
 
@@ -51,17 +87,13 @@ fn main() -> Result<()> {
         .map(|_| {
             let word_space = word_space.clone();
             let freq_space = freq_space.clone();
-            let stop_words = stop_words.clone();
+            let stemmer = stemmer.clone();
             thread::spawn(move || {
-                let mut freq: HashMap<String, usize> = HashMap::new();
+                let mut freq = FrequencyTable::new();
                 loop {
                     let w = { word_space.lock().unwrap().pop_front() };
                     match w {
-                        Some(w) => {
-                            if w.len() > 1 && !stop_words.contains(&w) {
-                                *freq.entry(w).or_default() += 1;
-                            }
-                        }
+                        Some((position, w)) => freq.add_at(stemmer.stem(w), position),
                         None => break,
                     }
                 }
@@ -72,18 +104,9 @@ fn main() -> Result<()> {
     // wait all workers to stop
     workers.into_iter().for_each(|h| h.join().unwrap());
     // merge frequencies
-    let mut freq: HashMap<String, usize> = HashMap::new();
+    let mut freq = FrequencyTable::new();
     for f in freq_space.lock().unwrap().drain(..) {
-        for (k, v) in f {
-            *freq.entry(k).or_default() += v;
-        }
+        freq.merge_positioned(f);
     }
-    // print top 25
-    let mut items: Vec<(String, usize)> = freq.into_iter().collect();
-    items.sort_unstable_by_key(|(_word, t)| Reverse(*t));
-    items.truncate(25);
-    for (word, times) in items {
-        println!("{} - {}", word, times);
-    }
-    Ok(())
+    Ok(freq)
 }
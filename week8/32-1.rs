@@ -16,24 +16,13 @@
 //! - Double inverse multiplexer
 //!
 
-use std::{
-    cmp::Reverse,
-    collections::{HashMap, HashSet},
-    env,
-    error::Error,
-    fs::File,
-    io::{BufReader, Read},
-    path::{Path, PathBuf},
-};
+use std::{collections::HashMap, error::Error};
+use tf_core::{ngrams::Window, Config, Input, Mapping, Report, Stemmer, StopWords, Tokenizer};
 
 type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
-///
-fn read_file(f: &Path) -> Result<String> {
-    let mut r = BufReader::new(File::open(f)?);
-    let mut buf = String::new();
-    r.read_to_string(&mut buf)?;
-    Ok(buf)
+fn read_files(inputs: &[Input]) -> tf_core::Result<String> {
+    Input::read_all(inputs)
 }
 
 fn partition(s: String, chunk_size: usize) -> impl Iterator<Item = String> {
@@ -51,7 +40,7 @@ fn partition(s: String, chunk_size: usize) -> impl Iterator<Item = String> {
             chunk.push('\n');
         }
     }
-    if chunk.len() > 0 {
+    if !chunk.is_empty() {
         result.push(chunk);
     }
     result.into_iter()
@@ -60,18 +49,26 @@ fn partition(s: String, chunk_size: usize) -> impl Iterator<Item = String> {
 /// Takes a string, returns a list of pairs (word, 1),
 /// one for each word in the input, so
 /// [(w1, 1), (w2, 1), ..., (wn, 1)]
-fn split_words(s: String) -> Vec<(String, usize)> {
-    let stop_words: HashSet<String> = {
-        let mut r = BufReader::new(File::open("../stop_words.txt").unwrap());
-        let mut buf = String::new();
-        r.read_to_string(&mut buf).unwrap();
-        buf.split(',').map(|s| s.to_string()).collect()
-    };
-    s.split(|ch: char| !ch.is_ascii_alphanumeric())
-        .map(|s| s.to_lowercase())
-        .filter(|s| s.len() > 1 && !stop_words.contains(s))
-        .map(|s| (s, 1))
-        .collect()
+/// With --stem, the words are stems. With --ngram they are n-grams, which
+/// may start in the previous chunk: `window` holds its last words.
+fn split_words(
+    s: String,
+    tokenizer: &Tokenizer,
+    stop_words: &StopWords,
+    window: &mut Window,
+    mapping: &Mapping,
+    stemmer: &Stemmer,
+) -> Vec<(String, usize)> {
+    let mut result = vec![];
+    for word in tokenizer.words(&s) {
+        let word = mapping.before(word);
+        if stop_words.contains(&word) {
+            window.stop();
+        } else if let Some(ngram) = window.push(word) {
+            result.push((stemmer.stem(mapping.after(ngram)), 1));
+        }
+    }
+    result
 }
 
 /// Takes a list of lists of pairs of the form
@@ -79,20 +76,22 @@ fn split_words(s: String) -> Vec<(String, usize)> {
 /// [(w1, 1), (w2, 1), ..., (wn, 1)],
 /// ...]
 /// and returns a dictionary mapping each unique word to the
-/// corresponding list of pairs, so
-/// { w1 : [(w1, 1), (w1, 1)...],
-///  w2 : [(w2, 1), (w2, 1)...],
-///  ...}
+/// corresponding list of pairs, in the order the words first appear, so
+/// [ (w1, [(w1, 1), (w1, 1)...]),
+///   (w2, [(w2, 1), (w2, 1)...]),
+///  ...]
 fn regroup(
     groups: impl Iterator<Item = Vec<(String, usize)>>,
-) -> HashMap<String, Vec<(String, usize)>> {
-    let mut result: HashMap<String, Vec<(String, usize)>> = HashMap::new();
+) -> Vec<(String, Vec<(String, usize)>)> {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut result: Vec<(String, Vec<(String, usize)>)> = vec![];
     for group in groups {
         for (word, times) in group {
-            if result.contains_key(&word) {
-                result.get_mut(&word).unwrap().push((word, times));
+            if let Some(&i) = index.get(&word) {
+                result[i].1.push((word, times));
             } else {
-                result.insert(word.clone(), vec![(word, times)]);
+                index.insert(word.clone(), result.len());
+                result.push((word.clone(), vec![(word, times)]));
             }
         }
     }
@@ -107,15 +106,41 @@ fn count_words(item: (String, Vec<(String, usize)>)) -> (String, usize) {
 }
 
 fn main() -> Result<()> {
-    let f: PathBuf = env::args().nth(1).expect("Usage: ./32 <path>").into();
-    let mapped = partition(read_file(&f)?, 200).map(split_words);
-    let regrouped = regroup(mapped);
-
-    let mut sorted: Vec<(String, usize)> = regrouped.into_iter().map(count_words).collect();
-    sorted.sort_unstable_by_key(|(_w, times)| Reverse(*times));
-    sorted.truncate(25);
-    for (w, c) in sorted {
-        println!("{} - {}", w, c);
+    let config = Config::from_env()?;
+    let mapping = config.mapping()?;
+    if config.inputs.is_empty() {
+        panic!("Usage: ./32 [options] <path>...");
+    }
+    // with --per-file: each input alone, then all of them together
+    for config in config.runs()? {
+        let stop_words = config.stop_words()?;
+        let stemmer = config.stemmer();
+        let sources = config.sources()?;
+        // with --cache, inputs counted before with the same options are not
+        // mapped and reduced again
+        let counted = config.counted(&sources, &stop_words, &mapping, || {
+            let mut window = config.ngrams.window();
+            let mapped = partition(read_files(&sources)?, 200).map(|chunk| {
+                split_words(
+                    chunk,
+                    &config.tokenizer,
+                    &stop_words,
+                    &mut window,
+                    &mapping,
+                    &stemmer,
+                )
+            });
+            let regrouped = regroup(mapped);
+            Ok(regrouped.into_iter().map(count_words).collect())
+        })?;
+        let counted = stemmer.relabel(&counted);
+        Report::new(config.ranking.rank(&counted), counted.total())
+            .ranked_by(config.ranking)
+            .paged(&config.page)
+            .of_file(config.file.clone())
+            .after_file(config.after_file)
+            .with_notes(config.about()?)
+            .print(config.format);
     }
     Ok(())
 }